@@ -0,0 +1,16 @@
+-- Package ownership: every package name has one or more owners who may publish new versions.
+-- zorbs.owner_id keeps recording the user who published each individual version.
+CREATE TABLE IF NOT EXISTS zorb_owners (
+    package_name TEXT NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    added_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    PRIMARY KEY (package_name, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_zorb_owners_user_id ON zorb_owners(user_id);
+
+-- Backfill from versions that already recorded a publisher
+INSERT INTO zorb_owners (package_name, user_id)
+SELECT DISTINCT name, owner_id FROM zorbs WHERE owner_id IS NOT NULL
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,2 @@
+-- Yanked versions stay downloadable (existing lockfiles keep working) but are skipped by new resolutions
+ALTER TABLE zorbs ADD COLUMN IF NOT EXISTS yanked BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- SHA-256 (hex) of the published .zorb archive; NULL for versions published before checksums were recorded
+ALTER TABLE zorbs ADD COLUMN IF NOT EXISTS checksum TEXT;
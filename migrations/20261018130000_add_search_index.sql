@@ -0,0 +1,13 @@
+-- Full-text and fuzzy package search.
+-- Names are split on scope/word punctuation so '@data/serde' indexes as 'data' and 'serde'.
+-- README text is capped: to_tsvector rejects documents over 1 MB.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+ALTER TABLE zorbs ADD COLUMN IF NOT EXISTS search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', translate(name, '@/-_.', '     ')), 'A') ||
+    setweight(to_tsvector('english', COALESCE(description, '')), 'B') ||
+    setweight(to_tsvector('english', LEFT(COALESCE(readme, ''), 100000)), 'D')
+) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_zorbs_search_vector ON zorbs USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_zorbs_name_trgm ON zorbs USING GIN (name gin_trgm_ops);
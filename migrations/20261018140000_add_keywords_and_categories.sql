@@ -0,0 +1,22 @@
+-- Keywords and categories from [package], stored per version like the rest of the metadata.
+-- Categories are validated against the registry in src/categories.rs at publish time.
+ALTER TABLE zorbs ADD COLUMN IF NOT EXISTS keywords TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE zorbs ADD COLUMN IF NOT EXISTS categories TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_zorbs_categories ON zorbs USING GIN (categories);
+
+-- array_to_string is only STABLE, but over TEXT[] it is immutable, which generated columns require
+CREATE OR REPLACE FUNCTION zorbs_keywords_text(TEXT[]) RETURNS TEXT
+    LANGUAGE SQL IMMUTABLE PARALLEL SAFE
+    AS $$ SELECT array_to_string($1, ' ') $$;
+
+-- Rebuild the search vector with keywords weighted alongside the name
+DROP INDEX IF EXISTS idx_zorbs_search_vector;
+ALTER TABLE zorbs DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE zorbs ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', translate(name, '@/-_.', '     ')), 'A') ||
+    setweight(to_tsvector('simple', translate(zorbs_keywords_text(keywords), '-_', '  ')), 'A') ||
+    setweight(to_tsvector('english', COALESCE(description, '')), 'B') ||
+    setweight(to_tsvector('english', LEFT(COALESCE(readme, ''), 100000)), 'D')
+) STORED;
+CREATE INDEX idx_zorbs_search_vector ON zorbs USING GIN (search_vector);
@@ -0,0 +1,12 @@
+-- Downloads per version per UTC day. zorbs.downloads stays the all-time total;
+-- this table only has history from the day it was created.
+CREATE TABLE IF NOT EXISTS version_downloads (
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    date DATE NOT NULL,
+    downloads BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (name, version, date),
+    FOREIGN KEY (name, version) REFERENCES zorbs(name, version) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_version_downloads_date ON version_downloads(date);
@@ -0,0 +1,13 @@
+-- Archives were stored under a key derived from the package name, which distinct
+-- names could share; they are now stored under their SHA-256. Every version that
+-- predates the change is queued here until its blob has been moved.
+CREATE TABLE IF NOT EXISTS legacy_blobs (
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    PRIMARY KEY (name, version),
+    FOREIGN KEY (name, version) REFERENCES zorbs(name, version) ON DELETE CASCADE
+);
+
+INSERT INTO legacy_blobs (name, version)
+SELECT name, version FROM zorbs
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,12 @@
+-- Every regular file in each version's archive, recorded at publish. Versions
+-- published earlier have no rows until their listing is first requested, when it
+-- is read from the stored archive.
+CREATE TABLE IF NOT EXISTS version_files (
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    path TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    mode INTEGER NOT NULL,
+    PRIMARY KEY (name, version, path),
+    FOREIGN KEY (name, version) REFERENCES zorbs(name, version) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- The full validated zorb.toml of each version, normalized (see src/manifest.rs).
+-- NULL for versions published before manifests were recorded, or without one.
+ALTER TABLE zorbs ADD COLUMN manifest JSONB;
@@ -0,0 +1,288 @@
+// src/archive.rs — reading `.zorb` archives (gzipped tarballs) safely
+//
+// Every upload is walked once by [`inspect`] before anything else reads it. The
+// walk enforces the limits below, accepts only regular files and directories,
+// and rejects any path that could land outside the unpack directory or collide
+// with another entry on a case-insensitive filesystem.
+use crate::models::FileEntry;
+use flate2::read::GzDecoder;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+use std::rc::Rc;
+use tar::{Archive, EntryType};
+
+/// Bounds on what an archive may unpack to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Bytes of decompressed tar stream, headers and padding included.
+    pub max_unpacked_size: u64,
+    pub max_entries: usize,
+    /// Bytes in an entry's path.
+    pub max_path_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_unpacked_size: 256 * 1024 * 1024,
+            max_entries: 10_000,
+            max_path_len: 256,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveError {
+    /// Not a readable gzipped tarball; the detail comes from the decoder.
+    Corrupt(String),
+    TooLarge { limit: u64 },
+    TooManyEntries { limit: usize },
+    PathTooLong { path: String, limit: usize },
+    /// A path that is absolute, climbs out with `..`, or is otherwise unusable.
+    InvalidPath { path: String, reason: &'static str },
+    /// An entry that is neither a regular file nor a directory.
+    ForbiddenEntry { path: String, kind: &'static str },
+    Duplicate(String),
+    /// Two paths that differ only in case, so one would overwrite the other on
+    /// case-insensitive filesystems.
+    CaseCollision { first: String, second: String },
+    /// `path` needs `file` to be a directory.
+    FileConflict { file: String, path: String },
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Corrupt(detail) => write!(f, "Failed to read tar archive: {}", detail),
+            ArchiveError::TooLarge { limit } => {
+                write!(f, "Archive unpacks to more than {} MB", limit / 1024 / 1024)
+            }
+            ArchiveError::TooManyEntries { limit } => write!(f, "Archive has more than {} entries", limit),
+            ArchiveError::PathTooLong { path, limit } => {
+                write!(f, "Path '{}…' in archive is longer than {} bytes", truncate(path, 64), limit)
+            }
+            ArchiveError::InvalidPath { path, reason } => write!(f, "Path '{}' in archive {}", path, reason),
+            ArchiveError::ForbiddenEntry { path, kind } => {
+                write!(f, "'{}' in archive is a {}; only regular files and directories are allowed", path, kind)
+            }
+            ArchiveError::Duplicate(path) => write!(f, "'{}' appears more than once in archive", path),
+            ArchiveError::CaseCollision { first, second } => {
+                write!(f, "'{}' and '{}' in archive differ only in case", first, second)
+            }
+            ArchiveError::FileConflict { file, path } => {
+                write!(f, "'{}' in archive is inside '{}', which is a file", path, file)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+fn truncate(s: &str, max: usize) -> &str {
+    let mut end = max.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Decompressed bytes, failing once more than `remaining` have been read. The
+/// flag outlives the reader so the walk can tell this apart from corruption.
+struct Limited<R> {
+    inner: R,
+    remaining: u64,
+    exceeded: Rc<Cell<bool>>,
+}
+
+impl<R: Read> Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let want = buf.len().min(usize::try_from(self.remaining.saturating_add(1)).unwrap_or(usize::MAX));
+        let n = self.inner.read(&mut buf[..want])?;
+        if n as u64 > self.remaining {
+            self.exceeded.set(true);
+            return Err(io::Error::other("unpacked size limit exceeded"));
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Validate every entry of a `.zorb` archive and list its regular files,
+/// sorted by path. Nothing is kept in memory beyond the listing.
+pub fn inspect(file_bytes: &[u8], limits: &Limits) -> Result<Vec<FileEntry>, ArchiveError> {
+    let exceeded = Rc::new(Cell::new(false));
+    let reader = Limited { inner: GzDecoder::new(file_bytes), remaining: limits.max_unpacked_size, exceeded: exceeded.clone() };
+    let mut archive = Archive::new(reader);
+    let read_error = |e: io::Error| {
+        if exceeded.get() {
+            ArchiveError::TooLarge { limit: limits.max_unpacked_size }
+        } else {
+            ArchiveError::Corrupt(e.to_string())
+        }
+    };
+
+    let mut seen = Paths::default();
+    let mut files = Vec::new();
+    for (count, entry) in archive.entries().map_err(read_error)?.enumerate() {
+        if count >= limits.max_entries {
+            return Err(ArchiveError::TooManyEntries { limit: limits.max_entries });
+        }
+        let mut entry = entry.map_err(read_error)?;
+        // PAX global headers (as `git archive` writes) describe the archive,
+        // not a file in it
+        if entry.header().entry_type() == EntryType::XGlobalHeader {
+            io::copy(&mut entry, &mut io::sink()).map_err(read_error)?;
+            continue;
+        }
+        let raw = String::from_utf8(entry.path_bytes().into_owned()).map_err(|e| ArchiveError::InvalidPath {
+            path: String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            reason: "is not valid UTF-8",
+        })?;
+        if raw.len() > limits.max_path_len {
+            return Err(ArchiveError::PathTooLong { path: raw, limit: limits.max_path_len });
+        }
+        let path = normalize(&raw)?;
+
+        let entry_type = entry.header().entry_type();
+        let is_file = match entry_type {
+            t if is_regular(t) => true,
+            EntryType::Directory => false,
+            other => return Err(ArchiveError::ForbiddenEntry { path: raw, kind: entry_kind(other) }),
+        };
+        // `./` itself is the unpack directory
+        if path.is_empty() {
+            if is_file {
+                return Err(ArchiveError::InvalidPath { path: raw, reason: "names the archive root" });
+            }
+            continue;
+        }
+        seen.add(&path, is_file)?;
+        if is_file {
+            files.push(FileEntry {
+                size: entry.size() as i64,
+                mode: entry.header().mode().unwrap_or(0o644) as i32 & 0o7777,
+                path,
+            });
+        }
+        // Skipping an entry's data still decompresses it, which the limit counts
+        io::copy(&mut entry, &mut io::sink()).map_err(read_error)?;
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Contents of the regular file at `path` (as listed by [`inspect`]), or `None`
+/// if the archive has no such file.
+pub fn read_file(file_bytes: &[u8], path: &str) -> Result<Option<Vec<u8>>, ArchiveError> {
+    let mut archive = Archive::new(GzDecoder::new(file_bytes));
+    let corrupt = |e: io::Error| ArchiveError::Corrupt(e.to_string());
+    for entry in archive.entries().map_err(corrupt)? {
+        let mut entry = entry.map_err(corrupt)?;
+        let matches = is_regular(entry.header().entry_type())
+            && std::str::from_utf8(&entry.path_bytes()).ok().and_then(|p| normalize(p).ok()).as_deref() == Some(path);
+        if matches {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).map_err(corrupt)?;
+            return Ok(Some(content));
+        }
+    }
+    Ok(None)
+}
+
+/// Entry types [`inspect`] lists as files and [`read_file`] reads.
+fn is_regular(entry_type: EntryType) -> bool {
+    matches!(entry_type, EntryType::Regular | EntryType::Continuous)
+}
+
+fn entry_kind(entry_type: EntryType) -> &'static str {
+    match entry_type {
+        EntryType::Symlink => "symbolic link",
+        EntryType::Link => "hard link",
+        EntryType::Char => "character device",
+        EntryType::Block => "block device",
+        EntryType::Fifo => "FIFO",
+        EntryType::GNUSparse => "sparse file",
+        _ => "special entry",
+    }
+}
+
+/// `./src//mod.z` → `src/mod.z`. Rejects anything that does not stay inside the
+/// unpack directory on every platform.
+fn normalize(raw: &str) -> Result<String, ArchiveError> {
+    let invalid = |reason| Err(ArchiveError::InvalidPath { path: raw.to_string(), reason });
+    if raw.chars().any(char::is_control) {
+        return invalid("contains control characters");
+    }
+    if raw.contains('\\') {
+        return invalid("uses backslashes");
+    }
+    if raw.starts_with('/') {
+        return invalid("is absolute");
+    }
+    let bytes = raw.as_bytes();
+    if bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+        return invalid("starts with a drive letter");
+    }
+    let mut parts = Vec::new();
+    for part in raw.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return invalid("climbs out of the package with '..'"),
+            _ => parts.push(part),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+/// Every path seen so far, keyed by its lowercase form.
+#[derive(Default)]
+struct Paths {
+    /// Explicit entries: original path and whether it is a file.
+    entries: HashMap<String, (String, bool)>,
+    /// Directories implied by the entries' parents.
+    parents: HashMap<String, String>,
+}
+
+impl Paths {
+    fn add(&mut self, path: &str, is_file: bool) -> Result<(), ArchiveError> {
+        let key = path.to_lowercase();
+        if let Some((first, _)) = self.entries.get(&key) {
+            return Err(if first == path {
+                ArchiveError::Duplicate(path.to_string())
+            } else {
+                ArchiveError::CaseCollision { first: first.clone(), second: path.to_string() }
+            });
+        }
+        if let Some(dir) = self.parents.get(&key) {
+            if is_file {
+                return Err(ArchiveError::FileConflict { file: path.to_string(), path: format!("{}/…", dir) });
+            }
+            if dir != path {
+                return Err(ArchiveError::CaseCollision { first: dir.clone(), second: path.to_string() });
+            }
+        }
+        let mut parent = path;
+        while let Some((dir, _)) = parent.rsplit_once('/') {
+            let dir_key = dir.to_lowercase();
+            match self.entries.get(&dir_key) {
+                Some((file, true)) => return Err(ArchiveError::FileConflict { file: file.clone(), path: path.to_string() }),
+                Some((first, false)) if first != dir => {
+                    return Err(ArchiveError::CaseCollision { first: first.clone(), second: path.to_string() });
+                }
+                _ => {}
+            }
+            match self.parents.get(&dir_key) {
+                Some(first) if first != dir => {
+                    return Err(ArchiveError::CaseCollision { first: first.clone(), second: dir.to_string() });
+                }
+                Some(_) => break,
+                None => { self.parents.insert(dir_key, dir.to_string()); }
+            }
+            parent = dir;
+        }
+        self.entries.insert(key, (path.to_string(), is_file));
+        Ok(())
+    }
+}
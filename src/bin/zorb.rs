@@ -5,11 +5,11 @@ use std::path::Path;
 use std::env;
 use std::io::Write;
 use serde::{Deserialize, Serialize};
-use toml;
 use reqwest::multipart;
 use tar::Builder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use sha2::{Digest, Sha256};
 
 fn registry_base() -> String {
     let url = env::var("REGISTRY_URL")
@@ -17,6 +17,41 @@ fn registry_base() -> String {
     url.trim_end_matches('/').to_string()
 }
 
+/// Path of the CLI config file holding the API token (`~/.zorb/config.toml`).
+fn config_path() -> std::path::PathBuf {
+    let home = env::var("HOME")
+        .or_else(|_| env::var("USERPROFILE"))
+        .unwrap_or_else(|_| ".".to_string());
+    Path::new(&home).join(".zorb").join("config.toml")
+}
+
+/// API token for authenticated registry calls: `ZORB_TOKEN` wins over the config file.
+fn api_token() -> Option<String> {
+    if let Ok(token) = env::var("ZORB_TOKEN") {
+        if !token.trim().is_empty() {
+            return Some(token.trim().to_string());
+        }
+    }
+    let content = fs::read_to_string(config_path()).ok()?;
+    let config: CliConfig = toml::from_str(&content).ok()?;
+    config.token
+}
+
+/// Describe a failed registry response from its `{"error", "code"}` envelope.
+async fn api_error(resp: reqwest::Response) -> String {
+    let status = resp.status();
+    let body = resp.json::<serde_json::Value>().await.unwrap_or_default();
+    let message = body["error"].as_str().unwrap_or_else(|| status.canonical_reason().unwrap_or("request failed"));
+    let mut text = match body["code"].as_str() {
+        Some(code) => format!("{} [{}]", message, code),
+        None => format!("{} {}", status, message),
+    };
+    if body["code"] == "unauthorized" {
+        text.push_str("\nRun `zorb login <token>` or set ZORB_TOKEN with a valid token.");
+    }
+    text
+}
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -34,17 +69,37 @@ enum Commands {
         #[arg(short, long)]
         version: Option<String>,
     },
+    /// Save an API token (created on zorbs.io) to ~/.zorb/config.toml
+    Login {
+        token: String,
+    },
     Publish,
+    /// Yank a published version so new resolutions skip it (`--undo` restores it)
+    Yank {
+        /// Package and version, e.g. `mylib@1.0.0` or `@scope/mylib@1.0.0`
+        spec: String,
+        #[arg(long)]
+        undo: bool,
+    },
     Install {
         package: Option<String>,
     },
-    Lock,
+    /// Resolve `[dependencies]` into zorb.lock
+    Lock {
+        /// Root features to turn on, comma separated
+        #[arg(long, value_delimiter = ',')]
+        features: Vec<String>,
+        /// Do not turn on the root `default` feature
+        #[arg(long)]
+        no_default_features: bool,
+    },
 }
 
 #[derive(Serialize, Deserialize)]
 struct ZorbToml {
     package: Package,
     dependencies: Option<toml::Table>,
+    features: Option<toml::Table>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -55,8 +110,15 @@ struct Package {
     license: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Default)]
+struct CliConfig {
+    token: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 struct Lockfile {
+    /// `/api/resolve/graph` returns the same shape under `packages`.
+    #[serde(alias = "packages")]
     package: Vec<LockedPackage>,
 }
 
@@ -65,6 +127,12 @@ struct LockedPackage {
     name: String,
     version: String,
     download_url: String,
+    /// SHA-256 of the `.zorb`; `zorb install` refuses downloads that do not match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+    /// Features turned on in this package, unified across the graph.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    features: Vec<String>,
 }
 
 #[tokio::main]
@@ -73,9 +141,11 @@ async fn main() {
     match cli.command {
         Commands::New { name } => new_project(&name),
         Commands::Add { package, version } => add_dependency(&package, version),
+        Commands::Login { token } => login(&token),
         Commands::Publish => publish().await,
+        Commands::Yank { spec, undo } => yank(&spec, undo).await,
         Commands::Install { package } => install(package).await,
-        Commands::Lock => generate_lock().await,
+        Commands::Lock { features, no_default_features } => generate_lock(features, !no_default_features).await,
     }
 }
 
@@ -127,12 +197,38 @@ fn add_dependency(package: &str, version: Option<String>) {
     println!("Run `zorb lock` to update zorb.lock");
 }
 
+fn login(token: &str) {
+    let path = config_path();
+    let mut config: CliConfig = fs::read_to_string(&path)
+        .ok()
+        .and_then(|c| toml::from_str(&c).ok())
+        .unwrap_or_default();
+    config.token = Some(token.trim().to_string());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(&path, toml::to_string_pretty(&config).unwrap()).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
+    }
+    println!("API token saved to {}", path.display());
+}
+
 async fn publish() {
     let current_dir = env::current_dir().unwrap();
     if !current_dir.join("zorb.toml").exists() {
         eprintln!("No zorb.toml found. Run `zorb new` first.");
         return;
     }
+    let token = match api_token() {
+        Some(t) => t,
+        None => {
+            eprintln!("No API token found. Run `zorb login <token>` or set ZORB_TOKEN.");
+            return;
+        }
+    };
     let mut tar_buf = Vec::new();
     {
         let mut builder = Builder::new(&mut tar_buf);
@@ -150,13 +246,13 @@ async fn publish() {
     let form = multipart::Form::new()
         .part("file", multipart::Part::bytes(compressed).file_name("package.zorb"));
     let url = format!("{}/api/zorbs/new", registry_base());
-    match client.post(url).multipart(form).send().await {
+    match client.post(url).bearer_auth(token).multipart(form).send().await {
         Ok(resp) if resp.status().is_success() => {
             println!("Zorb published successfully!");
             println!("Run `zorb lock` in dependent projects to update lockfiles");
         }
         Ok(resp) => {
-            eprintln!("Publish failed: {}", resp.status());
+            eprintln!("Publish failed: {}", api_error(resp).await);
         }
         Err(e) => {
             eprintln!("Request error: {}", e);
@@ -164,7 +260,42 @@ async fn publish() {
     }
 }
 
-async fn generate_lock() {
+async fn yank(spec: &str, undo: bool) {
+    let (name, version) = match spec.rsplit_once('@') {
+        Some((name, version)) if !name.is_empty() && !version.is_empty() => (name, version),
+        _ => {
+            eprintln!("Expected <name>@<version>, e.g. mylib@1.0.0");
+            return;
+        }
+    };
+    let token = match api_token() {
+        Some(t) => t,
+        None => {
+            eprintln!("No API token found. Run `zorb login <token>` or set ZORB_TOKEN.");
+            return;
+        }
+    };
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/zorbs/{}/{}/yank", registry_base(), name, version);
+    let request = if undo { client.delete(url) } else { client.put(url) };
+    match request.bearer_auth(token).send().await {
+        Ok(resp) if resp.status().is_success() => {
+            if undo {
+                println!("Restored {}@{}", name, version);
+            } else {
+                println!("Yanked {}@{}", name, version);
+            }
+        }
+        Ok(resp) => {
+            eprintln!("Yank failed: {}", api_error(resp).await);
+        }
+        Err(e) => {
+            eprintln!("Request error: {}", e);
+        }
+    }
+}
+
+async fn generate_lock(features: Vec<String>, default_features: bool) {
     let content = match fs::read_to_string("zorb.toml") {
         Ok(c) => c,
         Err(_) => {
@@ -179,35 +310,32 @@ async fn generate_lock() {
             return;
         }
     };
-    let mut packages = vec![];
-    if let Some(deps) = zorb.dependencies {
-        let client = reqwest::Client::new();
-        for (name, _) in deps {
-            let url = format!("{}/api/resolve?name={}", registry_base(), name);
-            match client.get(&url).send().await {
-                Ok(resp) if resp.status().is_success() => {
-                    let data: serde_json::Value = match resp.json::<serde_json::Value>().await {
-                        Ok(d) => d,
-                        Err(_) => continue,
-                    };
-                    if let (Some(name), Some(version), Some(download_url)) = (
-                        data.get("name").and_then(|v| v.as_str()),
-                        data.get("version").and_then(|v| v.as_str()),
-                        data.get("download_url").and_then(|v| v.as_str()),
-                    ) {
-                        packages.push(LockedPackage {
-                            name: name.to_string(),
-                            version: version.to_string(),
-                            download_url: download_url.to_string(),
-                        });
-                    }
-                }
-                _ => {
-                    eprintln!("Could not resolve {}", name);
-                }
+    let deps = zorb.dependencies.unwrap_or_default();
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/resolve/graph", registry_base());
+    let body = serde_json::json!({
+        "dependencies": deps,
+        "features": zorb.features.unwrap_or_default(),
+        "activate": features,
+        "default-features": default_features,
+    });
+    let packages = match client.post(&url).json(&body).send().await {
+        Ok(resp) if resp.status().is_success() => match resp.json::<Lockfile>().await {
+            Ok(graph) => graph.package,
+            Err(e) => {
+                eprintln!("Invalid response from registry: {}", e);
+                return;
             }
+        },
+        Ok(resp) => {
+            eprintln!("Could not resolve dependencies: {}", api_error(resp).await);
+            return;
         }
-    }
+        Err(e) => {
+            eprintln!("Request error: {}", e);
+            return;
+        }
+    };
     let lockfile = Lockfile { package: packages };
     let lock_content = toml::to_string_pretty(&lockfile).unwrap();
     fs::write("zorb.lock", lock_content).unwrap();
@@ -219,27 +347,36 @@ async fn install(package: Option<String>) {
         let url = format!("{}/{}/0.1.0/download",
             registry_base(),
             pkg.replace('@', "").replace('/', "-"));
-        download_single(&url, &pkg).await;
+        download_single(&url, &pkg, None).await;
         return;
     }
     if !Path::new("zorb.lock").exists() {
         println!("No zorb.lock found. Generating now...");
-        generate_lock().await;
+        generate_lock(Vec::new(), true).await;
     }
     if Path::new("zorb.lock").exists() {
         let content = fs::read_to_string("zorb.lock").unwrap();
         let lock: Lockfile = toml::from_str(&content).unwrap();
         for p in lock.package {
-            download_single(&p.download_url, &p.name).await;
+            download_single(&p.download_url, &p.name, p.checksum.as_deref()).await;
         }
     }
 }
 
-async fn download_single(url: &str, name: &str) {
+async fn download_single(url: &str, name: &str, checksum: Option<&str>) {
+    // The registry hands out download URLs relative to itself
+    let url = if url.starts_with('/') { format!("{}{}", registry_base(), url) } else { url.to_string() };
     let client = reqwest::Client::new();
-    match client.get(url).send().await {
+    match client.get(&url).send().await {
         Ok(resp) if resp.status().is_success() => {
             let bytes = resp.bytes().await.unwrap();
+            if let Some(expected) = checksum {
+                let actual = hex::encode(Sha256::digest(&bytes));
+                if !actual.eq_ignore_ascii_case(expected) {
+                    eprintln!("Checksum mismatch for {}: expected {}, got {}. Refusing to install.", name, expected, actual);
+                    return;
+                }
+            }
             let filename = format!("{}.zorb", name.replace('/', "-"));
             fs::write(&filename, bytes).unwrap();
             println!("Installed {} -> {}", name, filename);
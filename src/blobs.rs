@@ -0,0 +1,277 @@
+// src/blobs.rs — bulk blob maintenance: the move to content-addressed keys, and
+// copying every archive from one storage backend to another
+//
+// Archives used to live at `utils::zorb_filename(name, version)`, which lowercases
+// and flattens names, so `@a-b/c` and `@a/b-c` overwrote each other. They now live
+// at `utils::blob_key(checksum)`. Versions published before the switch are queued
+// in `legacy_blobs` by their migration and drained here.
+use crate::archive;
+use crate::error::AppResult;
+use crate::storage::{Storage, StorageBackend, StorageError};
+use crate::utils;
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+
+/// What [`migrate_legacy_blobs`] did with the queued versions.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LegacyBlobReport {
+    /// Versions whose archive now sits at its content-addressed key.
+    pub moved: usize,
+    /// Versions with no archive at either key.
+    pub missing: usize,
+    /// Versions whose legacy blob was overwritten by a colliding package: its
+    /// digest differs from the recorded checksum or, with none recorded, its
+    /// `zorb.toml` names another package or version.
+    pub mismatched: usize,
+}
+
+impl LegacyBlobReport {
+    pub fn total(&self) -> usize {
+        self.moved + self.missing + self.mismatched
+    }
+}
+
+/// Copy every queued version's archive to its content-addressed key, recording
+/// checksums for versions published before they were kept, then delete the old
+/// keys. Versions leave the queue as they are handled, so an interrupted run
+/// resumes where it stopped.
+pub async fn migrate_legacy_blobs(pool: &PgPool, storage: &StorageBackend) -> AppResult<LegacyBlobReport> {
+    let queued: Vec<(String, String, Option<String>)> = sqlx::query_as(
+        "SELECT l.name, l.version, z.checksum
+         FROM legacy_blobs l JOIN zorbs z USING (name, version)
+         ORDER BY l.name, l.version"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut report = LegacyBlobReport::default();
+    // Colliding versions share a legacy key, so old keys are only deleted once all are handled
+    let mut copied = BTreeSet::new();
+    for (name, version, checksum) in queued {
+        let legacy_key = utils::zorb_filename(&name, &version);
+        match storage.get(&legacy_key).await? {
+            Some(data) => {
+                let digest = utils::sha256_hex(&data);
+                // Without a recorded checksum, only the archive's own manifest can
+                // tell whether it belongs to this version or to a colliding name
+                let belongs = match checksum.as_deref() {
+                    Some(c) => c == digest,
+                    None => packaged_version(&data).is_some_and(|(n, v)| n == name && v == version),
+                };
+                if !belongs {
+                    tracing::warn!("{} holds another package's archive, not {}@{}", legacy_key, name, version);
+                    report.mismatched += 1;
+                } else {
+                    match storage.store(&utils::blob_key(&digest), &data).await {
+                        Ok(()) | Err(StorageError::AlreadyExists(_)) => {}
+                        Err(e) => return Err(e.into()),
+                    }
+                    if checksum.is_none() {
+                        sqlx::query("UPDATE zorbs SET checksum = $3 WHERE name = $1 AND version = $2")
+                            .bind(&name)
+                            .bind(&version)
+                            .bind(&digest)
+                            .execute(pool)
+                            .await?;
+                    }
+                    copied.insert(legacy_key);
+                    report.moved += 1;
+                }
+            }
+            None => {
+                // Already moved, e.g. by a run that stopped before dequeuing it
+                let moved = match checksum.as_deref() {
+                    Some(c) => storage.exists(&utils::blob_key(c)).await?,
+                    None => false,
+                };
+                if moved {
+                    report.moved += 1;
+                } else {
+                    tracing::warn!("No archive stored for {}@{}", name, version);
+                    report.missing += 1;
+                }
+            }
+        }
+        sqlx::query("DELETE FROM legacy_blobs WHERE name = $1 AND version = $2")
+            .bind(&name)
+            .bind(&version)
+            .execute(pool)
+            .await?;
+    }
+
+    for key in copied {
+        if let Err(e) = storage.delete(&key).await {
+            tracing::warn!("Failed to remove legacy blob {}: {}", key, e);
+        }
+    }
+    Ok(report)
+}
+
+/// `package.name` and `package.version` from the archive's `zorb.toml`, or `None`
+/// if the archive is unreadable or has no such manifest.
+fn packaged_version(data: &[u8]) -> Option<(String, String)> {
+    let files = archive::inspect(data, &archive::Limits::default()).ok()?;
+    let manifest = archive::read_file(data, &utils::manifest_entry(&files)?.path).ok()??;
+    let manifest: toml::Table = toml::from_str(&String::from_utf8(manifest).ok()?).ok()?;
+    let package = manifest.get("package")?;
+    Some((package.get("name")?.as_str()?.to_string(), package.get("version")?.as_str()?.to_string()))
+}
+
+/// A referenced blob that could not be copied, and the versions that need it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobProblem {
+    pub checksum: String,
+    /// `name@version` of every version whose archive this is.
+    pub versions: Vec<String>,
+    pub detail: String,
+}
+
+/// What [`copy_blobs`] did, or in a dry run would do.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CopyReport {
+    pub copied: usize,
+    /// Blobs the target already held; a rerun skips everything copied before.
+    pub already_present: usize,
+    /// Versions that have no content-addressed blob yet (see [`migrate_legacy_blobs`]).
+    pub unaddressed: usize,
+    /// Referenced blobs the source does not have.
+    pub missing: Vec<BlobProblem>,
+    /// Source blobs whose contents do not hash to their checksum.
+    pub corrupt: Vec<BlobProblem>,
+    /// Copies that failed or did not read back intact from the target.
+    pub failed: Vec<BlobProblem>,
+}
+
+impl CopyReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.corrupt.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Copy every blob referenced by the `zorbs` table from `source` to `target`,
+/// one at a time, checking each against its checksum before and after the copy.
+/// Blobs already in the target with the right length are skipped, so an
+/// interrupted run can simply be repeated; a length mismatch means an earlier
+/// copy was cut short, and the blob is replaced. Length alone does not catch a
+/// damaged copy, so with `verify` every blob already in the target is hashed
+/// too, and replaced if it does not match. With `dry_run` nothing is written,
+/// but the source is still read and verified.
+pub async fn copy_blobs(pool: &PgPool, source: &StorageBackend, target: &StorageBackend, dry_run: bool, verify: bool) -> AppResult<CopyReport> {
+    let referenced: Vec<(String, Vec<String>)> = sqlx::query_as(
+        "SELECT z.checksum, array_agg(z.name || '@' || z.version ORDER BY z.name, z.version)
+         FROM zorbs z
+         WHERE z.checksum IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM legacy_blobs l WHERE l.name = z.name AND l.version = z.version)
+         GROUP BY z.checksum
+         ORDER BY z.checksum"
+    )
+    .fetch_all(pool)
+    .await?;
+    let unaddressed: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM zorbs z
+         WHERE z.checksum IS NULL
+            OR EXISTS (SELECT 1 FROM legacy_blobs l WHERE l.name = z.name AND l.version = z.version)"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let mut report = CopyReport { unaddressed: unaddressed as usize, ..CopyReport::default() };
+    for (checksum, versions) in referenced {
+        let problem = |detail: String| BlobProblem { checksum: checksum.clone(), versions: versions.clone(), detail };
+        match copy_blob(source, target, &checksum, dry_run, verify).await {
+            Ok(BlobCopy::Copied) => report.copied += 1,
+            Ok(BlobCopy::AlreadyPresent) => report.already_present += 1,
+            Ok(BlobCopy::Missing) => report.missing.push(problem("not in source storage".to_string())),
+            Ok(BlobCopy::Corrupt(digest)) => report.corrupt.push(problem(format!("source contents hash to {}", digest))),
+            Err(detail) => report.failed.push(problem(detail)),
+        }
+    }
+    Ok(report)
+}
+
+enum BlobCopy {
+    Copied,
+    AlreadyPresent,
+    Missing,
+    /// The source blob hashes to this instead.
+    Corrupt(String),
+}
+
+/// Copy one blob; `Err` describes a storage failure or a bad read-back.
+async fn copy_blob(source: &StorageBackend, target: &StorageBackend, checksum: &str, dry_run: bool, verify: bool) -> Result<BlobCopy, String> {
+    let key = utils::blob_key(checksum);
+    let source_meta = source.metadata(&key).await.map_err(|e| format!("source: {}", e))?;
+    let target_meta = target.metadata(&key).await.map_err(|e| format!("target: {}", e))?;
+    let stale = match (&source_meta, &target_meta) {
+        (Some(s), Some(t)) if s.len == t.len && !verify => return Ok(BlobCopy::AlreadyPresent),
+        (Some(s), Some(t)) if s.len == t.len => {
+            match read_blob(target, &key, false).await.map_err(|e| format!("target: {}", e))? {
+                Some((digest, _)) if digest == checksum => return Ok(BlobCopy::AlreadyPresent),
+                _ => true,
+            }
+        }
+        (Some(_), Some(_)) => true,
+        // Gone from the source since an earlier run copied it: keep the copy if it is intact
+        (None, Some(_)) => {
+            let copy = read_blob(target, &key, false).await.map_err(|e| format!("target: {}", e))?;
+            return Ok(match copy {
+                Some((digest, _)) if digest == checksum => BlobCopy::AlreadyPresent,
+                _ => BlobCopy::Missing,
+            });
+        }
+        (_, None) => false,
+    };
+
+    let Some((digest, data)) = read_blob(source, &key, true).await.map_err(|e| format!("source: {}", e))? else {
+        return Ok(BlobCopy::Missing);
+    };
+    if digest != checksum {
+        return Ok(BlobCopy::Corrupt(digest));
+    }
+    if dry_run {
+        return Ok(BlobCopy::Copied);
+    }
+
+    if stale {
+        target.delete(&key).await.map_err(|e| format!("target: removing bad copy: {}", e))?;
+    }
+    match target.store(&key, &data).await {
+        Ok(()) | Err(StorageError::AlreadyExists(_)) => {}
+        Err(e) => return Err(format!("target: {}", e)),
+    }
+    match read_blob(target, &key, false).await.map_err(|e| format!("target: reading back: {}", e))? {
+        Some((digest, _)) if digest == checksum => Ok(BlobCopy::Copied),
+        Some(_) => Err("copy in target does not match its checksum".to_string()),
+        None => Err("copy vanished from target".to_string()),
+    }
+}
+
+/// The hex SHA-256 of the blob at `key`, read as a stream, or `None` if there is none.
+pub async fn stored_digest(storage: &StorageBackend, key: &str) -> Result<Option<String>, StorageError> {
+    Ok(read_blob(storage, key, false).await?.map(|(digest, _)| digest))
+}
+
+/// Stream the blob at `key` through SHA-256 and return its hex digest, along with
+/// its contents when `keep` is set (`store` takes the whole blob).
+async fn read_blob(storage: &StorageBackend, key: &str, keep: bool) -> Result<Option<(String, Vec<u8>)>, StorageError> {
+    use sha2::{Digest, Sha256};
+    use tokio::io::AsyncReadExt;
+    let Some(mut stream) = storage.get_stream(key, 0).await? else {
+        return Ok(None);
+    };
+    let mut hasher = Sha256::new();
+    let mut data = Vec::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = stream.reader.read(&mut buf).await
+            .map_err(|e| StorageError::Backend(format!("read: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        if keep {
+            data.extend_from_slice(&buf[..n]);
+        }
+    }
+    Ok(Some((hex::encode(hasher.finalize()), data)))
+}
@@ -0,0 +1,112 @@
+// src/categories.rs — the server-side category registry
+//
+// One top-level category per Super Domain, plus a few subcategories written
+// `parent::child`. Packages may only list slugs from this table.
+
+/// Most categories a single version may list.
+pub const MAX_CATEGORIES: usize = 5;
+/// Most keywords a single version may list.
+pub const MAX_KEYWORDS: usize = 5;
+/// Longest keyword accepted, in characters.
+pub const MAX_KEYWORD_LEN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Category {
+    pub slug: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+impl Category {
+    /// `web-programming` for `web-programming::http-server`; `None` for top-level categories.
+    pub fn parent(&self) -> Option<&'static str> {
+        self.slug.split_once("::").map(|(parent, _)| parent)
+    }
+}
+
+const fn category(slug: &'static str, name: &'static str, description: &'static str) -> Category {
+    Category { slug, name, description }
+}
+
+pub const CATEGORIES: &[Category] = &[
+    category("core", "Core", "Language primitives, cells and building blocks used everywhere."),
+    category("data-structures", "Data Structures", "Collections, maps, trees and other containers."),
+    category("algorithms", "Algorithms", "Sorting, searching, hashing and randomness."),
+    category("encoding", "Encoding", "Serialization, data formats and parsers."),
+    category("asynchronous", "Asynchronous", "Async runtimes, futures, channels and concurrency."),
+    category("network-programming", "Network Programming", "Sockets, protocols and low-level networking."),
+    category("web-programming", "Web Programming", "Frameworks and tools for building on the web."),
+    category("web-programming::http-server", "HTTP Server", "Servers, routers and middleware."),
+    category("web-programming::http-client", "HTTP Client", "Clients for calling HTTP APIs."),
+    category("web-programming::websocket", "WebSocket", "WebSocket clients and servers."),
+    category("database", "Database", "Database drivers, ORMs and query builders."),
+    category("logging", "Logging", "Logging, tracing and metrics."),
+    category("command-line", "Command Line", "Argument parsing and terminal interfaces."),
+    category("cryptography", "Cryptography", "Ciphers, hashes, signatures and TLS."),
+    category("mathematics", "Mathematics", "Numerics, linear algebra and statistics."),
+    category("testing", "Testing", "Test frameworks, mocks and property testing."),
+    category("os", "Operating Systems", "Bindings to OS APIs, processes and filesystems."),
+    category("text-processing", "Text Processing", "Formatting, templates, regexes and Unicode."),
+    category("config", "Configuration", "Loading and merging application settings."),
+    category("date-and-time", "Date and Time", "Dates, times, zones and durations."),
+    category("development-tools", "Development Tools", "Build helpers, linters and debugging aids."),
+];
+
+pub fn find(slug: &str) -> Option<&'static Category> {
+    CATEGORIES.iter().find(|c| c.slug == slug)
+}
+
+/// Top-level categories, in registry order.
+pub fn top_level() -> impl Iterator<Item = &'static Category> {
+    CATEGORIES.iter().filter(|c| c.parent().is_none())
+}
+
+/// Direct subcategories of `slug`.
+pub fn children(slug: &str) -> impl Iterator<Item = &'static Category> + '_ {
+    CATEGORIES.iter().filter(move |c| c.parent() == Some(slug))
+}
+
+/// Check `categories` against the registry, dropping duplicates but keeping order.
+pub fn validate_categories(categories: &[String]) -> Result<Vec<String>, String> {
+    let mut valid: Vec<String> = Vec::new();
+    for slug in categories {
+        let slug = slug.trim();
+        if find(slug).is_none() {
+            return Err(format!("Unknown category '{}'; see /categories for the list of valid slugs", slug));
+        }
+        if !valid.iter().any(|s| s == slug) {
+            valid.push(slug.to_string());
+        }
+    }
+    if valid.len() > MAX_CATEGORIES {
+        return Err(format!("At most {} categories are allowed", MAX_CATEGORIES));
+    }
+    Ok(valid)
+}
+
+/// Keywords are lowercased and deduplicated. Each must start with a letter and
+/// contain only ASCII letters, digits, `-`, `_` or `+`.
+pub fn validate_keywords(keywords: &[String]) -> Result<Vec<String>, String> {
+    let mut valid: Vec<String> = Vec::new();
+    for keyword in keywords {
+        let keyword = keyword.trim().to_lowercase();
+        if keyword.is_empty() || keyword.chars().count() > MAX_KEYWORD_LEN {
+            return Err(format!("Keyword '{}' must be 1 to {} characters long", keyword, MAX_KEYWORD_LEN));
+        }
+        let well_formed = keyword.starts_with(|c: char| c.is_ascii_alphabetic())
+            && keyword.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+'));
+        if !well_formed {
+            return Err(format!(
+                "Keyword '{}' must start with a letter and only contain letters, digits, -, _ or +",
+                keyword
+            ));
+        }
+        if !valid.contains(&keyword) {
+            valid.push(keyword);
+        }
+    }
+    if valid.len() > MAX_KEYWORDS {
+        return Err(format!("At most {} keywords are allowed", MAX_KEYWORDS));
+    }
+    Ok(valid)
+}
@@ -12,6 +12,42 @@ pub fn upload_dir() -> String {
     env::var("UPLOAD_DIR")
         .unwrap_or_else(|_| "uploads".to_string())
 }
+/// How often buffered download counts are written to the database.
+pub fn download_flush_interval() -> std::time::Duration {
+    let secs = env::var("DOWNLOAD_FLUSH_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|s| *s > 0)
+        .unwrap_or(10);
+    std::time::Duration::from_secs(secs)
+}
+/// How often the webhook queue is checked for due deliveries.
+pub fn webhook_poll_interval() -> std::time::Duration {
+    let secs = env::var("WEBHOOK_POLL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|s| *s > 0)
+        .unwrap_or(5);
+    std::time::Duration::from_secs(secs)
+}
+/// Webhook hosts that may resolve to private, loopback or link-local addresses,
+/// from the comma-separated `WEBHOOK_ALLOWED_HOSTS`; no host may by default.
+pub fn webhook_allowed_hosts() -> Vec<String> {
+    env::var("WEBHOOK_ALLOWED_HOSTS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|h| h.trim().trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase())
+        .filter(|h| !h.is_empty())
+        .collect()
+}
+/// Memory the source browser may use to keep file contents, in bytes.
+pub fn source_cache_bytes() -> usize {
+    let mib: usize = env::var("SOURCE_CACHE_MB")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(64);
+    mib * 1024 * 1024
+}
 pub fn registry_url() -> String {
     env::var("REGISTRY_URL")
         .unwrap_or_else(|_| "http://localhost:3000".to_string())
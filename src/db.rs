@@ -41,55 +41,641 @@ pub async fn run_migrations(pool: &PgPool) {
 
 pub mod queries {
     use super::*;
-    use crate::models::Zorb;
+    use crate::models::{ApiToken, DownloadDay, FileEntry, PackageFilter, PackageOwner, PackageSort, PublishAccess, SearchHit, TrendingZorb, Webhook, WebhookDelivery, Zorb};
+    use crate::manifest::Manifest;
+    use crate::models::listing::{HIGHLIGHT_END, HIGHLIGHT_START};
+    use chrono::{DateTime, NaiveDate, Utc};
+    use std::collections::HashMap;
+    use uuid::Uuid;
 
-    pub async fn list_zorbs(pool: &PgPool) -> Vec<Zorb> {
-        // Latest version of each package ordered by downloads for trending
+    /// Packages (latest non-yanked version each) matching `$1` by full text, by
+    /// word prefix (`$2`, a `simple` tsquery or NULL) or by trigram similarity of
+    /// the name, which is what tolerates typos.
+    const SEARCH_CTE: &str = "WITH pkg AS ( \
+        SELECT DISTINCT ON (z.name) z.id, z.name, z.version, z.description, z.license, z.repository, \
+               z.created_at, z.updated_at, z.owner_id, z.dependencies, z.readme, z.yanked, z.checksum, z.keywords, z.categories, \
+               z.search_vector, (SUM(z.downloads) OVER w)::BIGINT AS total_downloads \
+        FROM zorbs z \
+        WINDOW w AS (PARTITION BY z.name) \
+        ORDER BY z.name, z.yanked, z.created_at DESC \
+    ), q AS ( \
+        SELECT websearch_to_tsquery('english', $1) || COALESCE(to_tsquery('simple', $2), ''::TSQUERY) AS tsq, \
+               LOWER($1) AS term \
+    ), hits AS ( \
+        SELECT pkg.*, q.tsq, q.term, \
+               ts_rank(pkg.search_vector, q.tsq) AS text_rank, \
+               GREATEST(similarity(LOWER(pkg.name), q.term), word_similarity(q.term, LOWER(pkg.name))) AS name_similarity \
+        FROM pkg, q \
+        WHERE pkg.search_vector @@ q.tsq \
+           OR GREATEST(similarity(LOWER(pkg.name), q.term), word_similarity(q.term, LOWER(pkg.name))) >= 0.3 \
+    )";
+
+    /// One page of search results, best first, plus the number of matching packages.
+    ///
+    /// Score: text rank (name > description > README), plus half the name
+    /// similarity, plus 1 for an exact name match (with or without scope), plus
+    /// a small log-scaled popularity boost.
+    pub async fn search_packages(pool: &PgPool, term: &str, limit: i64, offset: i64) -> Result<(Vec<SearchHit>, i64), sqlx::Error> {
+        let prefix = prefix_tsquery(term);
+        let page_sql = format!(
+            "{SEARCH_CTE} SELECT id, name, version, description, license, repository, \
+                    total_downloads AS downloads, created_at, updated_at, owner_id, dependencies, readme, yanked, checksum, keywords, categories, score, \
+                    ts_headline('english', CONCAT_WS(' ', description, LEFT(readme, 20000)), tsq, $5) AS snippet \
+             FROM ( \
+                SELECT *, (text_rank + name_similarity * 0.5 \
+                           + CASE WHEN LOWER(name) = term OR LOWER(split_part(name, '/', 2)) = term THEN 1 ELSE 0 END \
+                           + LN(1 + total_downloads) / 50)::FLOAT8 AS score \
+                FROM hits ORDER BY score DESC, name ASC LIMIT $3 OFFSET $4 \
+             ) AS page \
+             ORDER BY score DESC, name ASC"
+        );
+        let headline_options = format!(
+            "StartSel={}, StopSel={}, MaxWords=24, MinWords=8, MaxFragments=2, FragmentDelimiter=\" … \"",
+            HIGHLIGHT_START, HIGHLIGHT_END
+        );
+        let hits = sqlx::query_as(&page_sql)
+            .bind(term)
+            .bind(&prefix)
+            .bind(limit)
+            .bind(offset)
+            .bind(headline_options)
+            .fetch_all(pool)
+            .await?;
+        let total = sqlx::query_scalar(&format!("{SEARCH_CTE} SELECT COUNT(*) FROM hits"))
+            .bind(term)
+            .bind(&prefix)
+            .fetch_one(pool)
+            .await?;
+        Ok((hits, total))
+    }
+
+    /// `serd:* & jso:*` from `serd jso`, so results appear while a word is still being typed.
+    /// Only alphanumeric runs are kept, which leaves nothing for tsquery syntax to trip on.
+    fn prefix_tsquery(term: &str) -> Option<String> {
+        let words: Vec<String> = term
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| format!("{}:*", w.to_lowercase()))
+            .collect();
+        (!words.is_empty()).then(|| words.join(" & "))
+    }
+
+    pub async fn get_zorb_versions(pool: &PgPool, name: &str) -> Result<Vec<Zorb>, sqlx::Error> {
+        sqlx::query_as("SELECT id, name, version, description, license, repository, downloads, created_at, updated_at, owner_id, dependencies, readme, yanked, checksum, keywords, categories FROM zorbs WHERE name = $1 ORDER BY created_at DESC")
+            .bind(name)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// The current UTC day, which is what `version_downloads.date` records.
+    const TODAY: &str = "(NOW() AT TIME ZONE 'UTC')::DATE";
+
+    /// Latest non-yanked version of each package (latest of all if every one is yanked),
+    /// with download counts and publish dates aggregated over all versions.
+    const PACKAGES_CTE: &str = "WITH pkg AS ( \
+        SELECT DISTINCT ON (z.name) z.id, z.name, z.version, z.description, z.license, z.repository, \
+               z.created_at, z.updated_at, z.owner_id, z.dependencies, z.readme, z.yanked, z.checksum, z.keywords, z.categories, \
+               (SUM(z.downloads) OVER w)::BIGINT AS total_downloads, \
+               MIN(z.created_at) OVER w AS first_published, \
+               MAX(z.created_at) OVER w AS last_published \
+        FROM zorbs z \
+        WINDOW w AS (PARTITION BY z.name) \
+        ORDER BY z.name, z.yanked, z.created_at DESC)";
+
+    /// `$1` owner username, `$2` scope, `$3` license, `$4` keyword, `$5` category
+    /// (subcategories included); NULL disables a filter.
+    const PACKAGES_WHERE: &str = "WHERE ($1::TEXT IS NULL OR EXISTS ( \
+            SELECT 1 FROM zorb_owners o JOIN users u ON u.id = o.user_id \
+            WHERE o.package_name = pkg.name AND LOWER(u.username) = LOWER($1))) \
+        AND ($2::TEXT IS NULL OR starts_with(name, '@' || $2 || '/')) \
+        AND ($3::TEXT IS NULL OR LOWER(license) = LOWER($3)) \
+        AND ($4::TEXT IS NULL OR LOWER($4) = ANY(keywords) OR strpos(LOWER(name), LOWER($4)) > 0 \
+             OR strpos(LOWER(COALESCE(description, '')), LOWER($4)) > 0) \
+        AND ($5::TEXT IS NULL OR EXISTS ( \
+            SELECT 1 FROM unnest(categories) AS c WHERE c = $5 OR starts_with(c, $5 || '::')))";
+
+    /// One page of packages matching `filter`, with `downloads` summed over every
+    /// version, plus the total number of matching packages.
+    pub async fn list_packages(
+        pool: &PgPool,
+        filter: &PackageFilter,
+        sort: PackageSort,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Zorb>, i64), sqlx::Error> {
+        let page_sql = format!(
+            "{PACKAGES_CTE} SELECT id, name, version, description, license, repository, \
+             total_downloads AS downloads, created_at, updated_at, owner_id, dependencies, readme, yanked, checksum, keywords, categories \
+             FROM pkg {PACKAGES_WHERE} ORDER BY {} LIMIT $6 OFFSET $7",
+            sort.order_by()
+        );
+        let zorbs = sqlx::query_as(&page_sql)
+            .bind(&filter.owner)
+            .bind(&filter.scope)
+            .bind(&filter.license)
+            .bind(&filter.keyword)
+            .bind(&filter.category)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?;
+        let count_sql = format!("{PACKAGES_CTE} SELECT COUNT(*) FROM pkg {PACKAGES_WHERE}");
+        let total = sqlx::query_scalar(&count_sql)
+            .bind(&filter.owner)
+            .bind(&filter.scope)
+            .bind(&filter.license)
+            .bind(&filter.keyword)
+            .bind(&filter.category)
+            .fetch_one(pool)
+            .await?;
+        Ok((zorbs, total))
+    }
+
+    /// Number of packages in each category, judged by their latest version. A
+    /// parent category counts every package in it or any of its subcategories.
+    pub async fn category_counts(pool: &PgPool) -> Result<HashMap<String, i64>, sqlx::Error> {
+        let sql = format!(
+            "{PACKAGES_CTE} SELECT s.slug, COUNT(DISTINCT pkg.name) \
+             FROM pkg, unnest(pkg.categories) AS c, \
+             LATERAL (VALUES (c), (split_part(c, '::', 1))) AS s(slug) \
+             GROUP BY s.slug"
+        );
+        let rows: Vec<(String, i64)> = sqlx::query_as(&sql).fetch_all(pool).await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Packages whose downloads grew most over the last 7 UTC days compared with
+    /// the 7 before; ties go to the busier package, then to all-time downloads.
+    pub async fn trending_zorbs(pool: &PgPool, limit: i64) -> Result<Vec<TrendingZorb>, sqlx::Error> {
+        let sql = format!(
+            "{PACKAGES_CTE}, recent AS ( \
+                SELECT name, \
+                       COALESCE(SUM(downloads) FILTER (WHERE date > {TODAY} - 7), 0)::BIGINT AS this_week, \
+                       COALESCE(SUM(downloads) FILTER (WHERE date <= {TODAY} - 7), 0)::BIGINT AS last_week \
+                FROM version_downloads WHERE date > {TODAY} - 14 GROUP BY name) \
+             SELECT pkg.id, pkg.name, pkg.version, pkg.description, pkg.license, pkg.repository, \
+                    pkg.total_downloads AS downloads, pkg.created_at, pkg.updated_at, pkg.owner_id, pkg.dependencies, \
+                    pkg.readme, pkg.yanked, pkg.checksum, pkg.keywords, pkg.categories, \
+                    COALESCE(recent.this_week, 0) AS recent_downloads \
+             FROM pkg LEFT JOIN recent ON recent.name = pkg.name \
+             ORDER BY COALESCE(recent.this_week - recent.last_week, 0) DESC, \
+                      COALESCE(recent.this_week, 0) DESC, pkg.total_downloads DESC, pkg.name ASC \
+             LIMIT $1"
+        );
+        sqlx::query_as(&sql)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Daily downloads of every version of `name` between `from` and `to`
+    /// inclusive; days without downloads are absent.
+    pub async fn download_series(pool: &PgPool, name: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<DownloadDay>, sqlx::Error> {
         sqlx::query_as(
-            "SELECT id, name, version, description, license, repository, downloads, \
-             created_at, updated_at, owner_id, dependencies, readme \
-             FROM (SELECT DISTINCT ON (name) * FROM zorbs ORDER BY name, created_at DESC) \
-             AS latest ORDER BY downloads DESC, name ASC LIMIT 12"
+            "SELECT version, date, downloads FROM version_downloads \
+             WHERE name = $1 AND date BETWEEN $2 AND $3 ORDER BY date, version"
         )
+            .bind(name)
+            .bind(from)
+            .bind(to)
             .fetch_all(pool)
             .await
-            .unwrap_or_default()
     }
 
-    pub async fn search_zorbs(pool: &PgPool, term: &str) -> Vec<Zorb> {
-        let term = format!("%{}%", term.to_lowercase());
-        sqlx::query_as("SELECT id, name, version, description, license, repository, downloads, created_at, updated_at, owner_id, dependencies, readme FROM zorbs WHERE LOWER(name) LIKE $1 OR LOWER(description) LIKE $1 ORDER BY downloads DESC LIMIT 12")
-            .bind(term)
+    /// Latest non-yanked version, used for new resolutions.
+    pub async fn get_latest_zorb(pool: &PgPool, name: &str) -> Result<Option<Zorb>, sqlx::Error> {
+        sqlx::query_as("SELECT id, name, version, description, license, repository, downloads, created_at, updated_at, owner_id, dependencies, readme, yanked, checksum, keywords, categories FROM zorbs WHERE name = $1 AND NOT yanked ORDER BY created_at DESC LIMIT 1")
+            .bind(name)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// The most recent non-yanked releases, newest first, optionally only of the
+    /// package `name`, of packages in `scope`, or of packages `owner` owns.
+    pub async fn recent_releases(
+        pool: &PgPool,
+        name: Option<&str>,
+        scope: Option<&str>,
+        owner: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Zorb>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, name, version, description, license, repository, downloads, created_at, updated_at, owner_id, dependencies, readme, yanked, checksum, keywords, categories \
+             FROM zorbs \
+             WHERE NOT yanked \
+             AND ($1::TEXT IS NULL OR name = $1) \
+             AND ($2::TEXT IS NULL OR starts_with(name, '@' || $2 || '/')) \
+             AND ($3::TEXT IS NULL OR EXISTS ( \
+                 SELECT 1 FROM zorb_owners o JOIN users u ON u.id = o.user_id \
+                 WHERE o.package_name = zorbs.name AND LOWER(u.username) = LOWER($3))) \
+             ORDER BY created_at DESC LIMIT $4"
+        )
+            .bind(name)
+            .bind(scope)
+            .bind(owner)
+            .bind(limit)
             .fetch_all(pool)
             .await
-            .unwrap_or_default()
     }
 
-    pub async fn get_zorb_versions(pool: &PgPool, name: &str) -> Vec<Zorb> {
-        sqlx::query_as("SELECT id, name, version, description, license, repository, downloads, created_at, updated_at, owner_id, dependencies, readme FROM zorbs WHERE name = $1 ORDER BY created_at DESC")
+    /// Every version (yanked included) of each named package, for dependency graph resolution.
+    pub async fn get_zorbs_by_names(pool: &PgPool, names: &[String]) -> Result<Vec<Zorb>, sqlx::Error> {
+        sqlx::query_as("SELECT id, name, version, description, license, repository, downloads, created_at, updated_at, owner_id, dependencies, readme, yanked, checksum, keywords, categories FROM zorbs WHERE name = ANY($1)")
+            .bind(names)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Package count, all-time downloads and downloads over the last 7 days.
+    pub async fn get_home_stats(pool: &PgPool) -> Result<(i64, i64, i64), sqlx::Error> {
+        let sql = format!(
+            "SELECT COUNT(DISTINCT name), COALESCE(SUM(downloads)::BIGINT, 0), \
+             (SELECT COALESCE(SUM(downloads)::BIGINT, 0) FROM version_downloads WHERE date > {TODAY} - 7) \
+             FROM zorbs"
+        );
+        sqlx::query_as(&sql)
+            .fetch_one(pool)
+            .await
+    }
+
+    pub async fn package_exists(pool: &PgPool, name: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM zorbs WHERE name = $1)")
+            .bind(name)
+            .fetch_one(pool)
+            .await
+    }
+
+    /// Those of `names` that no version has been published under.
+    pub async fn missing_packages(pool: &PgPool, names: &[&str]) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT n FROM UNNEST($1::TEXT[]) AS n WHERE NOT EXISTS (SELECT 1 FROM zorbs WHERE name = n) ORDER BY n"
+        )
+        .bind(names)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Set or clear the yanked flag on one version. Returns `false` if the version does not exist.
+    pub async fn set_yanked(pool: &PgPool, name: &str, version: &str, yanked: bool) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE zorbs SET yanked = $3, updated_at = NOW() WHERE name = $1 AND version = $2")
             .bind(name)
+            .bind(version)
+            .bind(yanked)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ─── File listings ──────────────────────────────────────────
+
+    /// The recorded file listing of one version, sorted by path. Empty when none
+    /// has been recorded yet.
+    pub async fn version_files(pool: &PgPool, name: &str, version: &str) -> Result<Vec<FileEntry>, sqlx::Error> {
+        sqlx::query_as("SELECT path, size, mode FROM version_files WHERE name = $1 AND version = $2 ORDER BY path")
+            .bind(name)
+            .bind(version)
             .fetch_all(pool)
             .await
-            .unwrap_or_default()
     }
 
-    pub async fn get_latest_zorb(pool: &PgPool, name: &str) -> Option<Zorb> {
-        sqlx::query_as("SELECT id, name, version, description, license, repository, downloads, created_at, updated_at, owner_id, dependencies, readme FROM zorbs WHERE name = $1 ORDER BY created_at DESC LIMIT 1")
+    /// Record a version's file listing; paths already recorded are left as they are.
+    pub async fn record_version_files(
+        executor: impl sqlx::PgExecutor<'_>,
+        name: &str,
+        version: &str,
+        files: &[FileEntry],
+    ) -> Result<(), sqlx::Error> {
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        let sizes: Vec<i64> = files.iter().map(|f| f.size).collect();
+        let modes: Vec<i32> = files.iter().map(|f| f.mode).collect();
+        sqlx::query(
+            "INSERT INTO version_files (name, version, path, size, mode)
+             SELECT $1, $2, * FROM UNNEST($3::TEXT[], $4::BIGINT[], $5::INTEGER[])
+             ON CONFLICT DO NOTHING"
+        )
+        .bind(name)
+        .bind(version)
+        .bind(&paths)
+        .bind(&sizes)
+        .bind(&modes)
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    // ─── Manifests ──────────────────────────────────────────────
+
+    /// The stored manifest of one version: `None` if the version does not exist,
+    /// `Some(None)` if it has no manifest recorded.
+    pub async fn version_manifest(pool: &PgPool, name: &str, version: &str) -> Result<Option<Option<Manifest>>, sqlx::Error> {
+        let row: Option<(Option<sqlx::types::Json<Manifest>>,)> =
+            sqlx::query_as("SELECT manifest FROM zorbs WHERE name = $1 AND version = $2")
+                .bind(name)
+                .bind(version)
+                .fetch_optional(pool)
+                .await?;
+        Ok(row.map(|(manifest,)| manifest.map(|m| m.0)))
+    }
+
+    /// Stored manifests of every version of `names`, by version id.
+    pub async fn get_manifests_by_names(pool: &PgPool, names: &[String]) -> Result<HashMap<Uuid, Manifest>, sqlx::Error> {
+        let rows: Vec<(Uuid, sqlx::types::Json<Manifest>)> =
+            sqlx::query_as("SELECT id, manifest FROM zorbs WHERE name = ANY($1) AND manifest IS NOT NULL")
+                .bind(names)
+                .fetch_all(pool)
+                .await?;
+        Ok(rows.into_iter().map(|(id, manifest)| (id, manifest.0)).collect())
+    }
+
+    pub async fn record_manifest(
+        executor: impl sqlx::PgExecutor<'_>,
+        name: &str,
+        version: &str,
+        manifest: &Manifest,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE zorbs SET manifest = $3 WHERE name = $1 AND version = $2")
             .bind(name)
-            .fetch_optional(pool)
+            .bind(version)
+            .bind(sqlx::types::Json(manifest))
+            .execute(executor)
+            .await?;
+        Ok(())
+    }
+
+    // ─── API tokens ─────────────────────────────────────────────
+
+    pub async fn create_api_token(
+        pool: &PgPool,
+        user_id: Uuid,
+        name: &str,
+        token_hash: &str,
+        scopes: &[String],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<ApiToken, sqlx::Error> {
+        sqlx::query_as(
+            "INSERT INTO api_tokens (user_id, name, token_hash, scopes, expires_at) \
+             VALUES ($1, $2, $3, $4, $5) \
+             RETURNING id, user_id, name, token_hash, scopes, expires_at, last_used_at, revoked_at, created_at"
+        )
+            .bind(user_id)
+            .bind(name)
+            .bind(token_hash)
+            .bind(scopes)
+            .bind(expires_at)
+            .fetch_one(pool)
             .await
-            .unwrap_or(None)
     }
 
-    pub async fn get_home_stats(pool: &PgPool) -> (i64, i64) {
-        let pkg_count: Option<(i64,)> = sqlx::query_as("SELECT COUNT(DISTINCT name) FROM zorbs")
-            .fetch_optional(pool)
+    pub async fn list_api_tokens(pool: &PgPool, user_id: Uuid) -> Result<Vec<ApiToken>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, user_id, name, token_hash, scopes, expires_at, last_used_at, revoked_at, created_at \
+             FROM api_tokens WHERE user_id = $1 ORDER BY created_at DESC"
+        )
+            .bind(user_id)
+            .fetch_all(pool)
             .await
-            .unwrap_or(None);
-        let total_dl: Option<(i64,)> = sqlx::query_as("SELECT COALESCE(SUM(downloads)::BIGINT, 0) FROM zorbs")
+    }
+
+    /// Look up a token by hash and record its use. Returns `None` for unknown hashes;
+    /// callers still need to check [`ApiToken::is_active`].
+    pub async fn use_api_token(pool: &PgPool, token_hash: &str) -> Result<Option<ApiToken>, sqlx::Error> {
+        sqlx::query_as(
+            "UPDATE api_tokens SET last_used_at = NOW() WHERE token_hash = $1 \
+             RETURNING id, user_id, name, token_hash, scopes, expires_at, last_used_at, revoked_at, created_at"
+        )
+            .bind(token_hash)
             .fetch_optional(pool)
             .await
-            .unwrap_or(None);
-        (pkg_count.map(|r| r.0).unwrap_or(0), total_dl.map(|r| r.0).unwrap_or(0))
+    }
+
+    /// Revoke one of a user's tokens. Returns `false` if no such active token exists.
+    pub async fn revoke_api_token(pool: &PgPool, user_id: Uuid, token_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE api_tokens SET revoked_at = NOW() \
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+        )
+            .bind(token_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ─── Ownership ──────────────────────────────────────────────
+
+    /// Check whether `user_id` may publish to `name`, claiming the name if nobody has yet.
+    /// Packages published before ownership existed have versions but no owner; nobody
+    /// may publish to those until an admin assigns one (see [`add_owner`]).
+    /// Runs under a per-name advisory lock so two first publishes cannot both claim it.
+    pub async fn claim_package(pool: &PgPool, name: &str, user_id: Uuid) -> Result<PublishAccess, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+        let (published, owned, owner): (bool, bool, bool) = sqlx::query_as(
+            "SELECT EXISTS(SELECT 1 FROM zorbs WHERE name = $1), \
+                    EXISTS(SELECT 1 FROM zorb_owners WHERE package_name = $1), \
+                    EXISTS(SELECT 1 FROM zorb_owners WHERE package_name = $1 AND user_id = $2)"
+        )
+            .bind(name)
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let access = match (owned, owner) {
+            (_, true) => PublishAccess::Owner,
+            (true, false) => PublishAccess::NotOwner,
+            (false, _) if published => PublishAccess::NotOwner,
+            (false, _) => {
+                sqlx::query("INSERT INTO zorb_owners (package_name, user_id) VALUES ($1, $2)")
+                    .bind(name)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await?;
+                PublishAccess::NewPackage
+            }
+        };
+        tx.commit().await?;
+        Ok(access)
+    }
+
+    /// Undo a [`claim_package`] whose publish failed, so the name is free to claim again.
+    pub async fn release_package_claim(pool: &PgPool, name: &str, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "DELETE FROM zorb_owners WHERE package_name = $1 AND user_id = $2 \
+             AND NOT EXISTS(SELECT 1 FROM zorb_owners WHERE package_name = $1 AND user_id <> $2)"
+        )
+            .bind(name)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn is_owner(pool: &PgPool, name: &str, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM zorb_owners WHERE package_name = $1 AND user_id = $2)")
+            .bind(name)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+    }
+
+    pub async fn list_owners(pool: &PgPool, name: &str) -> Result<Vec<PackageOwner>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT o.user_id, u.username, u.provider, u.avatar_url, o.added_by, o.created_at \
+             FROM zorb_owners o JOIN users u ON u.id = o.user_id \
+             WHERE o.package_name = $1 ORDER BY o.created_at ASC"
+        )
+            .bind(name)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Add an owner. Returns `false` if the user already owns the package.
+    pub async fn add_owner(pool: &PgPool, name: &str, user_id: Uuid, added_by: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO zorb_owners (package_name, user_id, added_by) VALUES ($1, $2, $3) \
+             ON CONFLICT DO NOTHING"
+        )
+            .bind(name)
+            .bind(user_id)
+            .bind(added_by)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove an owner unless they are the last one. Returns `false` when nothing was removed.
+    /// Takes the same per-name lock as [`claim_package`], so two concurrent removals
+    /// cannot both see a second owner and leave the package with none.
+    pub async fn remove_owner(pool: &PgPool, name: &str, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query(
+            "DELETE FROM zorb_owners WHERE package_name = $1 AND user_id = $2 \
+             AND (SELECT COUNT(*) FROM zorb_owners WHERE package_name = $1) > 1"
+        )
+            .bind(name)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Find users by username (usernames are only unique per login provider).
+    pub async fn find_users_by_username(pool: &PgPool, username: &str) -> Result<Vec<User>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, username, email, provider, provider_id, avatar_url, created_at, updated_at \
+             FROM users WHERE username = $1"
+        )
+            .bind(username)
+            .fetch_all(pool)
+            .await
+    }
+
+    pub async fn is_admin(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND is_admin)")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+    }
+
+    /// Grant or revoke admin rights of the account called `username`, on
+    /// `provider` if given. Returns the providers of the matching accounts;
+    /// nothing changes unless exactly one matches.
+    pub async fn set_admin(pool: &PgPool, username: &str, provider: Option<&str>, admin: bool) -> Result<Vec<String>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let matches: Vec<(Uuid, String)> = sqlx::query_as(
+            "SELECT id, provider FROM users WHERE username = $1 AND ($2::TEXT IS NULL OR provider = $2) ORDER BY provider FOR UPDATE"
+        )
+        .bind(username)
+        .bind(provider)
+        .fetch_all(&mut *tx)
+        .await?;
+        if let [(id, _)] = matches.as_slice() {
+            sqlx::query("UPDATE users SET is_admin = $2, updated_at = NOW() WHERE id = $1")
+                .bind(id)
+                .bind(admin)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(matches.into_iter().map(|(_, provider)| provider).collect())
+    }
+
+    // ─── Webhooks ───────────────────────────────────────────────
+
+    pub async fn create_webhook(
+        pool: &PgPool,
+        package: Option<&str>,
+        url: &str,
+        secret: &str,
+        events: &[String],
+        created_by: Uuid,
+    ) -> Result<Webhook, sqlx::Error> {
+        sqlx::query_as(
+            "INSERT INTO webhooks (package, url, secret, events, created_by) VALUES ($1, $2, $3, $4, $5) \
+             RETURNING id, package, url, secret, events, created_by, created_at"
+        )
+            .bind(package)
+            .bind(url)
+            .bind(secret)
+            .bind(events)
+            .bind(created_by)
+            .fetch_one(pool)
+            .await
+    }
+
+    /// Webhooks of one package, or the registry-wide ones when `package` is `None`.
+    pub async fn list_webhooks(pool: &PgPool, package: Option<&str>) -> Result<Vec<Webhook>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, package, url, secret, events, created_by, created_at FROM webhooks \
+             WHERE package IS NOT DISTINCT FROM $1 ORDER BY created_at ASC"
+        )
+            .bind(package)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Delete a webhook and its delivery log. Returns `false` if `id` is not one
+    /// of `package`'s webhooks.
+    pub async fn delete_webhook(pool: &PgPool, package: Option<&str>, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = $1 AND package IS NOT DISTINCT FROM $2")
+            .bind(id)
+            .bind(package)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The most recent deliveries of one of `package`'s webhooks, newest first;
+    /// `None` if the webhook does not belong to `package`.
+    pub async fn webhook_deliveries(
+        pool: &PgPool,
+        package: Option<&str>,
+        id: Uuid,
+        limit: i64,
+    ) -> Result<Option<Vec<WebhookDelivery>>, sqlx::Error> {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM webhooks WHERE id = $1 AND package IS NOT DISTINCT FROM $2)")
+            .bind(id)
+            .bind(package)
+            .fetch_one(pool)
+            .await?;
+        if !exists {
+            return Ok(None);
+        }
+        let deliveries = sqlx::query_as(
+            "SELECT id, webhook_id, event, payload, status, attempts, next_attempt_at, last_status_code, last_error, delivered_at, created_at \
+             FROM webhook_deliveries WHERE webhook_id = $1 ORDER BY created_at DESC LIMIT $2"
+        )
+            .bind(id)
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+        Ok(Some(deliveries))
     }
 }
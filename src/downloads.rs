@@ -0,0 +1,103 @@
+// src/downloads.rs — buffered download counting
+//
+// Downloads are tallied in memory and written to Postgres in batches, so a burst
+// of installs of one version never queues up on that version's row lock.
+use chrono::{NaiveDate, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Pending downloads of one version on one UTC day.
+type DownloadKey = (String, String, NaiveDate);
+
+#[derive(Debug, Default)]
+pub struct DownloadCounter {
+    pending: Mutex<HashMap<DownloadKey, i64>>,
+}
+
+impl DownloadCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one download of `name@version` today; it reaches the database on the next flush.
+    pub fn record(&self, name: &str, version: &str) {
+        let key = (name.to_string(), version.to_string(), Utc::now().date_naive());
+        *self.pending.lock().unwrap_or_else(|e| e.into_inner()).entry(key).or_insert(0) += 1;
+    }
+
+    /// Downloads recorded but not yet flushed.
+    pub fn pending(&self) -> i64 {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).values().sum()
+    }
+
+    /// Write every pending download to `zorbs.downloads` and `version_downloads`
+    /// in one statement and return how many left the buffer. On failure the batch
+    /// goes back into the buffer for the next attempt, so no download is lost.
+    /// Downloads of versions that no longer exist are dropped.
+    pub async fn flush(&self, pool: &PgPool) -> Result<i64, sqlx::Error> {
+        let batch = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        if batch.is_empty() {
+            return Ok(0);
+        }
+        let total = batch.values().sum();
+        let mut names = Vec::with_capacity(batch.len());
+        let mut versions = Vec::with_capacity(batch.len());
+        let mut dates = Vec::with_capacity(batch.len());
+        let mut counts = Vec::with_capacity(batch.len());
+        for ((name, version, date), count) in &batch {
+            names.push(name.clone());
+            versions.push(version.clone());
+            dates.push(*date);
+            counts.push(*count);
+        }
+
+        let result = sqlx::query(
+            "WITH batch AS (
+                 SELECT * FROM unnest($1::TEXT[], $2::TEXT[], $3::DATE[], $4::BIGINT[]) AS b(name, version, date, downloads)
+             ),
+             bumped AS (
+                 UPDATE zorbs z SET downloads = z.downloads + t.downloads
+                 FROM (SELECT name, version, SUM(downloads)::BIGINT AS downloads FROM batch GROUP BY name, version) t
+                 WHERE z.name = t.name AND z.version = t.version
+                 RETURNING z.name, z.version
+             )
+             INSERT INTO version_downloads (name, version, date, downloads)
+             SELECT b.name, b.version, b.date, b.downloads FROM batch b JOIN bumped USING (name, version)
+             ON CONFLICT (name, version, date) DO UPDATE SET downloads = version_downloads.downloads + EXCLUDED.downloads"
+        )
+        .bind(&names)
+        .bind(&versions)
+        .bind(&dates)
+        .bind(&counts)
+        .execute(pool)
+        .await;
+
+        if let Err(e) = result {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            for (key, count) in batch {
+                *pending.entry(key).or_insert(0) += count;
+            }
+            return Err(e);
+        }
+        Ok(total)
+    }
+
+    /// Flush every `period` until the runtime shuts down. Failures are logged and
+    /// retried on the next tick.
+    pub fn spawn_flusher(self: &Arc<Self>, pool: PgPool, period: Duration) -> JoinHandle<()> {
+        let counter = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = counter.flush(&pool).await {
+                    tracing::warn!("Failed to flush {} buffered downloads: {}", counter.pending(), e);
+                }
+            }
+        })
+    }
+}
@@ -1,22 +1,129 @@
 // src/error.rs
 use axum::response::{IntoResponse, Response};
 use axum::http::StatusCode;
-use serde_json::json;
+use serde_json::{json, Value};
 use axum::Json;
+use crate::storage::StorageError;
 
-#[allow(dead_code)]
+pub type AppResult<T> = Result<T, AppError>;
+
+/// Every API failure, rendered as
+/// `{"error": "<message>", "code": "<code>", "details": {...}}`.
+///
+/// `code` is stable and meant for machines (the CLI, CI scripts); `error` is
+/// for humans and may change. `details` is only present when there is
+/// structured context, e.g. the available versions of an unresolvable package.
 #[derive(Debug)]
 pub enum AppError {
+    /// Malformed request: bad query, body or form fields.
+    BadRequest(String),
+    /// The uploaded archive or its `zorb.toml` failed validation.
+    InvalidPackage(String),
+    /// No credentials, or credentials that are invalid, expired or revoked.
+    Unauthorized(String),
+    /// Authenticated, but not allowed: not an owner, or a token missing a scope.
+    Forbidden(String),
+    NotFound(String),
+    /// The request clashes with existing state, e.g. an already published version.
+    Conflict(String),
+    /// Well-formed, but no version or dependency graph satisfies it.
+    Unresolvable(String),
+    /// The blob store could not be reached or refused the operation.
+    StorageUnavailable(String),
     Database(sqlx::Error),
-    NotFound,
+    Internal(String),
+    /// Any of the above plus structured context for the client.
+    WithDetails(Box<AppError>, Value),
+}
+
+impl AppError {
+    /// Attach structured context, rendered under `details`.
+    pub fn with_details(self, details: Value) -> Self {
+        AppError::WithDetails(Box::new(self), details)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::InvalidPackage(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unresolvable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::StorageUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::WithDetails(inner, _) => inner.status(),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::InvalidPackage(_) => "invalid_package",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Unresolvable(_) => "unresolvable",
+            AppError::StorageUnavailable(_) => "storage_unavailable",
+            AppError::Database(_) => "database_error",
+            AppError::Internal(_) => "internal_error",
+            AppError::WithDetails(inner, _) => inner.code(),
+        }
+    }
+
+    /// Message shown to the client. Database and internal errors are logged, not exposed.
+    pub fn message(&self) -> String {
+        match self {
+            AppError::BadRequest(m)
+            | AppError::InvalidPackage(m)
+            | AppError::Unauthorized(m)
+            | AppError::Forbidden(m)
+            | AppError::NotFound(m)
+            | AppError::Conflict(m)
+            | AppError::Unresolvable(m) => m.clone(),
+            AppError::StorageUnavailable(_) => "Package storage is unavailable".to_string(),
+            AppError::Database(_) | AppError::Internal(_) => "Internal server error".to_string(),
+            AppError::WithDetails(inner, _) => inner.message(),
+        }
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::StorageUnavailable(m) | AppError::Internal(m) => write!(f, "{}: {}", self.code(), m),
+            AppError::Database(e) => write!(f, "{}: {}", self.code(), e),
+            _ => write!(f, "{}: {}", self.code(), self.message()),
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        AppError::Database(e)
+    }
+}
+
+impl From<StorageError> for AppError {
+    fn from(e: StorageError) -> Self {
+        match e {
+            StorageError::AlreadyExists(key) => AppError::Conflict(format!("{} already exists in storage", key)),
+            StorageError::Backend(msg) => AppError::StorageUnavailable(msg),
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AppError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
-            AppError::NotFound => (StatusCode::NOT_FOUND, "Not found"),
-        };
-        (status, Json(json!({"error": message}))).into_response()
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::error!("{}", self);
+        }
+        let mut body = json!({"error": self.message(), "code": self.code()});
+        if let AppError::WithDetails(_, details) = &self {
+            body["details"] = details.clone();
+        }
+        (status, Json(body)).into_response()
     }
 }
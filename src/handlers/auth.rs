@@ -21,6 +21,7 @@ use crate::state::AppState;
 use crate::db;
 use crate::models::user::{User, UserBackend};
 use crate::config;
+use crate::error::{AppError, AppResult};
 use webauthn_rs::prelude::*;
 use uuid::Uuid;
 use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
@@ -88,7 +89,10 @@ pub async fn github_callback(
         Ok(u) => u,
         Err(_) => return Redirect::to("/?error=user"),
     };
-    let _ = auth_session.login(&user).await;
+    if let Err(e) = login(&mut auth_session, &user).await {
+        tracing::error!("{}", e);
+        return Redirect::to("/?error=session");
+    }
     Redirect::to("/")
 }
 pub async fn google_login() -> Redirect {
@@ -148,7 +152,10 @@ pub async fn google_callback(
         Ok(u) => u,
         Err(_) => return Redirect::to("/?error=user"),
     };
-    let _ = auth_session.login(&user).await;
+    if let Err(e) = login(&mut auth_session, &user).await {
+        tracing::error!("{}", e);
+        return Redirect::to("/?error=session");
+    }
     Redirect::to("/")
 }
 pub async fn twitter_login() -> Redirect {
@@ -208,14 +215,45 @@ pub async fn twitter_callback(
         Ok(u) => u,
         Err(_) => return Redirect::to("/?error=user"),
     };
-    let _ = auth_session.login(&user).await;
+    if let Err(e) = login(&mut auth_session, &user).await {
+        tracing::error!("{}", e);
+        return Redirect::to("/?error=session");
+    }
     Redirect::to("/")
 }
 pub async fn logout(mut auth_session: AuthSession<UserBackend>) -> Redirect {
-    let _ = auth_session.logout().await;
+    if let Err(e) = auth_session.logout().await {
+        tracing::error!("Failed to end session: {}", e);
+    }
     Redirect::to("/")
 }
+async fn login(auth_session: &mut AuthSession<UserBackend>, user: &User) -> AppResult<()> {
+    auth_session
+        .login(user)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to start session for {}: {}", user.username, e)))
+}
 // === Passkey handlers ===
+/// Keep WebAuthn ceremony state in the session between the start and finish requests.
+async fn save_ceremony<T: Serialize>(auth_session: &AuthSession<UserBackend>, key: &str, state: &T) -> AppResult<()> {
+    let json = serde_json::to_string(state)
+        .map_err(|e| AppError::Internal(format!("Failed to encode {}: {}", key, e)))?;
+    auth_session
+        .session
+        .insert(key, json)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to store {}: {}", key, e)))
+}
+/// Remove and decode ceremony state; a missing or stale one means the start request never happened.
+async fn take_ceremony<T: serde::de::DeserializeOwned>(auth_session: &AuthSession<UserBackend>, key: &str) -> AppResult<T> {
+    let json: Option<String> = auth_session
+        .session
+        .remove(key)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", key, e)))?;
+    json.and_then(|json| serde_json::from_str(&json).ok())
+        .ok_or_else(|| AppError::BadRequest("No passkey ceremony in progress; start again".to_string()))
+}
 #[derive(Deserialize)]
 pub struct PasskeyRegisterStart {
     username: String,
@@ -227,15 +265,14 @@ pub struct PasskeyRegisterStartResponse {
 pub async fn passkey_register_start(
     Json(payload): Json<PasskeyRegisterStart>,
     State(state): State<Arc<AppState>>,
-    mut auth_session: AuthSession<UserBackend>,
-) -> Json<PasskeyRegisterStartResponse> {
+    auth_session: AuthSession<UserBackend>,
+) -> AppResult<Json<PasskeyRegisterStartResponse>> {
     let user_id = Uuid::new_v4();
     let (ccr, skr) = state.webauthn
         .start_passkey_registration(user_id, &payload.username, &payload.username, None)
-        .expect("Failed to start registration");
-    let skr_json = serde_json::to_string(&skr).unwrap();
-    let _ = auth_session.session.insert("webauthn_reg_state", skr_json).await;
-    Json(PasskeyRegisterStartResponse { public_key_credential_creation_options: ccr })
+        .map_err(|e| AppError::BadRequest(format!("Cannot start passkey registration: {}", e)))?;
+    save_ceremony(&auth_session, "webauthn_reg_state", &skr).await?;
+    Ok(Json(PasskeyRegisterStartResponse { public_key_credential_creation_options: ccr }))
 }
 #[derive(Deserialize)]
 pub struct PasskeyRegisterFinish {
@@ -246,33 +283,30 @@ pub async fn passkey_register_finish(
     Json(payload): Json<PasskeyRegisterFinish>,
     State(state): State<Arc<AppState>>,
     mut auth_session: AuthSession<UserBackend>,
-) -> Redirect {
-    let skr_json: Option<String> = auth_session.session.get("webauthn_reg_state").await.unwrap_or(None);
-    let skr: PasskeyRegistration = serde_json::from_str(&skr_json.unwrap_or_default()).unwrap_or_else(|_| panic!("reg_state"));
-    let _ = auth_session.session.remove::<String>("webauthn_reg_state").await;
+) -> AppResult<Redirect> {
+    let skr: PasskeyRegistration = take_ceremony(&auth_session, "webauthn_reg_state").await?;
     let reg = match state.webauthn.finish_passkey_registration(&payload.response, &skr) {
         Ok(r) => r,
-        Err(_) => return Redirect::to("/?error=reg_finish"),
+        Err(_) => return Ok(Redirect::to("/?error=reg_finish")),
     };
     let cred_id_str = STANDARD_NO_PAD.encode(reg.cred_id().as_ref());
-    let user = match db::find_or_create_user(&state.db, "passkey", &cred_id_str, &payload.username, None, None).await {
-        Ok(u) => u,
-        Err(_) => return Redirect::to("/?error=user"),
-    };
-    let public_key_bytes = serde_cbor_2::to_vec(reg.get_public_key()).unwrap();
-    let _ = sqlx::query!(
+    let user = db::find_or_create_user(&state.db, "passkey", &cred_id_str, &payload.username, None, None).await?;
+    let public_key_bytes = serde_cbor_2::to_vec(reg.get_public_key())
+        .map_err(|e| AppError::Internal(format!("Failed to encode passkey: {}", e)))?;
+    sqlx::query!(
         "INSERT INTO webauthn_credentials (user_id, credential_id, public_key, counter)
          VALUES ($1, $2, $3, $4)",
         user.id,
         cred_id_str,
         public_key_bytes,
         0i64
-    ).execute(&state.db).await;
-    let _ = auth_session.login(&user).await;
-    Redirect::to("/")
+    ).execute(&state.db).await?;
+    login(&mut auth_session, &user).await?;
+    Ok(Redirect::to("/"))
 }
 #[derive(Deserialize)]
 pub struct PasskeyLoginStart {
+    #[allow(dead_code)]
     username: String,
 }
 #[derive(Serialize)]
@@ -282,14 +316,13 @@ pub struct PasskeyLoginStartResponse {
 pub async fn passkey_login_start(
     Json(_payload): Json<PasskeyLoginStart>,
     State(state): State<Arc<AppState>>,
-    mut auth_session: AuthSession<UserBackend>,
-) -> Json<PasskeyLoginStartResponse> {
+    auth_session: AuthSession<UserBackend>,
+) -> AppResult<Json<PasskeyLoginStartResponse>> {
     let (rcr, skr) = state.webauthn
         .start_passkey_authentication(&[])
-        .expect("Failed to start login");
-    let skr_json = serde_json::to_string(&skr).unwrap();
-    let _ = auth_session.session.insert("webauthn_login_state", skr_json).await;
-    Json(PasskeyLoginStartResponse { public_key_credential_request_options: rcr })
+        .map_err(|e| AppError::BadRequest(format!("Cannot start passkey login: {}", e)))?;
+    save_ceremony(&auth_session, "webauthn_login_state", &skr).await?;
+    Ok(Json(PasskeyLoginStartResponse { public_key_credential_request_options: rcr }))
 }
 #[derive(Deserialize)]
 pub struct PasskeyLoginFinish {
@@ -299,24 +332,22 @@ pub async fn passkey_login_finish(
     Json(payload): Json<PasskeyLoginFinish>,
     State(state): State<Arc<AppState>>,
     mut auth_session: AuthSession<UserBackend>,
-) -> Redirect {
-    let skr_json: Option<String> = auth_session.session.get("webauthn_login_state").await.unwrap_or(None);
-    let skr: PasskeyAuthentication = serde_json::from_str(&skr_json.unwrap_or_default()).unwrap_or_else(|_| panic!("login_state"));
-    let _ = auth_session.session.remove::<String>("webauthn_login_state").await;
+) -> AppResult<Redirect> {
+    let skr: PasskeyAuthentication = take_ceremony(&auth_session, "webauthn_login_state").await?;
     let auth_result = match state.webauthn.finish_passkey_authentication(&payload.response, &skr) {
         Ok(r) => r,
-        Err(_) => return Redirect::to("/?error=login_finish"),
+        Err(_) => return Ok(Redirect::to("/?error=login_finish")),
     };
     let cred_id_str = STANDARD_NO_PAD.encode(auth_result.cred_id().as_ref());
     let user: Option<User> = sqlx::query_as!(
         User,
         "SELECT id, username, email, provider, provider_id, avatar_url, created_at, updated_at FROM users WHERE id = (SELECT user_id FROM webauthn_credentials WHERE credential_id = $1)",
         cred_id_str
-    ).fetch_optional(&state.db).await.unwrap_or(None);
+    ).fetch_optional(&state.db).await?;
     let user = match user {
         Some(u) => u,
-        None => return Redirect::to("/?error=user"),
+        None => return Ok(Redirect::to("/?error=user")),
     };
-    let _ = auth_session.login(&user).await;
-    Redirect::to("/")
+    login(&mut auth_session, &user).await?;
+    Ok(Redirect::to("/"))
 }
\ No newline at end of file
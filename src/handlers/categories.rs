@@ -0,0 +1,170 @@
+// src/handlers/categories.rs
+use axum::extract::{State, Path, Query};
+use axum_login::AuthSession;
+use maud::{html, Markup, PreEscaped};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::state::AppState;
+use crate::categories::{self, Category};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::models::{PackageFilter, PackageSort};
+use crate::models::listing::Page;
+use crate::models::user::UserBackend;
+use crate::views;
+
+#[derive(Deserialize)]
+pub struct CategoryParams {
+    page: Option<i64>,
+}
+
+/// Every category with the number of packages in it.
+pub async fn categories_page(
+    auth_session: AuthSession<UserBackend>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Markup> {
+    let counts = queries::category_counts(&state.db).await?;
+    let content = html! {
+        h1 class="text-5xl sm:text-6xl font-black tracking-tighter hero-glow" { "Categories" }
+        p class="text-xl sm:text-2xl text-zinc-300 mt-3" { "Browse packages by what they do." }
+        div class="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 gap-6 mt-12" {
+            @for category in categories::top_level() {
+                div class="bg-zinc-900 border border-zinc-800 rounded-3xl p-8" {
+                    a href=(category_href(category)) class="flex justify-between items-start gap-4 hover:text-cyan-400" {
+                        span class="font-semibold text-lg" { (category.name) }
+                        (count_pill(&counts, category))
+                    }
+                    p class="text-zinc-400 mt-2 text-sm" { (category.description) }
+                    @for child in categories::children(category.slug) {
+                        a href=(category_href(child)) class="flex justify-between mt-3 text-sm text-zinc-300 hover:text-cyan-400" {
+                            span { (child.name) }
+                            span class="text-zinc-500" { (counts.get(child.slug).copied().unwrap_or(0)) }
+                        }
+                    }
+                }
+            }
+        }
+    };
+    Ok(render(auth_session, "Categories", content))
+}
+
+/// Packages in one category, most downloaded first. A parent category also
+/// lists the packages in its subcategories.
+pub async fn category_page(
+    auth_session: AuthSession<UserBackend>,
+    Path(slug): Path<String>,
+    Query(params): Query<CategoryParams>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Markup> {
+    let Some(category) = categories::find(&slug) else {
+        return Ok(html! { (PreEscaped(include_str!("../views/404.html"))) });
+    };
+    let page = Page::new(params.page, None).map_err(AppError::BadRequest)?;
+    let filter = PackageFilter { category: Some(category.slug.to_string()), ..PackageFilter::default() };
+    let (zorbs, total) = queries::list_packages(&state.db, &filter, PackageSort::Downloads, page.size, page.offset()).await?;
+    let counts = queries::category_counts(&state.db).await?;
+    let pages = page.count(total);
+    let parent = category.parent().and_then(categories::find);
+
+    let content = html! {
+        div class="text-sm text-zinc-500" {
+            a href="/categories" class="hover:text-cyan-400" { "Categories" }
+            @if let Some(parent) = parent {
+                " / "
+                a href=(category_href(parent)) class="hover:text-cyan-400" { (parent.name) }
+            }
+        }
+        h1 class="text-5xl sm:text-6xl font-black tracking-tighter hero-glow mt-3" { (category.name) }
+        p class="text-xl sm:text-2xl text-zinc-300 mt-3" { (category.description) }
+        p class="text-zinc-500 mt-2" { (total) " packages" }
+        @let children: Vec<&Category> = categories::children(category.slug).collect();
+        @if !children.is_empty() {
+            div class="flex flex-wrap gap-3 mt-8" {
+                @for child in children {
+                    a href=(category_href(child)) class="text-sm bg-zinc-900 border border-zinc-800 px-4 py-2 rounded-2xl hover:text-cyan-400" {
+                        (child.name) " "
+                        span class="text-zinc-500" { (counts.get(child.slug).copied().unwrap_or(0)) }
+                    }
+                }
+            }
+        }
+        div class="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 xl:grid-cols-4 gap-6 mt-12" {
+            @for zorb in &zorbs {
+                a href=(format!("/{}", zorb.name)) class="block h-full" {
+                    div class="zorb-card bg-zinc-900 border border-zinc-800 rounded-3xl p-8 h-full flex flex-col" {
+                        div class="flex-1 flex justify-between items-start" {
+                            div {
+                                span class="font-mono text-cyan-400" { (zorb.name) }
+                                p class="text-zinc-400 mt-2 text-sm" { (zorb.description.clone().unwrap_or_else(|| "No description".to_string())) }
+                            }
+                            span class="text-xs bg-emerald-500/10 text-emerald-400 px-3 py-1 rounded-full whitespace-nowrap" { (zorb.version) }
+                        }
+                        div class="mt-8 text-xs text-zinc-500 flex gap-6" {
+                            span { "↓ " (zorb.downloads) }
+                        }
+                    }
+                }
+            }
+        }
+        @if zorbs.is_empty() {
+            p class="text-zinc-500 mt-12" { "No packages in this category yet." }
+        }
+        @if pages > 1 {
+            div class="flex justify-center items-center gap-4 mt-8 text-sm text-zinc-400" {
+                @if page.number > 1 {
+                    a href=(format!("{}?page={}", category_href(category), page.number - 1)) class="px-4 py-2 bg-zinc-900 border border-zinc-800 rounded-2xl hover:text-cyan-400" { "← Previous" }
+                }
+                span { "Page " (page.number) " of " (pages) }
+                @if page.number < pages {
+                    a href=(format!("{}?page={}", category_href(category), page.number + 1)) class="px-4 py-2 bg-zinc-900 border border-zinc-800 rounded-2xl hover:text-cyan-400" { "Next →" }
+                }
+            }
+        }
+    };
+    Ok(render(auth_session, category.name, content))
+}
+
+fn category_href(category: &Category) -> String {
+    format!("/categories/{}", category.slug)
+}
+
+fn count_pill(counts: &HashMap<String, i64>, category: &Category) -> Markup {
+    html! {
+        span class="text-xs bg-emerald-500/10 text-emerald-400 px-3 py-1 rounded-full whitespace-nowrap" {
+            (counts.get(category.slug).copied().unwrap_or(0))
+        }
+    }
+}
+
+fn render(auth_session: AuthSession<UserBackend>, title: &str, content: Markup) -> Markup {
+    let user = &auth_session.user;
+    let auth_markup = if let Some(user) = user {
+        html! {
+            div class="flex items-center gap-6" {
+                span class="text-sm font-medium text-zinc-300" { "@" (user.username) }
+                a href="/auth/logout" class="px-6 py-3 bg-red-500/10 hover:bg-red-500/20 text-red-400 font-medium rounded-2xl transition-all" {
+                    "Logout"
+                }
+            }
+        }
+    } else {
+        html! {
+            button onclick="openLoginModal()" class="px-8 py-3 bg-white text-black font-semibold rounded-2xl hover:bg-cyan-400 hover:text-black transition-all flex items-center gap-2" {
+                "Sign in"
+                i class="fa-solid fa-right-to-bracket" {}
+            }
+        }
+    };
+    let mut html_str = views::CATEGORIES_HTML.to_string();
+    let auth_str = auth_markup.into_string();
+    if let Some(pos) = html_str.find("<!-- AUTH_SLOT -->") {
+        html_str.replace_range(pos..pos + "<!-- AUTH_SLOT -->".len(), &auth_str);
+    }
+    if let Some(pos) = html_str.find("<!-- AUTH_SLOT_MOBILE -->") {
+        html_str.replace_range(pos..pos + "<!-- AUTH_SLOT_MOBILE -->".len(), &auth_str);
+    }
+    html_str = html_str.replace("{{title}}", &html! { (title) }.into_string());
+    html_str = html_str.replace("<!-- CATEGORIES_CONTENT -->", &content.into_string());
+    html! { (PreEscaped(html_str)) }
+}
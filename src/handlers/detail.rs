@@ -1,11 +1,15 @@
 // src/handlers/detail.rs
 use axum::extract::{State, Path};
 use axum_login::AuthSession;
+use chrono::{Duration, NaiveDate, Utc};
 use maud::{html, Markup, PreEscaped};
 use std::sync::Arc;
 use crate::state::AppState;
+use crate::categories;
 use crate::db::queries;
+use crate::error::AppResult;
 use crate::models::Zorb;
+use crate::models::downloads::{daily_totals, DEFAULT_SERIES_DAYS};
 use crate::views;
 use crate::models::user::UserBackend;
 
@@ -13,7 +17,7 @@ pub async fn zorb_detail(
     auth_session: AuthSession<UserBackend>,
     Path(name): Path<String>,
     State(state): State<Arc<AppState>>,
-) -> Markup {
+) -> AppResult<Markup> {
     render_detail(auth_session, name, state).await
 }
 
@@ -21,21 +25,23 @@ pub async fn zorb_detail_scoped(
     auth_session: AuthSession<UserBackend>,
     Path((scope, name)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
-) -> Markup {
+) -> AppResult<Markup> {
     let full_name = format!("@{}/{}", scope, name);
     render_detail(auth_session, full_name, state).await
 }
 
-async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, state: Arc<AppState>) -> Markup {
-    let versions: Vec<Zorb> = queries::get_zorb_versions(&state.db, &name).await;
+async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, state: Arc<AppState>) -> AppResult<Markup> {
+    let versions: Vec<Zorb> = queries::get_zorb_versions(&state.db, &name).await?;
     if versions.is_empty() {
-        return html! { (PreEscaped(include_str!("../views/404.html"))) };
+        return Ok(html! { (PreEscaped(include_str!("../views/404.html"))) });
     }
-    let latest = &versions[0];
+    // Yanked releases stay in the history but are not presented as the current version
+    let latest = versions.iter().find(|v| !v.yanked).unwrap_or(&versions[0]);
     let download_url = format!("/{}/{}/download", name, latest.version);
     let mut page = views::DETAIL_HTML.to_string();
     page = page.replace("{{name}}", &latest.name);
     page = page.replace("{{latest.version}}", &latest.version);
+    page = page.replace("{{source_url}}", &format!("/{}/{}/source", name, latest.version));
     page = page.replace("{{latest.downloads}}", &latest.downloads.to_string());
     page = page.replace(
         "{{latest.description}}",
@@ -53,8 +59,34 @@ async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, sta
         "{{latest.created_at}}",
         &latest.created_at.format("%b %d, %Y").to_string()
     );
+    page = page.replace(
+        "{{latest.checksum}}",
+        latest.checksum.as_deref().unwrap_or("Not recorded")
+    );
+    // Categories link to their browse page; keywords are plain tags
+    let tag_chips = html! {
+        @if !latest.categories.is_empty() || !latest.keywords.is_empty() {
+            div class="flex flex-wrap gap-2 mt-4" {
+                @for slug in &latest.categories {
+                    a href=(format!("/categories/{}", slug)) class="text-xs bg-cyan-500/10 text-cyan-400 hover:bg-cyan-500/20 px-3 py-1 rounded-full transition-colors" {
+                        (categories::find(slug).map(|c| c.name).unwrap_or(slug.as_str()))
+                    }
+                }
+                @for keyword in &latest.keywords {
+                    span class="text-xs bg-zinc-800 text-zinc-400 px-3 py-1 rounded-full" { "#" (keyword) }
+                }
+            }
+        }
+    };
+    page = page.replace("<!-- TAG_CHIPS -->", &tag_chips.into_string());
+
     page = page.replace("href=\"#\"", &format!("href=\"{}\"", download_url));
 
+    let today = Utc::now().date_naive();
+    let chart_from = today - Duration::days(DEFAULT_SERIES_DAYS - 1);
+    let days = queries::download_series(&state.db, &name, chart_from, today).await?;
+    page = page.replace("<!-- DOWNLOAD_CHART -->", &download_chart(&daily_totals(&days, chart_from, today)).into_string());
+
     // Fetch and cache README from GitHub if not already stored
     let readme_html = if let Some(readme) = &latest.readme {
         readme.clone()
@@ -68,14 +100,18 @@ async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, sta
                 Ok(resp) => {
                     if let Ok(text) = resp.text().await {
                         if !text.is_empty() {
-                            let _ = sqlx::query!(
+                            // A failed cache write only means the README is fetched again next time
+                            if let Err(e) = sqlx::query!(
                                 "UPDATE zorbs SET readme = $1 WHERE name = $2 AND version = $3",
                                 text,
                                 latest.name,
                                 latest.version
                             )
                             .execute(&state.db)
-                            .await;
+                            .await
+                            {
+                                tracing::warn!("Failed to cache README for {}@{}: {}", latest.name, latest.version, e);
+                            }
                             text
                         } else { String::new() }
                     } else { String::new() }
@@ -119,9 +155,14 @@ async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, sta
         } else {
             format!("{} downloads", v.downloads)
         };
+        let yanked_badge = if v.yanked {
+            r##" <span class="ml-2 px-2 py-0.5 text-xs font-sans font-medium rounded-full bg-red-500/10 text-red-400 border border-red-500/30">yanked</span>"##
+        } else {
+            ""
+        };
         format!(
             r##"<tr class="hover:bg-zinc-800 transition">
-                            <td class="px-8 py-6 font-mono text-cyan-400">{version}</td>
+                            <td class="px-8 py-6 font-mono text-cyan-400">{version}{yanked_badge}</td>
                             <td class="px-8 py-6 text-zinc-400">{date}</td>
                             <td class="px-8 py-6 text-zinc-400">{downloads}</td>
                             <td class="px-8 py-6 text-right">
@@ -132,7 +173,8 @@ async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, sta
                             </td>
                         </tr>"##,
             version = v.version,
-            date = v.created_at.format("%b %d, %Y").to_string(),
+            yanked_badge = yanked_badge,
+            date = v.created_at.format("%b %d, %Y"),
             downloads = downloads_str,
             dl_url = dl_url,
         )
@@ -140,6 +182,8 @@ async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, sta
     page = page.replace("<!-- VERSION_HISTORY_ROWS -->", &version_rows);
 
     // Build dynamic dependency cards from DB
+    let manifest = queries::version_manifest(&state.db, &latest.name, &latest.version).await?.flatten();
+    let is_optional = |dep: &str| manifest.as_ref().and_then(|m| m.dependencies.get(dep)).is_some_and(|d| d.optional);
     let dep_map = latest.dependencies_map();
     let dep_cards: String = if dep_map.is_empty() {
         r##"<div class="bg-zinc-950 border border-zinc-700 rounded-2xl p-6 col-span-full">
@@ -148,7 +192,7 @@ async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, sta
     } else {
         let mut cards = Vec::new();
         for (dep_name, version_req) in &dep_map {
-            let dep_info = queries::get_latest_zorb(&state.db, dep_name).await;
+            let dep_info = queries::get_latest_zorb(&state.db, dep_name).await?;
             let dep_version = dep_info.as_ref()
                 .map(|d| &d.version)
                 .map(|v| v.as_str())
@@ -159,10 +203,15 @@ async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, sta
             let href = format!("/{}", dep_name);
             cards.push(format!(
                 r##"<div class="bg-zinc-950 border border-zinc-700 rounded-2xl p-6">
-                    <a href="{href}" class="font-mono text-cyan-400 hover:text-cyan-300">{dep_name} {dep_version}</a>
+                    <a href="{href}" class="font-mono text-cyan-400 hover:text-cyan-300">{dep_name} {dep_version}</a>{optional_badge}
                     <p class="text-xs text-zinc-500 mt-1">{desc}</p>
                 </div>"##,
                 href = href,
+                optional_badge = if is_optional(dep_name) {
+                    r##" <span class="ml-2 px-2 py-0.5 text-xs font-medium rounded-full bg-zinc-800 text-zinc-400">optional</span>"##
+                } else {
+                    ""
+                },
                 dep_name = dep_name,
                 dep_version = dep_version,
                 desc = desc,
@@ -172,6 +221,31 @@ async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, sta
     };
     page = page.replace("<!-- DEPENDENCY_CARDS -->", &dep_cards);
 
+    // Features declared in zorb.toml, with what each one turns on
+    let features_section = html! {
+        @if let Some(features) = manifest.as_ref().map(|m| &m.features).filter(|f| !f.is_empty()) {
+            div class="mt-10 sm:mt-16 bg-zinc-900 border border-zinc-800 rounded-2xl sm:rounded-3xl p-6 sm:p-10" id="features" {
+                h2 class="text-xl sm:text-3xl font-semibold mb-6 sm:mb-8 flex items-center gap-2 sm:gap-3" {
+                    span class="text-cyan-400" { "🧩" } " Features"
+                }
+                div class="grid grid-cols-1 md:grid-cols-2 gap-4" {
+                    @for (feature, enables) in features {
+                        div class="bg-zinc-950 border border-zinc-700 rounded-2xl p-6" {
+                            span class="font-mono text-cyan-400" { (feature) }
+                            @if features.get("default").is_some_and(|d| d.contains(feature)) {
+                                span class="ml-2 px-2 py-0.5 text-xs font-medium rounded-full bg-cyan-500/10 text-cyan-400" { "default" }
+                            }
+                            p class="text-xs text-zinc-500 mt-1 font-mono" {
+                                @if enables.is_empty() { "Enables nothing else" } @else { (enables.join(", ")) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+    page = page.replace("<!-- FEATURES_SECTION -->", &features_section.into_string());
+
     // dynamic nav with Passkey-ready modal trigger
     let user = &auth_session.user;
     let auth_markup = if let Some(user) = user {
@@ -199,5 +273,35 @@ async fn render_detail(auth_session: AuthSession<UserBackend>, name: String, sta
         page.replace_range(pos..pos + "<!-- AUTH_SLOT_MOBILE -->".len(), &auth_str);
     }
 
-    html! { (PreEscaped(page)) }
+    Ok(html! { (PreEscaped(page)) })
+}
+
+/// Bar chart of daily downloads, one bar per day, as inline SVG.
+fn download_chart(series: &[(NaiveDate, i64)]) -> Markup {
+    const BAR_WIDTH: usize = 10;
+    const HEIGHT: i64 = 120;
+    let total: i64 = series.iter().map(|(_, n)| n).sum();
+    let max = series.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+    html! {
+        div class="mt-10 sm:mt-16 bg-zinc-900 border border-zinc-800 rounded-2xl sm:rounded-3xl p-6 sm:p-10" {
+            h2 class="text-xl sm:text-3xl font-semibold mb-2 flex items-center gap-2 sm:gap-3" {
+                span class="text-cyan-400" { "📈" } " Downloads"
+            }
+            p class="text-sm text-zinc-500 mb-6" { (total) " in the last " (series.len()) " days" }
+            svg id="download-chart" viewBox=(format!("0 0 {} {}", series.len() * BAR_WIDTH, HEIGHT)) preserveAspectRatio="none" class="w-full h-32" {
+                @for (i, (date, downloads)) in series.iter().enumerate() {
+                    @let height = downloads * HEIGHT / max;
+                    rect x=(i * BAR_WIDTH + 1) y=(HEIGHT - height) width=(BAR_WIDTH - 2) height=(height) class="fill-cyan-400" {
+                        title { (date.format("%b %d, %Y")) ": " (downloads) }
+                    }
+                }
+            }
+            @if let (Some((first, _)), Some((last, _))) = (series.first(), series.last()) {
+                div class="flex justify-between text-xs text-zinc-500 mt-2" {
+                    span { (first.format("%b %d")) }
+                    span { (last.format("%b %d")) }
+                }
+            }
+        }
+    }
 }
@@ -1,56 +1,188 @@
 // src/handlers/download.rs
 use axum::{
+    body::Body,
     extract::{Path, State},
-    http::{header, StatusCode},
-    response::{IntoResponse, Redirect},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
 };
 use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio_util::io::ReaderStream;
 use crate::state::AppState;
+use crate::error::{AppError, AppResult};
+use crate::storage::{Storage, StorageBackend};
 use crate::utils;
 
-/// Redirect to the package download URL (S3/R2 or local path).
-/// Increments the download counter.
-async fn serve_file(name: String, version: String, state: Arc<AppState>) -> impl IntoResponse {
+/// Published archives never change, so clients and proxies may cache them forever.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Serve a package archive: streamed by the registry for local and in-memory
+/// storage, or a redirect for S3/R2. Counts the download (HEAD requests are only
+/// probes and are not counted); unknown versions are a 404.
+async fn serve_file(name: String, version: String, method: Method, headers: HeaderMap, state: Arc<AppState>) -> AppResult<Response> {
     let filename = utils::zorb_filename(&name, &version);
 
-    // Increment download counter
-    let _ = sqlx::query!(
-        "UPDATE zorbs SET downloads = downloads + 1 WHERE name = $1 AND version = $2",
+    let checksum = sqlx::query_scalar!(
+        "SELECT checksum FROM zorbs WHERE name = $1 AND version = $2",
         name,
         version
     )
-    .execute(&state.db)
-    .await;
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Version {} of '{}' not found", version, name)))?
+    .ok_or_else(|| AppError::NotFound(format!("Archive of {}@{} was never stored", name, version)))?;
+    let key = utils::blob_key(&checksum);
+
+    match &*state.storage {
+        StorageBackend::S3(_) => {
+            // Counted in memory and flushed in batches, so concurrent downloads never wait on a row lock
+            if method == Method::GET {
+                state.downloads.record(&name, &version);
+            }
+            let url = state.storage.download_url(&key);
+            let location = HeaderValue::from_str(&url)
+                .map_err(|e| AppError::Internal(format!("Invalid storage URL {}: {}", url, e)))?;
+            Ok((StatusCode::FOUND, [(header::LOCATION, location)]).into_response())
+        }
+        storage => {
+            let served = serve_stored(storage, &key, &filename, &checksum, &method, &headers).await?;
+            // Cache revalidations and resumed transfers are not new downloads
+            if served.counts {
+                state.downloads.record(&name, &version);
+            }
+            Ok(served.response)
+        }
+    }
+}
+
+struct Served {
+    response: Response,
+    /// Whether this response delivers the archive from its first byte.
+    counts: bool,
+}
+
+/// Stream the blob at `key` as `filename` with a strong ETag (the SHA-256 recorded
+/// at publish), honouring `If-None-Match`, single-range `Range` requests and `If-Range`.
+/// A HEAD request gets the same headers without the blob being read.
+async fn serve_stored(storage: &StorageBackend, key: &str, filename: &str, checksum: &str, method: &Method, headers: &HeaderMap) -> AppResult<Served> {
+    let etag = format!("\"{}\"", checksum);
+    let missing = || AppError::NotFound(format!("Archive {} is missing from storage", filename));
+    let len = storage.metadata(key).await?.ok_or_else(missing)?.len;
+
+    let mut common = HeaderMap::new();
+    common.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    common.insert(header::CACHE_CONTROL, HeaderValue::from_static(IMMUTABLE));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        common.insert(header::ETAG, value);
+    }
+
+    if let Some(candidates) = header_str(headers, header::IF_NONE_MATCH) {
+        if none_match_hits(candidates, &etag) {
+            return Ok(Served { response: (StatusCode::NOT_MODIFIED, common).into_response(), counts: false });
+        }
+    }
+
+    // A stale If-Range validator means the client's partial copy is outdated: send everything
+    let range_allowed = match header_str(headers, header::IF_RANGE) {
+        Some(validator) => validator.trim() == etag,
+        None => true,
+    };
+    let range = header_str(headers, header::RANGE)
+        .filter(|_| range_allowed)
+        .and_then(|value| parse_range(value, len));
 
-    let url = state.storage.download_url(&filename);
+    let (status, start, end) = match range {
+        None => (StatusCode::OK, 0, len.saturating_sub(1)),
+        Some(Ok((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end),
+        Some(Err(())) => {
+            common.insert(header::CONTENT_RANGE, HeaderValue::from_str(&format!("bytes */{}", len)).expect("numeric header"));
+            return Ok(Served { response: (StatusCode::RANGE_NOT_SATISFIABLE, common).into_response(), counts: false });
+        }
+    };
+    let body_len = if len == 0 { 0 } else { end - start + 1 };
 
-    // If storage backend is S3/R2, redirect to the public URL.
-    // If local, proxy through nginx (local URL).
-    // For local storage the URL is relative (nginx-served), use a redirect.
-    if url.starts_with('/') {
-        // Local storage — redirect to nginx-served path
-        Redirect::to(&url).into_response()
+    let mut response_headers = common;
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/gzip"));
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body_len));
+    if let Ok(value) = HeaderValue::from_str(&format!("attachment; filename=\"{}\"", filename)) {
+        response_headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    if status == StatusCode::PARTIAL_CONTENT {
+        let content_range = format!("bytes {}-{}/{}", start, end, len);
+        response_headers.insert(header::CONTENT_RANGE, HeaderValue::from_str(&content_range).expect("numeric header"));
+    }
+
+    if method == Method::HEAD {
+        return Ok(Served { response: (status, response_headers).into_response(), counts: false });
+    }
+    let stream = storage.get_stream(key, start).await?.ok_or_else(missing)?;
+    let body = Body::from_stream(ReaderStream::new(stream.reader.take(body_len)));
+    Ok(Served { response: (status, response_headers, body).into_response(), counts: start == 0 })
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// `If-None-Match` uses weak comparison, so `W/"x"` matches `"x"`; `*` matches anything.
+fn none_match_hits(candidates: &str, etag: &str) -> bool {
+    candidates
+        .split(',')
+        .map(|c| c.trim())
+        .any(|c| c == "*" || c.trim_start_matches("W/") == etag)
+}
+
+/// Parse a `Range` header against a body of `len` bytes into an inclusive byte span.
+///
+/// `None` means the header should be ignored and the whole body sent: it is
+/// malformed, not in bytes, or asks for several ranges. `Some(Err(()))` means
+/// the range is well formed but lies outside the body (416).
+fn parse_range(value: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = value.trim().strip_prefix("bytes=")?.trim();
+    if spec.contains(',') {
+        return None;
+    }
+    let (first, last) = spec.split_once('-')?;
+    let (first, last) = (first.trim(), last.trim());
+    if first.is_empty() {
+        // Suffix range: the last N bytes
+        let suffix: u64 = last.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(Err(()));
+        }
+        return Some(Ok((len.saturating_sub(suffix), len - 1)));
+    }
+    let start: u64 = first.parse().ok()?;
+    let end = if last.is_empty() {
+        None
     } else {
-        // S3/R2 — redirect to the public URL
-        let headers = axum::http::HeaderMap::from_iter([(
-            header::LOCATION,
-            url.parse().unwrap(),
-        )]);
-        (StatusCode::FOUND, headers).into_response()
+        let end: u64 = last.parse().ok()?;
+        if end < start {
+            return None;
+        }
+        Some(end)
+    };
+    if start >= len {
+        return Some(Err(()));
     }
+    Some(Ok((start, end.map_or(len - 1, |e| e.min(len - 1)))))
 }
 
 pub async fn download_zorb(
     Path((name, version)): Path<(String, String)>,
+    method: Method,
+    headers: HeaderMap,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    serve_file(name, version, state).await
+    serve_file(name, version, method, headers, state).await
 }
 
 pub async fn download_zorb_scoped(
     Path((scope, name, version)): Path<(String, String, String)>,
+    method: Method,
+    headers: HeaderMap,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
     let full_name = format!("@{}/{}", scope, name);
-    serve_file(full_name, version, state).await
+    serve_file(full_name, version, method, headers, state).await
 }
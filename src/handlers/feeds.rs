@@ -0,0 +1,141 @@
+// src/handlers/feeds.rs — Atom feeds of new releases
+use axum::{extract::{Path, State}, http::header, response::IntoResponse};
+use chrono::{DateTime, SecondsFormat, Utc};
+use maud::{html, PreEscaped};
+use std::sync::Arc;
+use crate::config;
+use crate::state::AppState;
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+
+/// Entries per feed.
+const FEED_LENGTH: i64 = 50;
+
+/// Which releases a feed follows.
+enum Feed {
+    All,
+    Package(String),
+    Scope(String),
+    User(String),
+}
+
+impl Feed {
+    fn title(&self) -> String {
+        match self {
+            Feed::All => "zorbs.io — new releases".to_string(),
+            Feed::Package(name) => format!("{} releases", name),
+            Feed::Scope(scope) => format!("@{} releases", scope),
+            Feed::User(username) => format!("Releases of packages owned by {}", username),
+        }
+    }
+
+    /// Path of the feed itself.
+    fn path(&self) -> String {
+        match self {
+            Feed::All => "/feed.atom".to_string(),
+            Feed::Package(name) => format!("/{}/feed.atom", name),
+            Feed::Scope(scope) => format!("/@{}/feed.atom", scope),
+            Feed::User(username) => format!("/users/{}/feed.atom", username),
+        }
+    }
+
+    /// Path of the web page the feed follows, if there is one.
+    fn page(&self) -> Option<String> {
+        match self {
+            Feed::All => Some("/".to_string()),
+            Feed::Package(name) => Some(format!("/{}", name)),
+            Feed::Scope(_) | Feed::User(_) => None,
+        }
+    }
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+async fn render(feed: Feed, state: Arc<AppState>) -> AppResult<impl IntoResponse> {
+    let (name, scope, owner) = match &feed {
+        Feed::All => (None, None, None),
+        Feed::Package(name) => (Some(name.as_str()), None, None),
+        Feed::Scope(scope) => (None, Some(scope.as_str()), None),
+        Feed::User(username) => (None, None, Some(username.as_str())),
+    };
+    let releases = queries::recent_releases(&state.db, name, scope, owner, FEED_LENGTH).await?;
+    if let (Feed::Package(name), true) = (&feed, releases.is_empty()) {
+        if !queries::package_exists(&state.db, name).await? {
+            return Err(AppError::NotFound(format!("Package '{}' not found", name)));
+        }
+    }
+
+    let base = config::registry_url();
+    let base = base.trim_end_matches('/');
+    let updated = releases.first().map(|z| z.created_at).unwrap_or_else(Utc::now);
+    let xml = html! {
+        (PreEscaped(r#"<?xml version="1.0" encoding="utf-8"?>"#))
+        feed xmlns="http://www.w3.org/2005/Atom" {
+            title { (feed.title()) }
+            id { (base) (feed.path()) }
+            link rel="self" type="application/atom+xml" href={ (base) (feed.path()) } {}
+            @if let Some(page) = feed.page() {
+                link rel="alternate" type="text/html" href={ (base) (page) } {}
+            }
+            updated { (timestamp(updated)) }
+            author { name { "zorbs.io" } }
+            generator { "zorbs" }
+            @for zorb in &releases {
+                entry {
+                    title { (zorb.name) " " (zorb.version) }
+                    id { "urn:uuid:" (zorb.id) }
+                    link rel="alternate" type="text/html" href={ (base) "/" (zorb.name) } {}
+                    @if let Some(repository) = &zorb.repository {
+                        link rel="related" href=(repository) {}
+                    }
+                    published { (timestamp(zorb.created_at)) }
+                    updated { (timestamp(zorb.created_at)) }
+                    summary {
+                        @match zorb.description.as_deref().filter(|d| !d.trim().is_empty()) {
+                            Some(description) => (description),
+                            None => { "Version " (zorb.version) " of " (zorb.name) " was published." }
+                        }
+                    }
+                    @for keyword in &zorb.keywords {
+                        category term=(keyword) {}
+                    }
+                }
+            }
+        }
+    };
+    Ok(([(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")], xml.into_string()))
+}
+
+pub async fn all_releases(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    render(Feed::All, state).await
+}
+
+pub async fn package_releases(
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    render(Feed::Package(name), state).await
+}
+
+pub async fn package_releases_scoped(
+    Path((scope, name)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    render(Feed::Package(format!("@{}/{}", scope, name)), state).await
+}
+
+pub async fn scope_releases(
+    Path(scope): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    render(Feed::Scope(scope), state).await
+}
+
+pub async fn user_releases(
+    Path(username): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    render(Feed::User(username), state).await
+}
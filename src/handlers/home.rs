@@ -8,15 +8,14 @@ use std::sync::Arc;
 use axum_login::AuthSession;
 use crate::state::AppState;
 use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::models::{PackageFilter, PackageSort};
+use crate::models::listing::Page;
+use crate::storage::{Storage, StorageError};
 use crate::views;
 use crate::models::user::UserBackend;
 
-#[derive(Deserialize)]
-pub struct SearchParams {
-    q: Option<String>,
-}
-
-pub async fn homepage(auth_session: AuthSession<UserBackend>, State(state): State<Arc<AppState>>) -> Markup {
+pub async fn homepage(auth_session: AuthSession<UserBackend>, State(state): State<Arc<AppState>>) -> AppResult<Markup> {
     let user = &auth_session.user;
     let auth_markup = if let Some(user) = user {
         html! {
@@ -45,11 +44,12 @@ pub async fn homepage(auth_session: AuthSession<UserBackend>, State(state): Stat
     }
 
     // Fetch aggregate stats for hero pills
-    let (total_packages, total_downloads) = queries::get_home_stats(&state.db).await;
+    let (total_packages, total_downloads, weekly_downloads) = queries::get_home_stats(&state.db).await?;
 
-    // Build dynamic trending cards from top downloaded zorbs
-    let trending = queries::list_zorbs(&state.db).await;
-    let trending_cards: String = trending.iter().map(|zorb| {
+    // Build dynamic trending cards from the fastest-growing zorbs
+    let trending = queries::trending_zorbs(&state.db, 12).await?;
+    let trending_cards: String = trending.iter().map(|trend| {
+        let zorb = &trend.zorb;
         let href = format!("/{}", zorb.name);
         let downloads_str = if zorb.downloads >= 1_000_000 {
             format!("{}M", zorb.downloads / 1_000_000)
@@ -77,6 +77,7 @@ pub async fn homepage(auth_session: AuthSession<UserBackend>, State(state): Stat
                     <div class="mt-8 text-xs text-zinc-500 flex gap-6">
                         <span>↓ {downloads}</span>
                         <span>★ {stars}</span>
+                        <span>↗ {recent} this week</span>
                     </div>
                 </div>
             </a>"##,
@@ -86,104 +87,137 @@ pub async fn homepage(auth_session: AuthSession<UserBackend>, State(state): Stat
             version = zorb.version,
             downloads = downloads_str,
             stars = stars_str,
+            recent = trend.recent_downloads,
         )
     }).collect::<Vec<_>>().join("\n");
     html_str = html_str.replace("<!-- TRENDING_CARDS -->", &trending_cards);
 
     // Inject stats pills into hero
     let stats_pills = format!(
-        r##"<div class="flex justify-center gap-3 mt-4">
+        r##"<div class="flex flex-wrap justify-center gap-3 mt-4">
             <div class="text-xs bg-emerald-500/10 text-emerald-400 px-4 py-1.5 rounded-full whitespace-nowrap font-medium">
                 📦 <span class="countup" data-target="{pkgs}">0</span> packages
             </div>
             <div class="text-xs bg-cyan-500/10 text-cyan-400 px-4 py-1.5 rounded-full whitespace-nowrap font-medium">
                 ⬇ <span class="countup" data-target="{dls}">0</span> downloads
             </div>
+            <div class="text-xs bg-amber-500/10 text-amber-400 px-4 py-1.5 rounded-full whitespace-nowrap font-medium">
+                ↗ <span class="countup" data-target="{weekly}">0</span> this week
+            </div>
         </div>"##,
         pkgs = total_packages,
         dls = total_downloads,
+        weekly = weekly_downloads,
     );
     html_str = html_str.replace("<!-- STATS_PILLS -->", &stats_pills);
 
-    html! { (PreEscaped(html_str)) }
-}
-
-pub async fn search_zorbs(Query(params): Query<SearchParams>, State(state): State<Arc<AppState>>) -> Markup {
-    let term = params.q.unwrap_or_default().trim().to_lowercase();
-    let zorbs = if term.is_empty() {
-        queries::list_zorbs(&state.db).await
-    } else {
-        queries::search_zorbs(&state.db, &term).await
-    };
-    html! {
-        div class="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 xl:grid-cols-4 gap-6" {
-            @for zorb in &zorbs {
-                a href=(format!("/{}", zorb.name)) class="block h-full" {
-                    div class="zorb-card bg-zinc-900 border border-zinc-800 rounded-3xl p-8 h-full flex flex-col" {
-                        div class="flex-1 flex justify-between items-start" {
-                            div {
-                                span class="font-mono text-cyan-400" { (zorb.name) }
-                                p class="text-zinc-400 mt-2 text-sm" { (zorb.description.clone().unwrap_or_else(|| "No description".to_string())) }
-                            }
-                            span class="text-xs bg-emerald-500/10 text-emerald-400 px-3 py-1 rounded-full whitespace-nowrap" { (zorb.version) }
-                        }
-                        div class="mt-8 text-xs text-zinc-500 flex gap-6" {
-                            span { "↓ " (zorb.downloads) }
-                            span { "★ " (zorb.downloads / 100) }
-                        }
-                    }
-                }
-            }
-        }
-    }
+    Ok(html! { (PreEscaped(html_str)) })
 }
 
 pub async fn health() -> impl IntoResponse {
     (StatusCode::OK, Json(json!({"status": "healthy", "service": "zorbs-registry"})))
 }
 
-pub async fn list_zorbs(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    use crate::models::Zorb;
-    match sqlx::query_as::<_, Zorb>(
-        "SELECT id, name, version, description, license, repository, owner_id, downloads, created_at, updated_at, dependencies, readme FROM zorbs ORDER BY downloads DESC"
-    )
-    .fetch_all(&state.db)
-    .await
-    {
-        Ok(zorbs) => {
-            let total = zorbs.len();
-            let zorbs_json: Vec<serde_json::Value> = zorbs.into_iter().map(|z| {
-                json!({
-                    "id": z.id,
-                    "name": z.name,
-                    "version": z.version,
-                    "description": z.description,
-                    "license": z.license,
-                    "repository": z.repository,
-                    "downloads": z.downloads,
-                    "owner_id": z.owner_id
-                })
-            }).collect();
-            (StatusCode::OK, Json(json!({"zorbs": zorbs_json, "total": total})))
+#[derive(Deserialize)]
+pub struct ListParams {
+    page: Option<i64>,
+    per_page: Option<i64>,
+    sort: Option<String>,
+    owner: Option<String>,
+    /// `data`, `@data` or `@data/*`
+    scope: Option<String>,
+    license: Option<String>,
+    keyword: Option<String>,
+    category: Option<String>,
+}
+
+impl ListParams {
+    fn filter(&self) -> PackageFilter {
+        let set = |v: &Option<String>| v.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+        PackageFilter {
+            owner: set(&self.owner),
+            scope: set(&self.scope).map(|s| s.trim_start_matches('@').trim_end_matches('*').trim_end_matches('/').to_string()),
+            license: set(&self.license),
+            keyword: set(&self.keyword),
+            category: set(&self.category),
         }
-        Err(e) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": format!("DB query failed: {}", e), "zorbs": [], "total": 0})))
+    }
+}
+
+/// `/api/zorbs?…` link to another page of the same listing.
+fn page_link(filter: &PackageFilter, sort: PackageSort, page: i64, per_page: i64) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    query.append_pair("page", &page.to_string());
+    query.append_pair("per_page", &per_page.to_string());
+    query.append_pair("sort", sort.as_str());
+    for (key, value) in [("owner", &filter.owner), ("scope", &filter.scope), ("license", &filter.license), ("keyword", &filter.keyword), ("category", &filter.category)] {
+        if let Some(value) = value {
+            query.append_pair(key, value);
         }
     }
+    format!("/api/zorbs?{}", query.finish())
+}
+
+/// Paginated package listing: one entry per package, sortable and filterable.
+pub async fn list_zorbs(Query(params): Query<ListParams>, State(state): State<Arc<AppState>>) -> AppResult<impl IntoResponse> {
+    let sort = match params.sort.as_deref() {
+        None | Some("") => PackageSort::default(),
+        Some(s) => PackageSort::parse(s).ok_or_else(|| {
+            let valid: Vec<&str> = PackageSort::ALL.iter().map(PackageSort::as_str).collect();
+            AppError::BadRequest(format!("Unknown sort '{}'; expected one of {}", s, valid.join(", ")))
+        })?,
+    };
+    let page = Page::new(params.page, params.per_page).map_err(AppError::BadRequest)?;
+    let filter = params.filter();
+    let (zorbs, total) = queries::list_packages(&state.db, &filter, sort, page.size, page.offset()).await?;
+
+    let zorbs_json: Vec<serde_json::Value> = zorbs.into_iter().map(|z| {
+        json!({
+            "id": z.id,
+            "name": z.name,
+            "version": z.version,
+            "description": z.description,
+            "license": z.license,
+            "repository": z.repository,
+            "downloads": z.downloads,
+            "owner_id": z.owner_id,
+            "yanked": z.yanked,
+            "checksum": z.checksum,
+            "created_at": z.created_at,
+            "updated_at": z.updated_at,
+            "keywords": z.keywords,
+            "categories": z.categories
+        })
+    }).collect();
+    let pages = page.count(total);
+    let next = (page.number < pages).then(|| page_link(&filter, sort, page.number + 1, page.size));
+    let prev = (page.number > 1).then(|| page_link(&filter, sort, (page.number - 1).min(pages.max(1)), page.size));
+    Ok((StatusCode::OK, Json(json!({
+        "zorbs": zorbs_json,
+        "total": total,
+        "page": page.number,
+        "per_page": page.size,
+        "pages": pages,
+        "sort": sort,
+        "next": next,
+        "prev": prev
+    }))))
 }
 
-fn generate_minimal_zorb(name: &str, version: &str, description: &str, license: &str, repository: &Option<String>) -> Vec<u8> {
+fn generate_minimal_zorb(name: &str, version: &str, description: &str, license: &str, repository: &Option<String>, keywords: &[&str], categories: &[&str]) -> Vec<u8> {
     let mut buf = Vec::new();
     let mut tar_builder = tar::Builder::new(flate2::write::GzEncoder::new(&mut buf, flate2::Compression::default()));
 
     // zorb.toml
     let toml_content = format!(
-        "[package]\nname = \"{}\"\nversion = \"{}\"\nedition = \"2026\"\ndescription = \"{}\"\nlicense = \"{}\"{}\n\n",
+        "[package]\nname = \"{}\"\nversion = \"{}\"\nedition = \"2026\"\ndescription = \"{}\"\nlicense = \"{}\"{}\nkeywords = {:?}\ncategories = {:?}\n\n",
         name,
         version,
         description,
         license,
-        repository.as_ref().map(|r| format!("\nrepository = \"{}\"", r)).unwrap_or_default()
+        repository.as_ref().map(|r| format!("\nrepository = \"{}\"", r)).unwrap_or_default(),
+        keywords,
+        categories
     );
     let mut header = tar::Header::new_gnu();
     header.set_path("zorb.toml").unwrap();
@@ -205,25 +239,38 @@ fn generate_minimal_zorb(name: &str, version: &str, description: &str, license:
     tar_builder.append(&header, std::io::Cursor::new(src_content.as_bytes())).unwrap();
 
     // Finalize gzip
-    let _ = tar_builder.finish().unwrap();
+    tar_builder.finish().unwrap();
     drop(tar_builder);
     buf
 }
 
-pub async fn seed_official(State(state): State<Arc<AppState>>) -> Redirect {
+pub async fn seed_official(State(state): State<Arc<AppState>>) -> AppResult<Redirect> {
     use serde_json::json;
     let official = vec![
-        ("@data/serde", "0.4.0", "Serialization/Deserialization framework for Zeta", "MIT", Some("https://github.com/murphsicles/serde"), json!({})),
-        ("@async/tokio", "1.42.0", "The async runtime that powers Zeta", "MIT", Some("https://github.com/zeta-lang/tokio"), json!({})),
-        ("@http/axum", "0.8.1", "Ergonomic web framework", "MIT", Some("https://github.com/zeta-lang/axum"), json!({"@async/tokio": "^1.42", "@http/hyper": "^1.3"})),
-        ("@core/once_cell", "1.19.0", "Single assignment cells", "MIT OR Apache-2.0", Some("https://github.com/zeta-lang/once_cell"), json!({})),
-        ("@log/tracing", "0.2.5", "Structured, performant logging", "MIT", Some("https://github.com/zeta-lang/tracing"), json!({"@core/once_cell": "^1.19"})),
-        ("@cli/clap", "4.5.0", "Command line argument parser", "MIT OR Apache-2.0", Some("https://github.com/zeta-lang/clap"), json!({})),
+        ("@data/serde", "0.4.0", "Serialization/Deserialization framework for Zeta", "MIT", Some("https://github.com/murphsicles/serde"), json!({}),
+            &["serde", "serialization"][..], &["encoding"][..]),
+        ("@async/tokio", "1.42.0", "The async runtime that powers Zeta", "MIT", Some("https://github.com/zeta-lang/tokio"), json!({}),
+            &["async", "runtime", "io"][..], &["asynchronous", "network-programming"][..]),
+        ("@http/axum", "0.8.1", "Ergonomic web framework", "MIT", Some("https://github.com/zeta-lang/axum"), json!({"@async/tokio": "^1.42", "@http/hyper": "^1.3"}),
+            &["http", "web", "framework"][..], &["web-programming::http-server"][..]),
+        ("@core/once_cell", "1.19.0", "Single assignment cells", "MIT OR Apache-2.0", Some("https://github.com/zeta-lang/once_cell"), json!({}),
+            &["lazy", "static", "cell"][..], &["core", "data-structures"][..]),
+        ("@log/tracing", "0.2.5", "Structured, performant logging", "MIT", Some("https://github.com/zeta-lang/tracing"), json!({"@core/once_cell": "^1.19"}),
+            &["logging", "tracing", "observability"][..], &["logging", "development-tools"][..]),
+        ("@cli/clap", "4.5.0", "Command line argument parser", "MIT OR Apache-2.0", Some("https://github.com/zeta-lang/clap"), json!({}),
+            &["cli", "argument", "parser"][..], &["command-line"][..]),
     ];
-    for (name, version, description, license, repository, deps) in official {
-        let _ = sqlx::query!(
-            "INSERT INTO zorbs (id, name, version, description, license, repository, downloads, created_at, updated_at, dependencies)
-             VALUES ($1, $2, $3, $4, $5, $6, 0, NOW(), NOW(), $7)
+    for (name, version, description, license, repository, deps, keywords, categories) in official {
+        // Also generate and store a minimal .zorb file so downloads work out of the box
+        let repo_opt: Option<String> = repository.map(|s| s.to_string());
+        let zorb_bytes = generate_minimal_zorb(name, version, description, license, &repo_opt, keywords, categories);
+        let keywords: Vec<String> = keywords.iter().map(|k| k.to_string()).collect();
+        let categories: Vec<String> = categories.iter().map(|c| c.to_string()).collect();
+        let checksum = crate::utils::sha256_hex(&zorb_bytes);
+
+        sqlx::query!(
+            "INSERT INTO zorbs (id, name, version, description, license, repository, downloads, created_at, updated_at, dependencies, checksum, keywords, categories)
+             VALUES ($1, $2, $3, $4, $5, $6, 0, NOW(), NOW(), $7, $8, $9, $10)
              ON CONFLICT (name, version) DO NOTHING",
             uuid::Uuid::new_v4(),
             name,
@@ -232,15 +279,22 @@ pub async fn seed_official(State(state): State<Arc<AppState>>) -> Redirect {
             Some(license),
             repository,
             deps as _,
+            checksum,
+            &keywords,
+            &categories,
         )
         .execute(&state.db)
-        .await;
+        .await?;
 
-        // Also generate and store a minimal .zorb file so downloads work out of the box
-        let filename = crate::utils::zorb_filename(name, version);
-        let repo_opt: Option<String> = repository.map(|s| s.to_string());
-        let zorb_bytes = generate_minimal_zorb(name, version, description, license, &repo_opt);
-        let _ = state.storage.store(&filename, &zorb_bytes).await;
+        let files = crate::archive::inspect(&zorb_bytes, &crate::archive::Limits::default())
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        queries::record_version_files(&state.db, name, version, &files).await?;
+
+        // Seeding is idempotent: a blob left by an earlier run is already the right one
+        match state.storage.store(&crate::utils::blob_key(&checksum), &zorb_bytes).await {
+            Ok(()) | Err(StorageError::AlreadyExists(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
     }
-    Redirect::to("/")
+    Ok(Redirect::to("/"))
 }
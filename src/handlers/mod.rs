@@ -1,8 +1,17 @@
 // src/handlers/mod.rs
 pub mod auth;
+pub mod categories;
 pub mod detail;
 pub mod download;
+pub mod feeds;
 pub mod home;
+pub mod owners;
 pub mod publish;
 pub mod resolve;
+pub mod search;
+pub mod source;
+pub mod stats;
 pub mod docs;
+pub mod tokens;
+pub mod webhooks;
+pub mod yank;
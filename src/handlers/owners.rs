@@ -0,0 +1,127 @@
+// src/handlers/owners.rs
+use axum::{Json, extract::{Path, State}, response::IntoResponse, http::StatusCode};
+use axum_login::AuthnBackend;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+use crate::state::AppState;
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::handlers::tokens::Caller;
+use crate::models::{TokenScope, WebhookEvent};
+use crate::webhooks;
+
+#[derive(Deserialize)]
+pub struct AddOwnerRequest {
+    user_id: Option<Uuid>,
+    username: Option<String>,
+}
+
+/// Owner management is open to session callers and to tokens with `publish-update`,
+/// the scope that already grants control over a package's contents. Registry admins
+/// manage the owners of any existing package, which is how packages published before
+/// ownership was recorded get their first owner.
+async fn require_owner(state: &AppState, caller: &Caller, name: &str) -> AppResult<()> {
+    caller.require_scope(TokenScope::PublishUpdate)?;
+    if caller.require_owner(state, name).await.is_ok()
+        || (queries::package_exists(&state.db, name).await? && queries::is_admin(&state.db, caller.user_id).await?)
+    {
+        return Ok(());
+    }
+    Err(AppError::Forbidden(format!("You are not an owner of '{}'", name)))
+}
+
+async fn list(name: String, state: Arc<AppState>) -> AppResult<Json<Value>> {
+    let owners = queries::list_owners(&state.db, &name).await?;
+    // Packages published before ownership was recorded may have none yet
+    if owners.is_empty() && !queries::package_exists(&state.db, &name).await? {
+        return Err(AppError::NotFound(format!("Package '{}' not found", name)));
+    }
+    Ok(Json(json!({"name": name, "owners": owners})))
+}
+
+async fn add(name: String, caller: Caller, state: Arc<AppState>, payload: AddOwnerRequest) -> AppResult<(StatusCode, Json<Value>)> {
+    require_owner(&state, &caller, &name).await?;
+    let user_id = match (payload.user_id, payload.username.as_deref()) {
+        (Some(id), _) => id,
+        (None, Some(username)) => {
+            let users = queries::find_users_by_username(&state.db, username).await?;
+            match users.as_slice() {
+                [user] => user.id,
+                [] => return Err(AppError::NotFound(format!("No user named '{}'", username))),
+                _ => return Err(AppError::Conflict(format!("Username '{}' is ambiguous; pass user_id instead", username))),
+            }
+        }
+        (None, None) => return Err(AppError::BadRequest("Either user_id or username is required".to_string())),
+    };
+    if state.backend.get_user(&user_id).await?.is_none() {
+        return Err(AppError::NotFound("User not found".to_string()));
+    }
+    if queries::add_owner(&state.db, &name, user_id, caller.user_id).await? {
+        webhooks::notify(&state.db, WebhookEvent::OwnershipChanged, &name, json!({"action": "added", "user_id": user_id})).await;
+        Ok((StatusCode::CREATED, Json(json!({"success": true, "name": name, "user_id": user_id}))))
+    } else {
+        Ok((StatusCode::OK, Json(json!({"success": true, "name": name, "user_id": user_id, "message": "Already an owner"}))))
+    }
+}
+
+async fn remove(name: String, user_id: Uuid, caller: Caller, state: Arc<AppState>) -> AppResult<Json<Value>> {
+    require_owner(&state, &caller, &name).await?;
+    if !queries::is_owner(&state.db, &name, user_id).await? {
+        return Err(AppError::NotFound("User is not an owner of this package".to_string()));
+    }
+    if !queries::remove_owner(&state.db, &name, user_id).await? {
+        return Err(AppError::Conflict("Cannot remove the last owner of a package".to_string()));
+    }
+    webhooks::notify(&state.db, WebhookEvent::OwnershipChanged, &name, json!({"action": "removed", "user_id": user_id})).await;
+    Ok(Json(json!({"success": true, "name": name, "user_id": user_id})))
+}
+
+pub async fn list_owners(
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    list(name, state).await
+}
+
+pub async fn list_owners_scoped(
+    Path((scope, name)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    list(format!("@{}/{}", scope, name), state).await
+}
+
+pub async fn add_owner(
+    caller: Caller,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AddOwnerRequest>,
+) -> impl IntoResponse {
+    add(name, caller, state, payload).await
+}
+
+pub async fn add_owner_scoped(
+    caller: Caller,
+    Path((scope, name)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AddOwnerRequest>,
+) -> impl IntoResponse {
+    add(format!("@{}/{}", scope, name), caller, state, payload).await
+}
+
+pub async fn remove_owner(
+    caller: Caller,
+    Path((name, user_id)): Path<(String, Uuid)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    remove(name, user_id, caller, state).await
+}
+
+pub async fn remove_owner_scoped(
+    caller: Caller,
+    Path((scope, name, user_id)): Path<(String, String, Uuid)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    remove(format!("@{}/{}", scope, name), user_id, caller, state).await
+}
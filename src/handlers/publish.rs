@@ -1,16 +1,21 @@
 // src/handlers/publish.rs
-use axum::{Json, extract::{State, Multipart}, response::IntoResponse, http::StatusCode, response::Redirect};
+use axum::{Json, extract::{State, Multipart, multipart::MultipartError}, response::IntoResponse, http::StatusCode};
 use serde_json::json;
 use maud::{html, Markup, PreEscaped};
 use axum_login::AuthSession;
 use std::sync::Arc;
-use tokio::fs;
+use crate::archive;
+use crate::blobs;
 use crate::state::AppState;
-use crate::models::NewZorb;
+use crate::storage::{Storage, StorageError};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::handlers::tokens::Caller;
+use crate::manifest::Manifest;
+use crate::models::{NewZorb, PublishAccess, TokenScope, WebhookEvent};
 use crate::utils;
-use crate::config;
 use crate::views;
-use crate::storage;
+use crate::webhooks;
 use crate::models::user::UserBackend;
 
 pub async fn publish_page(auth_session: AuthSession<UserBackend>) -> Markup {
@@ -47,55 +52,142 @@ pub async fn publish_page(auth_session: AuthSession<UserBackend>) -> Markup {
     html! { (PreEscaped(html_str)) }
 }
 
-pub async fn publish_zorb(State(state): State<Arc<AppState>>, mut multipart: Multipart) -> impl IntoResponse {
+pub async fn publish_zorb(
+    caller: Caller,
+    State(state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> AppResult<impl IntoResponse> {
     let mut form_name = String::new();
     let mut form_version = String::new();
     let mut form_description: Option<String> = None;
     let mut form_license: Option<String> = None;
     let mut form_repository: Option<String> = None;
-    let mut form_dependencies: Option<serde_json::Value> = None;
-    let mut form_readme: Option<String> = None;
     let mut file_bytes: Option<Vec<u8>> = None;
-    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
+    let malformed = |e: MultipartError| AppError::BadRequest(format!("Malformed multipart body: {}", e.body_text()));
+    while let Some(field) = multipart.next_field().await.map_err(malformed)? {
         match field.name() {
-            Some("name") => form_name = field.text().await.unwrap_or_default(),
-            Some("version") => form_version = field.text().await.unwrap_or_default(),
-            Some("description") => form_description = Some(field.text().await.unwrap_or_default()),
-            Some("license") => form_license = Some(field.text().await.unwrap_or_default()),
-            Some("repository") => form_repository = Some(field.text().await.unwrap_or_default()),
-            Some("file") => file_bytes = field.bytes().await.ok().map(|b| b.to_vec()),
+            Some("name") => form_name = field.text().await.map_err(malformed)?,
+            Some("version") => form_version = field.text().await.map_err(malformed)?,
+            Some("description") => form_description = Some(field.text().await.map_err(malformed)?),
+            Some("license") => form_license = Some(field.text().await.map_err(malformed)?),
+            Some("repository") => form_repository = Some(field.text().await.map_err(malformed)?),
+            Some("file") => file_bytes = Some(field.bytes().await.map_err(malformed)?.to_vec()),
             _ => {}
         }
     }
     let file_bytes_vec = match file_bytes {
         Some(bytes) if !bytes.is_empty() => bytes,
-        _ => return (StatusCode::BAD_REQUEST, Json(json!({"error": "File upload is required"}))),
+        _ => return Err(AppError::BadRequest("File upload is required".to_string())),
     };
     let new_zorb = match utils::parse_zorb_toml(&file_bytes_vec) {
         Ok(parsed) => parsed,
-        Err(err) => {
-            if form_name.is_empty() || form_version.is_empty() {
-                return (StatusCode::BAD_REQUEST, Json(json!({"error": err})));
-            }
-            NewZorb {
-                name: form_name,
-                version: form_version,
-                description: form_description,
-                license: form_license,
-                repository: form_repository,
-                dependencies: serde_json::json!({}),
-                readme: None,
-            }
+        // A manifest that is present but invalid is always an error; only a missing one
+        // may be replaced by the form fields
+        Err(err) if err != utils::NO_ZORB_TOML || form_name.is_empty() || form_version.is_empty() => {
+            return Err(AppError::InvalidPackage(err));
         }
+        Err(_) => NewZorb {
+            files: archive::inspect(&file_bytes_vec, &archive::Limits::default()).map_err(|e| AppError::InvalidPackage(e.to_string()))?,
+            name: form_name,
+            version: form_version,
+            description: form_description,
+            license: form_license,
+            repository: form_repository,
+            dependencies: serde_json::json!({}),
+            readme: None,
+            keywords: Vec::new(),
+            categories: Vec::new(),
+            manifest: None,
+            warnings: Vec::new(),
+        },
+    };
+    if let Some(manifest) = &new_zorb.manifest {
+        check_dependencies(&state, manifest).await?;
+    }
+    let scope = if queries::package_exists(&state.db, &new_zorb.name).await? {
+        TokenScope::PublishUpdate
+    } else {
+        TokenScope::PublishNew
     };
-    let filename = utils::zorb_filename(&new_zorb.name, &new_zorb.version);
-    // Store package bytes via storage backend (local FS or S3/R2)
-    state.storage.store(&filename, &file_bytes_vec).await
-        .unwrap_or_else(|e| panic!("Failed to store {}: {}", filename, e));
+    caller.require_scope(scope)?;
+    // New names are claimed by the publisher; the rest only accept their owners
+    let access = queries::claim_package(&state.db, &new_zorb.name, caller.user_id).await?;
+    if access == PublishAccess::NotOwner {
+        return Err(AppError::Forbidden(format!("You are not an owner of '{}'", new_zorb.name)));
+    }
+    let checksum = utils::sha256_hex(&file_bytes_vec);
     let id = uuid::Uuid::new_v4();
-    let _ = sqlx::query!(
-        "INSERT INTO zorbs (id, name, version, description, license, repository, downloads, created_at, updated_at, dependencies, readme)
-         VALUES ($1, $2, $3, $4, $5, $6, 0, NOW(), NOW(), $7, $8)",
+
+    // Published versions are immutable. The row is inserted first, inside a transaction,
+    // so a duplicate is rejected before any blob is touched; the blob is then written
+    // under its digest and only a successful write commits the row.
+    let result = store_version(&state, &new_zorb, id, &file_bytes_vec, &checksum, caller.user_id).await;
+    if let Err(e) = result {
+        if access == PublishAccess::NewPackage {
+            if let Err(cleanup) = queries::release_package_claim(&state.db, &new_zorb.name, caller.user_id).await {
+                tracing::error!("Failed to release claim on {}: {}", new_zorb.name, cleanup);
+            }
+        }
+        return Err(e);
+    }
+    webhooks::notify(&state.db, WebhookEvent::Published, &new_zorb.name, json!({
+        "version": new_zorb.version,
+        "checksum": checksum,
+        "description": new_zorb.description,
+    })).await;
+    Ok((StatusCode::CREATED, Json(json!({
+        "success": true,
+        "id": id,
+        "name": new_zorb.name,
+        "version": new_zorb.version,
+        "checksum": checksum,
+        "keywords": new_zorb.keywords,
+        "categories": new_zorb.categories,
+        "warnings": new_zorb.warnings,
+        "message": "Zorb published successfully! Metadata validated and extracted from zorb.toml."
+    }))))
+}
+
+/// Reject dependencies that dependents could not fetch from this registry,
+/// naming every offending entry at once.
+async fn check_dependencies(state: &AppState, manifest: &Manifest) -> AppResult<()> {
+    let mut errors = manifest.publish_errors();
+    let registry: Vec<(&str, &str)> = manifest.registry_dependencies().collect();
+    let names: Vec<&str> = registry.iter().map(|(_, name)| *name).collect();
+    let missing = queries::missing_packages(&state.db, &names).await?;
+    errors.extend(
+        registry
+            .iter()
+            .filter(|(_, name)| missing.iter().any(|m| m == name))
+            .map(|(section, name)| format!("`{}.{}` does not exist in the registry", section, name)),
+    );
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::InvalidPackage(errors.join("; ")))
+    }
+}
+
+fn duplicate_version(new_zorb: &NewZorb) -> AppError {
+    AppError::Conflict(format!(
+        "{}@{} has already been published; versions are immutable, publish a new version instead",
+        new_zorb.name, new_zorb.version
+    ))
+}
+
+async fn store_version(
+    state: &AppState,
+    new_zorb: &NewZorb,
+    id: uuid::Uuid,
+    data: &[u8],
+    checksum: &str,
+    owner_id: uuid::Uuid,
+) -> AppResult<()> {
+    let mut tx = state.db.begin().await?;
+    lock_blob(&mut tx, checksum).await?;
+    let inserted = sqlx::query!(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, downloads, created_at, updated_at, dependencies, readme, owner_id, checksum, keywords, categories)
+         VALUES ($1, $2, $3, $4, $5, $6, 0, NOW(), NOW(), $7, $8, $9, $10, $11, $12)",
         id,
         new_zorb.name,
         new_zorb.version,
@@ -103,15 +195,85 @@ pub async fn publish_zorb(State(state): State<Arc<AppState>>, mut multipart: Mul
         new_zorb.license,
         new_zorb.repository,
         &new_zorb.dependencies as _,
-        new_zorb.readme
+        new_zorb.readme,
+        owner_id,
+        checksum,
+        &new_zorb.keywords,
+        &new_zorb.categories
     )
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await;
-    (StatusCode::CREATED, Json(json!({
-        "success": true,
-        "id": id,
-        "name": new_zorb.name,
-        "version": new_zorb.version,
-        "message": "Zorb published successfully! Metadata validated and extracted from zorb.toml."
-    })))
+    match inserted {
+        Ok(_) => {}
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => return Err(duplicate_version(new_zorb)),
+        Err(e) => return Err(e.into()),
+    }
+    queries::record_version_files(&mut *tx, &new_zorb.name, &new_zorb.version, &new_zorb.files).await?;
+    if let Some(manifest) = &new_zorb.manifest {
+        queries::record_manifest(&mut *tx, &new_zorb.name, &new_zorb.version, manifest).await?;
+    }
+
+    // Store package bytes via storage backend (local FS or S3/R2); dropping `tx` rolls back.
+    // An identical archive may already be stored for another version: share its blob.
+    let key = utils::blob_key(checksum);
+    let created = match state.storage.store(&key, data).await {
+        Ok(()) => true,
+        Err(StorageError::AlreadyExists(_)) => repair_blob(state, &key, data, checksum).await?,
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Err(e) = tx.commit().await {
+        // The blob is written but its row is gone: remove it so nothing is orphaned
+        if created {
+            if let Err(cleanup) = remove_orphaned_blob(state, &key, checksum).await {
+                tracing::error!("Failed to remove orphaned {}: {}", key, cleanup);
+            }
+        }
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Check a blob that was already stored before sharing it. Stores only ever expose
+/// complete blobs, but one truncated by a crash before writes were atomic, or damaged
+/// since, is replaced with `data`. Returns whether this call wrote the blob.
+async fn repair_blob(state: &AppState, key: &str, data: &[u8], checksum: &str) -> AppResult<bool> {
+    if blobs::stored_digest(&state.storage, key).await?.as_deref() == Some(checksum) {
+        return Ok(false);
+    }
+    tracing::warn!("Replacing damaged blob {}", key);
+    state.storage.delete(key).await?;
+    match state.storage.store(key, data).await {
+        Ok(()) => Ok(true),
+        // Replaced by a concurrent publish of the same archive
+        Err(StorageError::AlreadyExists(_)) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Serialize the storing and removal of one content-addressed blob until the
+/// transaction ends, so a blob is never deleted while a publish is sharing it.
+async fn lock_blob(conn: &mut sqlx::PgConnection, checksum: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('blob:' || $1))")
+        .bind(checksum)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Delete the blob of a publish that failed to commit, unless a version published
+/// meanwhile references it. Runs under [`lock_blob`], which publishes of the same
+/// archive hold from storing it until their row commits.
+async fn remove_orphaned_blob(state: &AppState, key: &str, checksum: &str) -> AppResult<()> {
+    let mut tx = state.db.begin().await?;
+    lock_blob(&mut tx, checksum).await?;
+    let in_use: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM zorbs WHERE checksum = $1)")
+        .bind(checksum)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !in_use {
+        state.storage.delete(key).await?;
+    }
+    tx.commit().await?;
+    Ok(())
 }
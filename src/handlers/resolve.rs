@@ -1,33 +1,189 @@
 // src/handlers/resolve.rs
-use axum::{extract::{Query, State}, Json, response::IntoResponse};
+use axum::{extract::{Query, State}, Json};
 use serde::Deserialize;
-use serde_json::json;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 use crate::state::AppState;
 use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::manifest::Dependency;
+use crate::models::Zorb;
+use crate::resolver;
 
 #[derive(Deserialize)]
 pub struct ResolveParams {
     pub name: String,
+    /// Semver requirement such as `^1.4`; `version` is accepted as an alias.
+    #[serde(default, alias = "version")]
+    pub req: Option<String>,
+}
+
+fn download_url(name: &str, version: &str) -> String {
+    format!("/{}/{}/download", name, version)
 }
 
 pub async fn resolve_package(
     Query(params): Query<ResolveParams>,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    if let Some(zorb) = queries::get_latest_zorb(&state.db, &params.name).await {
-        let download_url = if params.name.starts_with('@') {
-            format!("/{}{}/{}", params.name, if params.name.contains('/') { "" } else { "/" }, zorb.version)
-        } else {
-            format!("/{}/{}", params.name, zorb.version)
-        } + "/download";
-
-        Json(json!({
+) -> AppResult<Json<Value>> {
+    let req = resolver::parse_req(params.req.as_deref()).map_err(|e| {
+        AppError::BadRequest(format!("Invalid version requirement '{}': {}", params.req.clone().unwrap_or_default(), e))
+    })?;
+
+    let versions = queries::get_zorb_versions(&state.db, &params.name).await?;
+    if versions.is_empty() {
+        return Err(AppError::NotFound("Package not found".to_string()));
+    }
+
+    match resolver::best_match(&versions, &req) {
+        Some(zorb) => Ok(Json(json!({
             "name": zorb.name,
             "version": zorb.version,
-            "download_url": download_url
-        }))
-    } else {
-        Json(json!({"error": "Package not found"}))
+            "download_url": download_url(&zorb.name, &zorb.version),
+            "checksum": zorb.checksum
+        }))),
+        None => {
+            let available: Vec<&str> = resolver::candidates(&versions)
+                .into_iter()
+                .map(|(_, z)| z.version.as_str())
+                .collect();
+            Err(AppError::Unresolvable(format!("No version of '{}' matches '{}'", params.name, req))
+                .with_details(json!({"name": params.name, "req": req.to_string(), "available": available})))
+        }
+    }
+}
+
+/// Packages loaded into the solver before giving up; keeps one request from walking the whole registry.
+const MAX_GRAPH_PACKAGES: usize = 2_000;
+
+#[derive(Deserialize)]
+pub struct GraphRequest {
+    /// The root manifest's `[dependencies]` table: `name = "req"` or `name = { version = "req",
+    /// features = [...], default-features = false, optional = true }`.
+    #[serde(default)]
+    pub dependencies: serde_json::Map<String, Value>,
+    /// The root manifest's `[features]` table.
+    #[serde(default)]
+    pub features: BTreeMap<String, Vec<String>>,
+    /// Root features to turn on, on top of `default` unless `default-features` is false.
+    #[serde(default)]
+    pub activate: Vec<String>,
+    #[serde(default = "default_true", rename = "default-features")]
+    pub default_features: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn requirement_json(r: &resolver::Requirement) -> Value {
+    json!({"name": r.name, "req": r.req, "path": r.path, "chain": r.chain()})
+}
+
+/// The root table as a [`resolver::Spec`]. Only the keys that affect resolution are read.
+fn root_spec(request: GraphRequest) -> AppResult<(resolver::Spec, BTreeSet<String>)> {
+    let mut dependencies = BTreeMap::new();
+    for (name, spec) in request.dependencies {
+        let invalid = || AppError::BadRequest(format!("Invalid dependency specification for '{}'", name));
+        let dep = match spec {
+            Value::String(s) => Dependency::registry(s),
+            Value::Object(table) => {
+                let flag = |key: &str, default: bool| match table.get(key) {
+                    None => Ok(default),
+                    Some(value) => value.as_bool().ok_or_else(invalid),
+                };
+                let mut dep = Dependency::registry(table.get("version").and_then(Value::as_str).unwrap_or("*").to_string());
+                dep.default_features = flag("default-features", true)?;
+                dep.optional = flag("optional", false)?;
+                if let Some(features) = table.get("features") {
+                    dep.features = serde_json::from_value(features.clone()).map_err(|_| invalid())?;
+                }
+                dep
+            }
+            _ => return Err(invalid()),
+        };
+        dependencies.insert(name, dep);
+    }
+    let mut activate: BTreeSet<String> = request.activate.into_iter().collect();
+    if request.default_features {
+        activate.insert("default".to_string());
+    }
+    Ok((resolver::Spec { dependencies, features: request.features }, activate))
+}
+
+/// Resolve a full dependency graph: every transitive dependency of the root
+/// table, one version per package, using each version's stored manifest (or,
+/// without one, its `dependencies`). Optional dependencies are only followed
+/// when an active feature enables them, and each package's features are the
+/// union of what every requirement on it asks for.
+pub async fn resolve_graph(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<GraphRequest>,
+) -> AppResult<Json<Value>> {
+    let (root, activate) = root_spec(request)?;
+
+    // Load every version of every reachable package, one query per layer of the graph
+    let mut registry: HashMap<String, Vec<Zorb>> = HashMap::new();
+    let mut manifests = HashMap::new();
+    let mut frontier: Vec<String> = root.dependencies.keys().cloned().collect();
+    let mut seen: HashSet<String> = frontier.iter().cloned().collect();
+    while !frontier.is_empty() {
+        if seen.len() > MAX_GRAPH_PACKAGES {
+            return Err(AppError::Unresolvable("Dependency graph too large to resolve".to_string()));
+        }
+        let rows = queries::get_zorbs_by_names(&state.db, &frontier).await?;
+        manifests.extend(queries::get_manifests_by_names(&state.db, &frontier).await?);
+        let mut next = HashSet::new();
+        for zorb in &rows {
+            next.extend(zorb.dependencies_map().into_keys());
+        }
+        for zorb in rows {
+            registry.entry(zorb.name.clone()).or_default().push(zorb);
+        }
+        // Names with no rows never enter the registry; the solver reports them as not found
+        frontier = next.into_iter().filter(|name| seen.insert(name.clone())).collect();
+    }
+
+    let resolved = resolver::Solver::new(&registry, &manifests).solve(&root, &activate).map_err(graph_error)?;
+    let packages: Vec<Value> = resolved.iter().map(|r| json!({
+        "name": r.zorb.name,
+        "version": r.zorb.version,
+        "download_url": download_url(&r.zorb.name, &r.zorb.version),
+        "checksum": r.zorb.checksum,
+        "features": r.features,
+    })).collect();
+    Ok(Json(json!({"packages": packages})))
+}
+
+/// Missing packages are `not_found`; everything else the solver rejects is `unresolvable`,
+/// with the offending requirement chain or conflict under `details`.
+fn graph_error(e: resolver::ResolveError) -> AppError {
+    let message = e.to_string();
+    let details = match &e {
+        resolver::ResolveError::NotFound(r) | resolver::ResolveError::InvalidRequirement(r, _) => {
+            json!({"requirement": requirement_json(r)})
+        }
+        resolver::ResolveError::NoMatch { requirement, available } => {
+            json!({"requirement": requirement_json(requirement), "available": available})
+        }
+        resolver::ResolveError::Conflict { selected, previous, requirement } => json!({
+            "conflict": {
+                "name": requirement.name,
+                "selected": selected,
+                "requirements": [requirement_json(previous), requirement_json(requirement)],
+            }
+        }),
+        resolver::ResolveError::UnknownFeature { feature, selected, requirement } => json!({
+            "feature": feature,
+            "selected": selected,
+            "requirement": requirement.as_deref().map(requirement_json),
+        }),
+        resolver::ResolveError::TooComplex => return AppError::Unresolvable(message),
+    };
+    match e {
+        resolver::ResolveError::NotFound(_) => AppError::NotFound(message),
+        _ => AppError::Unresolvable(message),
     }
+    .with_details(details)
 }
@@ -0,0 +1,138 @@
+// src/handlers/search.rs
+use axum::{Json, extract::{State, Query}, http::{header, HeaderMap}, response::{IntoResponse, Response}};
+use maud::{html, Markup, PreEscaped};
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt::Write;
+use std::sync::Arc;
+use crate::state::AppState;
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::models::{PackageFilter, PackageSort, SearchHit};
+use crate::models::listing::{Page, HIGHLIGHT_END, HIGHLIGHT_START};
+
+#[derive(Deserialize)]
+pub struct SearchParams {
+    q: Option<String>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+/// Search packages. Returns the HTML fragment used by the homepage search box,
+/// or JSON when the request asks for `application/json`.
+///
+/// An empty query lists every package by downloads.
+pub async fn search_zorbs(
+    headers: HeaderMap,
+    Query(params): Query<SearchParams>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    let term = params.q.unwrap_or_default().trim().to_string();
+    let page = Page::new(params.page, params.per_page).map_err(AppError::BadRequest)?;
+    let (hits, total) = if term.is_empty() {
+        let (zorbs, total) = queries::list_packages(&state.db, &PackageFilter::default(), PackageSort::Downloads, page.size, page.offset()).await?;
+        let hits = zorbs.into_iter().map(|zorb| SearchHit { zorb, score: 0.0, snippet: None }).collect();
+        (hits, total)
+    } else {
+        queries::search_packages(&state.db, &term, page.size, page.offset()).await?
+    };
+
+    let wants_json = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"));
+    if wants_json {
+        Ok(search_json(&term, page, &hits, total).into_response())
+    } else {
+        Ok(search_html(&term, page, &hits, total).into_response())
+    }
+}
+
+/// `/api/search?…` link to another page of the same search.
+fn page_link(term: &str, page: i64, per_page: i64) -> String {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("q", term)
+        .append_pair("page", &page.to_string())
+        .append_pair("per_page", &per_page.to_string())
+        .finish();
+    format!("/api/search?{}", query)
+}
+
+/// HTML-escape a snippet and wrap its highlighted matches in `<mark>`.
+fn highlight(snippet: &str) -> String {
+    let mut escaped = String::new();
+    let _ = maud::Escaper::new(&mut escaped).write_str(snippet);
+    escaped
+        .replace(HIGHLIGHT_START, "<mark>")
+        .replace(HIGHLIGHT_END, "</mark>")
+}
+
+fn search_json(term: &str, page: Page, hits: &[SearchHit], total: i64) -> Json<serde_json::Value> {
+    let results: Vec<serde_json::Value> = hits.iter().map(|hit| {
+        let z = &hit.zorb;
+        json!({
+            "name": z.name,
+            "version": z.version,
+            "description": z.description,
+            "license": z.license,
+            "downloads": z.downloads,
+            "yanked": z.yanked,
+            "keywords": z.keywords,
+            "categories": z.categories,
+            "score": hit.score,
+            "snippet": hit.snippet.as_deref().map(highlight)
+        })
+    }).collect();
+    let pages = page.count(total);
+    Json(json!({
+        "query": term,
+        "results": results,
+        "total": total,
+        "page": page.number,
+        "per_page": page.size,
+        "pages": pages,
+        "next": (page.number < pages).then(|| page_link(term, page.number + 1, page.size)),
+        "prev": (page.number > 1).then(|| page_link(term, (page.number - 1).min(pages.max(1)), page.size))
+    }))
+}
+
+fn search_html(term: &str, page: Page, hits: &[SearchHit], total: i64) -> Markup {
+    let pages = page.count(total);
+    html! {
+        div class="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 xl:grid-cols-4 gap-6" {
+            @for hit in hits {
+                @let zorb = &hit.zorb;
+                a href=(format!("/{}", zorb.name)) class="block h-full" {
+                    div class="zorb-card bg-zinc-900 border border-zinc-800 rounded-3xl p-8 h-full flex flex-col" {
+                        div class="flex-1 flex justify-between items-start" {
+                            div {
+                                span class="font-mono text-cyan-400" { (zorb.name) }
+                                @if let Some(snippet) = &hit.snippet {
+                                    p class="search-snippet text-zinc-400 mt-2 text-sm" { (PreEscaped(highlight(snippet))) }
+                                } @else {
+                                    p class="text-zinc-400 mt-2 text-sm" { (zorb.description.clone().unwrap_or_else(|| "No description".to_string())) }
+                                }
+                            }
+                            span class="text-xs bg-emerald-500/10 text-emerald-400 px-3 py-1 rounded-full whitespace-nowrap" { (zorb.version) }
+                        }
+                        div class="mt-8 text-xs text-zinc-500 flex gap-6" {
+                            span { "↓ " (zorb.downloads) }
+                            span { "★ " (zorb.downloads / 100) }
+                        }
+                    }
+                }
+            }
+        }
+        @if pages > 1 {
+            div class="flex justify-center items-center gap-4 mt-8 text-sm text-zinc-400" {
+                @if page.number > 1 {
+                    button hx-get=(page_link(term, page.number - 1, page.size)) hx-target="#search-results" class="px-4 py-2 bg-zinc-900 border border-zinc-800 rounded-2xl hover:text-cyan-400" { "← Previous" }
+                }
+                span { "Page " (page.number) " of " (pages) " · " (total) " packages" }
+                @if page.number < pages {
+                    button hx-get=(page_link(term, page.number + 1, page.size)) hx-target="#search-results" class="px-4 py-2 bg-zinc-900 border border-zinc-800 rounded-2xl hover:text-cyan-400" { "Next →" }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+// src/handlers/source.rs
+use axum::{Json, extract::{State, Path}, http::StatusCode};
+use axum_login::AuthSession;
+use maud::{html, Markup, PreEscaped};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use crate::archive;
+use crate::state::AppState;
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::manifest::Manifest;
+use crate::models::FileEntry;
+use crate::models::user::UserBackend;
+use crate::storage::Storage;
+use crate::utils;
+use crate::views;
+
+/// Largest file the source browser renders; bigger ones link to the download.
+const MAX_SOURCE_BYTES: i64 = 512 * 1024;
+
+pub async fn list_files(
+    Path((name, version)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<Value>> {
+    files_json(name, version, state).await
+}
+
+pub async fn list_files_scoped(
+    Path((scope, name, version)): Path<(String, String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<Value>> {
+    let full_name = format!("@{}/{}", scope, name);
+    files_json(full_name, version, state).await
+}
+
+async fn files_json(name: String, version: String, state: Arc<AppState>) -> AppResult<Json<Value>> {
+    let files = load_files(&state, &name, &version)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Version {} of '{}' not found", version, name)))?;
+    Ok(Json(json!({
+        "name": name,
+        "version": version,
+        "total_size": files.iter().map(|f| f.size).sum::<i64>(),
+        "files": files
+    })))
+}
+
+/// The file listing of a version, or `None` if the version does not exist.
+/// Versions published before listings were recorded get theirs read from the
+/// stored archive the first time it is asked for, and recorded then.
+async fn load_files(state: &AppState, name: &str, version: &str) -> AppResult<Option<Vec<FileEntry>>> {
+    let Some(checksum) = version_checksum(state, name, version).await? else {
+        return Ok(None);
+    };
+    let files = queries::version_files(&state.db, name, version).await?;
+    if !files.is_empty() {
+        return Ok(Some(files));
+    }
+    let Some(data) = stored_archive(state, checksum.as_deref()).await? else {
+        return Ok(Some(files));
+    };
+    let files = archive::inspect(&data, &archive::Limits::default()).map_err(|e| AppError::Internal(e.to_string()))?;
+    queries::record_version_files(&state.db, name, version, &files).await?;
+    Ok(Some(files))
+}
+
+pub async fn get_manifest(
+    Path((name, version)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<Value>> {
+    manifest_json(name, version, state).await
+}
+
+pub async fn get_manifest_scoped(
+    Path((scope, name, version)): Path<(String, String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<Value>> {
+    let full_name = format!("@{}/{}", scope, name);
+    manifest_json(full_name, version, state).await
+}
+
+async fn manifest_json(name: String, version: String, state: Arc<AppState>) -> AppResult<Json<Value>> {
+    let manifest = load_manifest(&state, &name, &version)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Version {} of '{}' not found", version, name)))?;
+    Ok(Json(json!({
+        "name": name,
+        "version": version,
+        "manifest": manifest
+    })))
+}
+
+/// The normalized manifest of a version (`Some(None)` if it was published
+/// without one), or `None` if the version does not exist. Like file listings,
+/// manifests of older versions are read from the archive on first request.
+async fn load_manifest(state: &AppState, name: &str, version: &str) -> AppResult<Option<Option<Manifest>>> {
+    let Some(manifest) = queries::version_manifest(&state.db, name, version).await? else {
+        return Ok(None);
+    };
+    if manifest.is_some() {
+        return Ok(Some(manifest));
+    }
+    let checksum = version_checksum(state, name, version).await?.flatten();
+    let Some(data) = stored_archive(state, checksum.as_deref()).await? else {
+        return Ok(Some(None));
+    };
+    // Archives that predate strict validation may not pass it; they have no manifest to serve
+    let Some(manifest) = utils::parse_zorb_toml(&data).ok().and_then(|parsed| parsed.manifest) else {
+        return Ok(Some(None));
+    };
+    queries::record_manifest(&state.db, name, version, &manifest).await?;
+    Ok(Some(Some(manifest)))
+}
+
+/// `Some(checksum)` for an existing version; the checksum itself may not be recorded.
+async fn version_checksum(state: &AppState, name: &str, version: &str) -> AppResult<Option<Option<String>>> {
+    Ok(sqlx::query_scalar("SELECT checksum FROM zorbs WHERE name = $1 AND version = $2")
+        .bind(name)
+        .bind(version)
+        .fetch_optional(&state.db)
+        .await?)
+}
+
+async fn stored_archive(state: &AppState, checksum: Option<&str>) -> AppResult<Option<Vec<u8>>> {
+    match checksum {
+        Some(checksum) => Ok(state.storage.get(&utils::blob_key(checksum)).await?),
+        None => Ok(None),
+    }
+}
+
+pub async fn source_root(
+    auth_session: AuthSession<UserBackend>,
+    Path((name, version)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<(StatusCode, Markup)> {
+    browse(auth_session, name, version, String::new(), state).await
+}
+
+pub async fn source_root_scoped(
+    auth_session: AuthSession<UserBackend>,
+    Path((scope, name, version)): Path<(String, String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<(StatusCode, Markup)> {
+    let full_name = format!("@{}/{}", scope, name);
+    browse(auth_session, full_name, version, String::new(), state).await
+}
+
+pub async fn source_file(
+    auth_session: AuthSession<UserBackend>,
+    Path((name, version, path)): Path<(String, String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<(StatusCode, Markup)> {
+    browse(auth_session, name, version, path, state).await
+}
+
+pub async fn source_file_scoped(
+    auth_session: AuthSession<UserBackend>,
+    Path((scope, name, version, path)): Path<(String, String, String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<(StatusCode, Markup)> {
+    let full_name = format!("@{}/{}", scope, name);
+    browse(auth_session, full_name, version, path, state).await
+}
+
+/// A file rendered with line anchors (`#L12`), or the contents of a directory.
+async fn browse(auth_session: AuthSession<UserBackend>, name: String, version: String, path: String, state: Arc<AppState>) -> AppResult<(StatusCode, Markup)> {
+    let not_found = || (StatusCode::NOT_FOUND, html! { (PreEscaped(include_str!("../views/404.html"))) });
+    let path = path.trim_matches('/');
+    let Some(files) = load_files(&state, &name, &version).await? else {
+        return Ok(not_found());
+    };
+    let base = format!("/{}/{}/source", name, version);
+    let download_url = format!("/{}/{}/download", name, version);
+
+    let body = if let Some(file) = files.iter().find(|f| f.path == path) {
+        let content = if file.size > MAX_SOURCE_BYTES {
+            None
+        } else {
+            Some(file_contents(&state, &name, &version, path).await?)
+        };
+        file_view(file, content.as_deref().map(Vec::as_slice), &download_url)
+    } else {
+        let prefix = if path.is_empty() { String::new() } else { format!("{}/", path) };
+        let children = directory(&files, &prefix);
+        if children.is_empty() && !path.is_empty() {
+            return Ok(not_found());
+        }
+        directory_view(&children, &base, &prefix)
+    };
+
+    // Each path component links to its directory
+    let mut crumbs = Vec::new();
+    let mut at = base.clone();
+    for part in path.split('/').filter(|p| !p.is_empty()) {
+        at = format!("{}/{}", at, part);
+        crumbs.push((at.clone(), part));
+    }
+    let content = html! {
+        div class="text-sm text-zinc-500 font-mono break-all" {
+            a href=(format!("/{}", name)) class="hover:text-cyan-400" { (name) }
+            " " (version) " / "
+            a href=(base) class="hover:text-cyan-400" { "source" }
+            @for (href, part) in &crumbs {
+                " / "
+                a href=(href) class="hover:text-cyan-400" { (part) }
+            }
+        }
+        div class="mt-8" { (body) }
+    };
+    let title = if path.is_empty() { format!("{} {}", name, version) } else { format!("{} — {} {}", path, name, version) };
+    Ok((StatusCode::OK, render(auth_session, &title, content)))
+}
+
+/// The contents of `path` in the archive of `name@version`. Reading them means
+/// decompressing the whole archive, so they are kept in the source cache.
+async fn file_contents(state: &AppState, name: &str, version: &str, path: &str) -> AppResult<Arc<Vec<u8>>> {
+    let missing = || AppError::NotFound(format!("Archive of {}@{} is missing from storage", name, version));
+    let checksum = version_checksum(state, name, version).await?.flatten().ok_or_else(missing)?;
+    if let Some(content) = state.source_cache.get(&checksum, path) {
+        return Ok(content);
+    }
+    let data = stored_archive(state, Some(&checksum)).await?.ok_or_else(missing)?;
+    let content = archive::read_file(&data, path)
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("{} is missing from the archive of {}@{}", path, name, version)))?;
+    Ok(state.source_cache.insert(&checksum, path, content))
+}
+
+/// The entries directly under `prefix`: subdirectories (`None`) and files.
+fn directory<'a>(files: &'a [FileEntry], prefix: &str) -> BTreeMap<(bool, &'a str), Option<&'a FileEntry>> {
+    let mut children = BTreeMap::new();
+    for file in files {
+        let Some(rest) = file.path.strip_prefix(prefix) else { continue };
+        match rest.split_once('/') {
+            // Directories sort before files
+            Some((dir, _)) => { children.insert((false, dir), None); }
+            None => { children.insert((true, rest), Some(file)); }
+        }
+    }
+    children
+}
+
+fn directory_view(children: &BTreeMap<(bool, &str), Option<&FileEntry>>, base: &str, prefix: &str) -> Markup {
+    html! {
+        div class="bg-zinc-900 border border-zinc-800 rounded-3xl overflow-hidden" {
+            table class="w-full text-sm" {
+                tbody class="divide-y divide-zinc-800" {
+                    @for ((_, entry), file) in children {
+                        tr class="hover:bg-zinc-800 transition" {
+                            td class="px-6 py-3 font-mono" {
+                                a href=(format!("{}/{}{}", base, prefix, entry)) class="text-cyan-400 hover:text-cyan-300 flex items-center gap-3" {
+                                    @if file.is_some() {
+                                        i class="fa-regular fa-file text-zinc-500" {}
+                                    } @else {
+                                        i class="fa-solid fa-folder text-zinc-500" {}
+                                    }
+                                    (entry)
+                                }
+                            }
+                            @if let Some(file) = file {
+                                td class="px-6 py-3 text-zinc-500 font-mono text-right" { (format!("{:04o}", file.mode)) }
+                                td class="px-6 py-3 text-zinc-400 text-right whitespace-nowrap" { (human_size(file.size)) }
+                            } @else {
+                                td {}
+                                td {}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `content` is `None` when the file is too large to show.
+fn file_view(file: &FileEntry, content: Option<&[u8]>, download_url: &str) -> Markup {
+    let text = content.filter(|c| !c.contains(&0)).and_then(|c| std::str::from_utf8(c).ok());
+    html! {
+        div class="flex justify-between items-center text-sm text-zinc-500 mb-4" {
+            span class="font-mono" { (format!("{:04o}", file.mode)) " · " (human_size(file.size)) }
+            a href=(download_url) class="text-cyan-400 hover:text-cyan-300" { "Download archive" }
+        }
+        @match (text, &content) {
+            (Some(text), _) => {
+                @let text = text.strip_suffix('\n').unwrap_or(text);
+                div class="flex font-mono text-sm leading-6 bg-zinc-950 border border-zinc-800 rounded-2xl overflow-hidden" {
+                    div class="line-numbers select-none text-right text-zinc-600 py-4 px-4 border-r border-zinc-800" {
+                        @for n in 1..=text.split('\n').count() {
+                            a id=(format!("L{}", n)) href=(format!("#L{}", n)) class="block hover:text-cyan-400" { (n) }
+                        }
+                    }
+                    pre class="flex-1 overflow-x-auto py-4 px-4" {
+                        code class=(format!("language-{}", language(&file.path))) { (text) }
+                    }
+                }
+            }
+            (None, Some(_)) => p class="text-zinc-400" { "Binary file, not shown." },
+            (None, None) => p class="text-zinc-400" { "This file is too large to show; download the archive to read it." },
+        }
+    }
+}
+
+/// highlight.js language for a file, by name and extension.
+fn language(path: &str) -> &'static str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let extension = name.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        // Zeta reads like Rust, which highlights it well
+        "z" | "zeta" | "rs" => "rust",
+        "toml" | "ini" | "cfg" => "ini",
+        "md" | "markdown" => "markdown",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "sh" | "bash" => "bash",
+        "js" | "mjs" => "javascript",
+        "ts" => "typescript",
+        "py" => "python",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "hpp" => "cpp",
+        "html" | "xml" | "svg" => "xml",
+        "css" => "css",
+        "sql" => "sql",
+        _ if name == "Makefile" => "makefile",
+        _ => "plaintext",
+    }
+}
+
+fn human_size(bytes: i64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
+
+fn render(auth_session: AuthSession<UserBackend>, title: &str, content: Markup) -> Markup {
+    let user = &auth_session.user;
+    let auth_markup = if let Some(user) = user {
+        html! {
+            div class="flex items-center gap-6" {
+                span class="text-sm font-medium text-zinc-300" { "@" (user.username) }
+                a href="/auth/logout" class="px-6 py-3 bg-red-500/10 hover:bg-red-500/20 text-red-400 font-medium rounded-2xl transition-all" {
+                    "Logout"
+                }
+            }
+        }
+    } else {
+        html! {
+            button onclick="openLoginModal()" class="px-8 py-3 bg-white text-black font-semibold rounded-2xl hover:bg-cyan-400 hover:text-black transition-all flex items-center gap-2" {
+                "Sign in"
+                i class="fa-solid fa-right-to-bracket" {}
+            }
+        }
+    };
+    let mut html_str = views::SOURCE_HTML.to_string();
+    let auth_str = auth_markup.into_string();
+    if let Some(pos) = html_str.find("<!-- AUTH_SLOT -->") {
+        html_str.replace_range(pos..pos + "<!-- AUTH_SLOT -->".len(), &auth_str);
+    }
+    if let Some(pos) = html_str.find("<!-- AUTH_SLOT_MOBILE -->") {
+        html_str.replace_range(pos..pos + "<!-- AUTH_SLOT_MOBILE -->".len(), &auth_str);
+    }
+    html_str = html_str.replace("{{title}}", &html! { (title) }.into_string());
+    html_str = html_str.replace("<!-- SOURCE_CONTENT -->", &content.into_string());
+    html! { (PreEscaped(html_str)) }
+}
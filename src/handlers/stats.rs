@@ -0,0 +1,88 @@
+// src/handlers/stats.rs
+use axum::{Json, extract::{State, Path, Query}};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use crate::state::AppState;
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::models::downloads::{daily_totals, DEFAULT_SERIES_DAYS, MAX_SERIES_DAYS};
+
+#[derive(Deserialize)]
+pub struct SeriesParams {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+pub async fn download_series(
+    Path(name): Path<String>,
+    Query(params): Query<SeriesParams>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<Value>> {
+    series(name, params, state).await
+}
+
+pub async fn download_series_scoped(
+    Path((scope, name)): Path<(String, String)>,
+    Query(params): Query<SeriesParams>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<Value>> {
+    let full_name = format!("@{}/{}", scope, name);
+    series(full_name, params, state).await
+}
+
+fn parse_date(field: &str, value: Option<&str>) -> AppResult<Option<NaiveDate>> {
+    value
+        .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d")
+            .map_err(|_| AppError::BadRequest(format!("{} must be a date like 2026-01-31", field))))
+        .transpose()
+}
+
+/// Daily downloads between `from` and `to` (inclusive, UTC), overall and for each
+/// version that was downloaded in that range. Defaults to the last 90 days.
+async fn series(name: String, params: SeriesParams, state: Arc<AppState>) -> AppResult<Json<Value>> {
+    let to = parse_date("to", params.to.as_deref())?.unwrap_or_else(|| Utc::now().date_naive());
+    let from = parse_date("from", params.from.as_deref())?
+        .unwrap_or(to - Duration::days(DEFAULT_SERIES_DAYS - 1));
+    if from > to {
+        return Err(AppError::BadRequest("from must not be after to".to_string()));
+    }
+    if (to - from).num_days() >= MAX_SERIES_DAYS {
+        return Err(AppError::BadRequest(format!("At most {} days can be requested at once", MAX_SERIES_DAYS)));
+    }
+    if !queries::package_exists(&state.db, &name).await? {
+        return Err(AppError::NotFound(format!("Package '{}' not found", name)));
+    }
+
+    let days = queries::download_series(&state.db, &name, from, to).await?;
+    let mut versions: Vec<&str> = Vec::new();
+    for day in &days {
+        if !versions.contains(&day.version.as_str()) {
+            versions.push(&day.version);
+        }
+    }
+    // Newest version first; anything that is not semver sorts last
+    versions.sort_by(|a, b| semver::Version::parse(b).ok().cmp(&semver::Version::parse(a).ok()));
+
+    let to_json = |series: Vec<(NaiveDate, i64)>| -> Vec<Value> {
+        series.into_iter().map(|(date, downloads)| json!({ "date": date, "downloads": downloads })).collect()
+    };
+    let versions: Vec<Value> = versions.into_iter().map(|version| {
+        let own = days.iter().filter(|d| d.version == version);
+        json!({
+            "version": version,
+            "total": own.clone().map(|d| d.downloads).sum::<i64>(),
+            "daily": to_json(daily_totals(own, from, to))
+        })
+    }).collect();
+
+    Ok(Json(json!({
+        "name": name,
+        "from": from,
+        "to": to,
+        "total": days.iter().map(|d| d.downloads).sum::<i64>(),
+        "daily": to_json(daily_totals(&days, from, to)),
+        "versions": versions
+    })))
+}
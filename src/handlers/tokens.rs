@@ -0,0 +1,162 @@
+// src/handlers/tokens.rs
+use axum::{
+    Json,
+    extract::{FromRequestParts, Path, State},
+    http::{StatusCode, header, request::Parts},
+    response::IntoResponse,
+};
+use axum_login::AuthSession;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+use crate::state::AppState;
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::models::{ApiToken, TokenScope};
+use crate::models::token;
+use crate::models::user::UserBackend;
+
+const DEFAULT_TOKEN_DAYS: i64 = 90;
+const MAX_TOKEN_DAYS: i64 = 365;
+const MAX_TOKEN_NAME_LEN: usize = 64;
+
+/// The authenticated caller of an API route: either a logged-in browser session
+/// or a `Authorization: Bearer zorb_...` API token.
+pub struct Caller {
+    pub user_id: Uuid,
+    /// `None` for session callers, which implicitly hold every scope.
+    pub token: Option<ApiToken>,
+}
+
+impl Caller {
+    pub fn require_scope(&self, scope: TokenScope) -> AppResult<()> {
+        match &self.token {
+            Some(t) if !t.has_scope(scope) => Err(AppError::Forbidden(
+                format!("API token is missing the '{}' scope", scope.as_str()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Require that the caller owns `name`.
+    pub async fn require_owner(&self, state: &AppState, name: &str) -> AppResult<()> {
+        if queries::is_owner(&state.db, name, self.user_id).await? {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("You are not an owner of '{}'", name)))
+        }
+    }
+
+    /// Require that the caller is a registry admin.
+    pub async fn require_admin(&self, state: &AppState) -> AppResult<()> {
+        if queries::is_admin(&state.db, self.user_id).await? {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden("Only registry admins can do this".to_string()))
+        }
+    }
+}
+
+impl FromRequestParts<Arc<AppState>> for Caller {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let unauthorized = |msg: &str| AppError::Unauthorized(msg.to_string());
+
+        if let Some(value) = parts.headers.get(header::AUTHORIZATION) {
+            let raw = value.to_str().unwrap_or_default();
+            let Some(plaintext) = raw.strip_prefix("Bearer ").map(str::trim) else {
+                return Err(unauthorized("Authorization header must use the Bearer scheme"));
+            };
+            let found = queries::use_api_token(&state.db, &token::hash_token(plaintext)).await?;
+            return match found {
+                Some(t) if t.is_active() => Ok(Caller { user_id: t.user_id, token: Some(t) }),
+                Some(_) => Err(unauthorized("API token is expired or revoked")),
+                None => Err(unauthorized("Invalid API token")),
+            };
+        }
+
+        let auth_session = AuthSession::<UserBackend>::from_request_parts(parts, state)
+            .await
+            .map_err(|(_, msg)| AppError::Internal(msg.to_string()))?;
+        match auth_session.user {
+            Some(user) => Ok(Caller { user_id: user.id, token: None }),
+            None => Err(unauthorized("Authentication required: sign in or send an API token")),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateTokenRequest {
+    name: String,
+    scopes: Vec<String>,
+    expires_in_days: Option<i64>,
+}
+
+fn session_user(auth_session: AuthSession<UserBackend>) -> AppResult<crate::models::User> {
+    auth_session.user.ok_or_else(|| AppError::Unauthorized("Sign in to manage API tokens".to_string()))
+}
+
+/// List the signed-in user's API tokens (hashes are never returned).
+pub async fn list_tokens(
+    auth_session: AuthSession<UserBackend>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<impl IntoResponse> {
+    let user = session_user(auth_session)?;
+    let tokens = queries::list_api_tokens(&state.db, user.id).await?;
+    Ok(Json(json!({"tokens": tokens})))
+}
+
+/// Create a named, scoped token. The plaintext is returned exactly once.
+pub async fn create_token(
+    auth_session: AuthSession<UserBackend>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateTokenRequest>,
+) -> AppResult<impl IntoResponse> {
+    let user = session_user(auth_session)?;
+    let name = payload.name.trim();
+    if name.is_empty() || name.len() > MAX_TOKEN_NAME_LEN {
+        return Err(AppError::BadRequest(format!("Token name must be 1-{} characters", MAX_TOKEN_NAME_LEN)));
+    }
+    if payload.scopes.is_empty() {
+        return Err(AppError::BadRequest("At least one scope is required".to_string()));
+    }
+    let mut scopes: Vec<String> = Vec::new();
+    for s in &payload.scopes {
+        match TokenScope::parse(s) {
+            Some(scope) if !scopes.iter().any(|x| x == scope.as_str()) => scopes.push(scope.as_str().to_string()),
+            Some(_) => {}
+            None => return Err(AppError::BadRequest(format!("Unknown scope '{}'", s))),
+        }
+    }
+    let days = payload.expires_in_days.unwrap_or(DEFAULT_TOKEN_DAYS);
+    if !(1..=MAX_TOKEN_DAYS).contains(&days) {
+        return Err(AppError::BadRequest(format!("expires_in_days must be between 1 and {}", MAX_TOKEN_DAYS)));
+    }
+    let expires_at = Utc::now() + Duration::days(days);
+
+    let plaintext = token::generate_token();
+    let created = queries::create_api_token(&state.db, user.id, name, &token::hash_token(&plaintext), &scopes, Some(expires_at)).await?;
+    Ok((StatusCode::CREATED, Json(json!({
+        "id": created.id,
+        "name": created.name,
+        "scopes": created.scopes,
+        "expires_at": created.expires_at,
+        "token": plaintext,
+        "message": "Store this token now; it will not be shown again."
+    }))))
+}
+
+pub async fn revoke_token(
+    auth_session: AuthSession<UserBackend>,
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<impl IntoResponse> {
+    let user = session_user(auth_session)?;
+    if !queries::revoke_api_token(&state.db, user.id, id).await? {
+        return Err(AppError::NotFound("Token not found".to_string()));
+    }
+    Ok(Json(json!({"success": true, "id": id})))
+}
@@ -0,0 +1,204 @@
+// src/handlers/webhooks.rs
+//
+// Package webhooks are managed by the package's owners; registry-wide webhooks,
+// which receive events of every package, by registry admins.
+use axum::{Json, extract::{Path, State}, response::IntoResponse, http::StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+use crate::state::AppState;
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::handlers::tokens::Caller;
+use crate::models::{webhook, TokenScope, WebhookEvent};
+use crate::webhooks;
+
+/// Entries returned by the delivery log.
+const DELIVERY_LOG_LIMIT: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct CreateWebhookRequest {
+    url: String,
+    /// Every event when omitted.
+    events: Option<Vec<String>>,
+}
+
+/// Same rule as owner management: a session, or a token with `publish-update`.
+/// `package` is `None` for registry-wide webhooks, which need an admin.
+async fn authorize(state: &AppState, caller: &Caller, package: Option<&str>) -> AppResult<()> {
+    caller.require_scope(TokenScope::PublishUpdate)?;
+    match package {
+        Some(name) => caller.require_owner(state, name).await,
+        None => caller.require_admin(state).await,
+    }
+}
+
+async fn validate_url(raw: &str) -> AppResult<String> {
+    let url = url::Url::parse(raw.trim())
+        .map_err(|e| AppError::BadRequest(format!("Invalid webhook URL '{}': {}", raw, e)))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host().is_none() {
+        return Err(AppError::BadRequest(format!("Webhook URL '{}' must be an http or https URL", raw)));
+    }
+    webhooks::check_destination(url.as_str()).await
+        .map_err(|e| AppError::BadRequest(format!("Webhook URL '{}' is not allowed: {}", raw, e)))?;
+    Ok(url.to_string())
+}
+
+fn validate_events(events: Option<Vec<String>>) -> AppResult<Vec<String>> {
+    let Some(events) = events else {
+        return Ok(WebhookEvent::ALL.iter().map(|e| e.as_str().to_string()).collect());
+    };
+    if events.is_empty() {
+        return Err(AppError::BadRequest("A webhook needs at least one event".to_string()));
+    }
+    let mut valid: Vec<String> = Vec::new();
+    for event in events {
+        let Some(parsed) = WebhookEvent::parse(&event) else {
+            let known: Vec<&str> = WebhookEvent::ALL.iter().map(|e| e.as_str()).collect();
+            return Err(AppError::BadRequest(format!("Unknown event '{}'; expected one of: {}", event, known.join(", "))));
+        };
+        if !valid.iter().any(|e| e == parsed.as_str()) {
+            valid.push(parsed.as_str().to_string());
+        }
+    }
+    Ok(valid)
+}
+
+async fn list(package: Option<String>, caller: Caller, state: Arc<AppState>) -> AppResult<Json<Value>> {
+    authorize(&state, &caller, package.as_deref()).await?;
+    let webhooks = queries::list_webhooks(&state.db, package.as_deref()).await?;
+    Ok(Json(json!({"package": package, "webhooks": webhooks})))
+}
+
+/// Register a webhook. The signing secret is only ever returned here.
+async fn create(package: Option<String>, caller: Caller, state: Arc<AppState>, payload: CreateWebhookRequest) -> AppResult<(StatusCode, Json<Value>)> {
+    authorize(&state, &caller, package.as_deref()).await?;
+    let events = validate_events(payload.events)?;
+    let url = validate_url(&payload.url).await?;
+    let secret = webhook::generate_secret();
+    let hook = queries::create_webhook(&state.db, package.as_deref(), &url, &secret, &events, caller.user_id).await?;
+    Ok((StatusCode::CREATED, Json(json!({
+        "id": hook.id,
+        "package": hook.package,
+        "url": hook.url,
+        "events": hook.events,
+        "created_at": hook.created_at,
+        "secret": secret,
+        "message": "Store the secret now: it signs every delivery and will not be shown again",
+    }))))
+}
+
+async fn remove(package: Option<String>, id: Uuid, caller: Caller, state: Arc<AppState>) -> AppResult<Json<Value>> {
+    authorize(&state, &caller, package.as_deref()).await?;
+    if !queries::delete_webhook(&state.db, package.as_deref(), id).await? {
+        return Err(AppError::NotFound("Webhook not found".to_string()));
+    }
+    Ok(Json(json!({"success": true, "id": id})))
+}
+
+/// The most recent deliveries of a webhook, newest first.
+async fn deliveries(package: Option<String>, id: Uuid, caller: Caller, state: Arc<AppState>) -> AppResult<Json<Value>> {
+    authorize(&state, &caller, package.as_deref()).await?;
+    let Some(deliveries) = queries::webhook_deliveries(&state.db, package.as_deref(), id, DELIVERY_LOG_LIMIT).await? else {
+        return Err(AppError::NotFound("Webhook not found".to_string()));
+    };
+    Ok(Json(json!({"webhook_id": id, "deliveries": deliveries})))
+}
+
+pub async fn list_webhooks(
+    caller: Caller,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    list(Some(name), caller, state).await
+}
+
+pub async fn list_webhooks_scoped(
+    caller: Caller,
+    Path((scope, name)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    list(Some(format!("@{}/{}", scope, name)), caller, state).await
+}
+
+pub async fn list_global_webhooks(
+    caller: Caller,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    list(None, caller, state).await
+}
+
+pub async fn create_webhook(
+    caller: Caller,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> impl IntoResponse {
+    create(Some(name), caller, state, payload).await
+}
+
+pub async fn create_webhook_scoped(
+    caller: Caller,
+    Path((scope, name)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> impl IntoResponse {
+    create(Some(format!("@{}/{}", scope, name)), caller, state, payload).await
+}
+
+pub async fn create_global_webhook(
+    caller: Caller,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> impl IntoResponse {
+    create(None, caller, state, payload).await
+}
+
+pub async fn delete_webhook(
+    caller: Caller,
+    Path((name, id)): Path<(String, Uuid)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    remove(Some(name), id, caller, state).await
+}
+
+pub async fn delete_webhook_scoped(
+    caller: Caller,
+    Path((scope, name, id)): Path<(String, String, Uuid)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    remove(Some(format!("@{}/{}", scope, name)), id, caller, state).await
+}
+
+pub async fn delete_global_webhook(
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    remove(None, id, caller, state).await
+}
+
+pub async fn webhook_deliveries(
+    caller: Caller,
+    Path((name, id)): Path<(String, Uuid)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    deliveries(Some(name), id, caller, state).await
+}
+
+pub async fn webhook_deliveries_scoped(
+    caller: Caller,
+    Path((scope, name, id)): Path<(String, String, Uuid)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    deliveries(Some(format!("@{}/{}", scope, name)), id, caller, state).await
+}
+
+pub async fn global_webhook_deliveries(
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    deliveries(None, id, caller, state).await
+}
@@ -0,0 +1,54 @@
+// src/handlers/yank.rs
+use axum::{Json, extract::{Path, State}, response::IntoResponse};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use crate::state::AppState;
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::handlers::tokens::Caller;
+use crate::models::{TokenScope, WebhookEvent};
+use crate::webhooks;
+
+/// Yank (`yanked = true`) or restore a single version. Only owners may do either;
+/// API tokens additionally need the `yank` scope.
+async fn set_yanked(name: String, version: String, yanked: bool, caller: Caller, state: Arc<AppState>) -> AppResult<Json<Value>> {
+    caller.require_scope(TokenScope::Yank)?;
+    caller.require_owner(&state, &name).await?;
+    if !queries::set_yanked(&state.db, &name, &version, yanked).await? {
+        return Err(AppError::NotFound(format!("Version {} of '{}' not found", version, name)));
+    }
+    webhooks::notify(&state.db, WebhookEvent::Yanked, &name, json!({"version": version, "yanked": yanked})).await;
+    Ok(Json(json!({"success": true, "name": name, "version": version, "yanked": yanked})))
+}
+
+pub async fn yank(
+    caller: Caller,
+    Path((name, version)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    set_yanked(name, version, true, caller, state).await
+}
+
+pub async fn yank_scoped(
+    caller: Caller,
+    Path((scope, name, version)): Path<(String, String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    set_yanked(format!("@{}/{}", scope, name), version, true, caller, state).await
+}
+
+pub async fn unyank(
+    caller: Caller,
+    Path((name, version)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    set_yanked(name, version, false, caller, state).await
+}
+
+pub async fn unyank_scoped(
+    caller: Caller,
+    Path((scope, name, version)): Path<(String, String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    set_yanked(format!("@{}/{}", scope, name), version, false, caller, state).await
+}
@@ -1,15 +1,24 @@
 // lib.rs — Zorbs registry library (crate root for all source modules)
 
+pub mod archive;
+pub mod blobs;
+pub mod categories;
 pub mod config;
 pub mod db;
+pub mod downloads;
 pub mod error;
 pub mod handlers;
+pub mod manifest;
 pub mod models;
+pub mod resolver;
 pub mod routes;
+pub mod s3;
+pub mod source_cache;
 pub mod state;
 pub mod utils;
 pub mod views;
 pub mod storage;
+pub mod webhooks;
 
 use axum::Router;
 use std::sync::Arc;
@@ -1,18 +1,184 @@
 // main.rs — Zorbs registry server binary (thin wrapper around library)
 
-use zorbs::{build_app, config, db, state};
+use clap::{Parser, Subcommand};
+use zorbs::{blobs, build_app, config, db, state, storage, webhooks};
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the registry (the default)
+    Serve,
+    /// Copy every archive the registry references from one storage backend to another
+    MigrateStorage {
+        /// Where blobs are now: `local:<dir>`, or `s3` for the bucket configured by R2_*
+        #[arg(long)]
+        from: String,
+        /// Where blobs should go, in the same form as --from
+        #[arg(long)]
+        to: String,
+        /// Read and verify the source, but write nothing
+        #[arg(long)]
+        dry_run: bool,
+        /// Also hash blobs already in the target and replace any that do not match
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Make an account a registry admin, who manages registry-wide webhooks
+    Admin {
+        /// Username the account signed in with
+        username: String,
+        /// Sign-in provider (github, google, twitter or passkey), when several accounts share the username
+        #[arg(long)]
+        provider: Option<String>,
+        /// Take admin rights away instead
+        #[arg(long)]
+        revoke: bool,
+    },
+}
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
+    match Cli::parse().command.unwrap_or(Command::Serve) {
+        Command::Serve => serve().await,
+        Command::MigrateStorage { from, to, dry_run, verify } => {
+            let clean = migrate_storage(&from, &to, dry_run, verify).await;
+            std::process::exit(if clean { 0 } else { 1 });
+        }
+        Command::Admin { username, provider, revoke } => {
+            let done = set_admin(&username, provider.as_deref(), !revoke).await;
+            std::process::exit(if done { 0 } else { 1 });
+        }
+    }
+}
+
+async fn serve() {
     let app_state = state::new();
     db::run_migrations(&app_state.db).await;
 
-    let app = build_app(app_state);
+    // Move archives stored before keys were content-addressed; a no-op once done.
+    // It reads every queued blob, so it runs behind the listener rather than before
+    // it; versions still queued answer 404 on download until their blob is moved.
+    let (pool, storage) = (app_state.db.clone(), app_state.storage.clone());
+    tokio::spawn(async move {
+        match blobs::migrate_legacy_blobs(&pool, &storage).await {
+            Ok(report) if report.total() > 0 => tracing::info!(
+                "Moved {} legacy blobs ({} missing, {} overwritten by colliding packages)",
+                report.moved, report.missing, report.mismatched
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::error!("Legacy blob migration stopped, will resume on next start: {}", e),
+        }
+    });
+
+    app_state.downloads.spawn_flusher(app_state.db.clone(), config::download_flush_interval());
+    webhooks::spawn_worker(app_state.db.clone(), config::webhook_poll_interval());
+
+    let app = build_app(app_state.clone());
 
     let listener = tokio::net::TcpListener::bind(config::addr())
         .await
         .expect("Failed to bind");
     tracing::info!("🚀 Zorbs registry listening on {}", config::addr());
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+
+    // Write out downloads counted since the last flush before exiting
+    match app_state.downloads.flush(&app_state.db).await {
+        Ok(n) => tracing::info!("Flushed {} buffered downloads", n),
+        Err(e) => tracing::error!("Lost {} buffered downloads: {}", app_state.downloads.pending(), e),
+    }
+}
+
+/// Copy blobs between backends and print what happened; false if any blob was
+/// missing, corrupt or failed to copy.
+async fn migrate_storage(from: &str, to: &str, dry_run: bool, verify: bool) -> bool {
+    let (source, target) = match (storage::from_spec(from), storage::from_spec(to)) {
+        (Ok(source), Ok(target)) => (source, target),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    };
+    let pool = sqlx::PgPool::connect(&config::database_url())
+        .await
+        .expect("Failed to connect to the database");
+    db::run_migrations(&pool).await;
+
+    // Archives still under name-derived keys must be moved within the source first
+    if !dry_run {
+        if let Err(e) = blobs::migrate_legacy_blobs(&pool, &source).await {
+            eprintln!("Moving legacy blobs in {} failed: {}", from, e);
+            return false;
+        }
+    }
+    let report = match blobs::copy_blobs(&pool, &source, &target, dry_run, verify).await {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Storage migration failed: {}", e);
+            return false;
+        }
+    };
+
+    let verb = if dry_run { "Would copy" } else { "Copied" };
+    println!("{} {} blobs from {} to {}; {} already present", verb, report.copied, from, to, report.already_present);
+    if report.unaddressed > 0 {
+        println!("{} versions still use name-derived keys and were skipped; run without --dry-run to move them first", report.unaddressed);
+    }
+    for (label, problems) in [("Missing", &report.missing), ("Corrupt", &report.corrupt), ("Failed", &report.failed)] {
+        for problem in problems {
+            println!("{}: {} ({}): {}", label, problem.checksum, problem.versions.join(", "), problem.detail);
+        }
+    }
+    report.is_clean()
+}
+
+async fn set_admin(username: &str, provider: Option<&str>, admin: bool) -> bool {
+    let pool = sqlx::PgPool::connect(&config::database_url())
+        .await
+        .expect("Failed to connect to the database");
+    db::run_migrations(&pool).await;
+    let providers = match db::queries::set_admin(&pool, username, provider, admin).await {
+        Ok(providers) => providers,
+        Err(e) => {
+            eprintln!("Updating {} failed: {}", username, e);
+            return false;
+        }
+    };
+    match providers.as_slice() {
+        [] => eprintln!("No account is called {}", username),
+        [provider] if admin => println!("{} ({}) is now a registry admin", username, provider),
+        [provider] => println!("{} ({}) is no longer a registry admin", username, provider),
+        _ => eprintln!("Several accounts are called {}; pick one with --provider {}", username, providers.join("|")),
+    }
+    providers.len() == 1
+}
+
+/// Resolves on Ctrl-C or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("Failed to listen for Ctrl-C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("Shutting down");
 }
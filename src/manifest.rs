@@ -0,0 +1,398 @@
+// src/manifest.rs — the Zorbfile (`zorb.toml`), as specified in ZORBFILE.md
+//
+// [`Manifest::parse`] reads every section of the spec into typed fields and
+// rejects values of the wrong shape with the key that holds them. Keys the spec
+// does not define are not errors, so newer tools can add them, but each one is
+// reported as a warning. The parsed manifest is stored per version as JSON in
+// the normalized form below: every dependency is a table and every optional list
+// is present, so readers never need to handle the shorthand forms.
+use crate::categories;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use toml::{Table, Value};
+
+/// Editions this registry understands.
+pub const EDITIONS: &[&str] = &["2026"];
+/// Assumed when a manifest does not name its edition.
+pub const DEFAULT_EDITION: &str = "2026";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Manifest {
+    pub package: Package,
+    pub dependencies: BTreeMap<String, Dependency>,
+    pub dev_dependencies: BTreeMap<String, Dependency>,
+    /// Feature name to the features, `dep:name` optional dependencies and
+    /// `name/feature` dependency features it enables.
+    pub features: BTreeMap<String, Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fmt: Option<Fmt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lint: Option<Lint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build: Option<Build>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<Workspace>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub edition: String,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub repository: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Dependency {
+    /// Version requirement; `None` only for `git` and `path` dependencies.
+    pub version: Option<String>,
+    pub features: Vec<String>,
+    pub default_features: bool,
+    pub optional: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Fmt {
+    pub style: Option<String>,
+    pub max_width: Option<u32>,
+    pub use_tabs: Option<bool>,
+    pub indent_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lint {
+    pub tool: Option<String>,
+    pub level: Option<String>,
+    pub deny: Vec<String>,
+    pub warn: Vec<String>,
+    pub allow: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Build {
+    pub script: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub members: Vec<String>,
+}
+
+impl Dependency {
+    /// `name = "req"`: a registry dependency with default features.
+    pub fn registry(req: String) -> Dependency {
+        Dependency {
+            version: Some(req),
+            features: Vec::new(),
+            default_features: true,
+            optional: false,
+            git: None,
+            branch: None,
+            rev: None,
+            path: None,
+        }
+    }
+}
+
+/// A manifest that passed validation, and what was questionable about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedManifest {
+    pub manifest: Manifest,
+    pub warnings: Vec<String>,
+}
+
+impl Manifest {
+    /// Parse and validate a `zorb.toml`. Errors name the offending key.
+    pub fn parse(source: &str) -> Result<ParsedManifest, String> {
+        let root: Table = toml::from_str(source).map_err(|e| format!("Failed to parse zorb.toml: {}", e))?;
+        let mut warnings = Vec::new();
+        unknown_keys(&root, "", &["package", "dependencies", "dev-dependencies", "features", "fmt", "lint", "build", "workspace"], &mut warnings);
+
+        let package = match root.get("package") {
+            Some(Value::Table(t)) => package(t, &mut warnings)?,
+            Some(_) => return Err("`package` in zorb.toml must be a table".to_string()),
+            None => return Err("Missing [package] section in zorb.toml".to_string()),
+        };
+        let dependencies = dependency_table(&root, "dependencies", &mut warnings)?;
+        let dev_dependencies = dependency_table(&root, "dev-dependencies", &mut warnings)?;
+        if let Some((name, _)) = dev_dependencies.iter().find(|(_, d)| d.optional) {
+            return Err(format!("`dev-dependencies.{}.optional`: dev-dependencies cannot be optional", name));
+        }
+        let features = features(&root, &dependencies)?;
+
+        let fmt = section(&root, "fmt", &["style", "max_width", "use_tabs", "indent_size"], &mut warnings)?
+            .map(|t| -> Result<Fmt, String> {
+                Ok(Fmt {
+                    style: one_of(t, "fmt", "style", &["zeta-strict", "zeta-pretty", "custom"])?,
+                    max_width: integer(t, "fmt", "max_width", 1, 1000)?,
+                    use_tabs: boolean(t, "fmt", "use_tabs")?,
+                    indent_size: integer(t, "fmt", "indent_size", 1, 16)?,
+                })
+            })
+            .transpose()?;
+        let lint = section(&root, "lint", &["tool", "level", "deny", "warn", "allow"], &mut warnings)?
+            .map(|t| -> Result<Lint, String> {
+                Ok(Lint {
+                    tool: one_of(t, "lint", "tool", &["zippy"])?,
+                    level: one_of(t, "lint", "level", &["recommended", "pedantic", "strict"])?,
+                    deny: strings(t, "lint", "deny")?,
+                    warn: strings(t, "lint", "warn")?,
+                    allow: strings(t, "lint", "allow")?,
+                })
+            })
+            .transpose()?;
+        let build = section(&root, "build", &["script"], &mut warnings)?
+            .map(|t| -> Result<Build, String> { Ok(Build { script: string(t, "build", "script")? }) })
+            .transpose()?;
+        let workspace = section(&root, "workspace", &["members"], &mut warnings)?
+            .map(|t| -> Result<Workspace, String> { Ok(Workspace { members: strings(t, "workspace", "members")? }) })
+            .transpose()?;
+
+        let manifest = Manifest { package, dependencies, dev_dependencies, features, fmt, lint, build, workspace };
+        Ok(ParsedManifest { manifest, warnings })
+    }
+
+    /// `[dependencies]` flattened to name → version requirement, `*` for
+    /// dependencies without one; the form the resolver reads.
+    pub fn dependency_requirements(&self) -> serde_json::Value {
+        self.dependencies
+            .iter()
+            .map(|(name, dep)| (name.clone(), serde_json::Value::String(dep.version.clone().unwrap_or_else(|| "*".to_string()))))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    /// Why this manifest cannot be published, one message per offending entry: a
+    /// `path` or `git` dependency without a `version` would only resolve on the
+    /// publisher's machine. Dev-dependencies are exempt, since dependents never
+    /// build them.
+    pub fn publish_errors(&self) -> Vec<String> {
+        self.dependencies
+            .iter()
+            .filter(|(_, dep)| dep.version.is_none())
+            .map(|(name, dep)| {
+                let source = if dep.path.is_some() { "path" } else { "git" };
+                format!("`dependencies.{}` is a {} dependency with no `version` to use from the registry", name, source)
+            })
+            .collect()
+    }
+
+    /// Every dependency resolved from the registry once published, as
+    /// (section, name): those with a `version`, including dev-dependencies.
+    pub fn registry_dependencies(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [("dependencies", &self.dependencies), ("dev-dependencies", &self.dev_dependencies)]
+            .into_iter()
+            .flat_map(|(section, deps)| {
+                deps.iter().filter(|(_, dep)| dep.version.is_some()).map(move |(name, _)| (section, name.as_str()))
+            })
+    }
+}
+
+/// The dependency a feature refers to as `name`: by its full key, or by the part
+/// after the scope (`tracing` for `@logging/tracing`).
+pub fn find_dependency<'a>(dependencies: &'a BTreeMap<String, Dependency>, name: &str) -> Option<(&'a String, &'a Dependency)> {
+    dependencies
+        .get_key_value(name)
+        .or_else(|| dependencies.iter().find(|(key, _)| key.rsplit('/').next() == Some(name)))
+}
+
+fn package(t: &Table, warnings: &mut Vec<String>) -> Result<Package, String> {
+    unknown_keys(t, "package", &["name", "version", "edition", "authors", "description", "license", "repository", "keywords", "categories"], warnings);
+    let name = string(t, "package", "name")?.ok_or("Missing 'name' field in zorb.toml [package]")?;
+    let version = string(t, "package", "version")?.ok_or("Missing 'version' field in zorb.toml [package]")?;
+    Version::parse(&version).map_err(|_| format!("Invalid semantic version: {}", version))?;
+    let edition = match string(t, "package", "edition")? {
+        Some(edition) if EDITIONS.contains(&edition.as_str()) => edition,
+        Some(edition) => return Err(format!("`package.edition` '{}' is not a known edition (expected one of: {})", edition, EDITIONS.join(", "))),
+        None => {
+            warnings.push(format!("`package.edition` is not set; assuming {}", DEFAULT_EDITION));
+            DEFAULT_EDITION.to_string()
+        }
+    };
+    let authors = match t.get("authors") {
+        Some(Value::String(author)) => vec![author.clone()],
+        _ => strings(t, "package", "authors")?,
+    };
+    Ok(Package {
+        name,
+        version,
+        edition,
+        authors,
+        description: string(t, "package", "description")?,
+        license: string(t, "package", "license")?,
+        repository: string(t, "package", "repository")?,
+        keywords: categories::validate_keywords(&strings(t, "package", "keywords")?)?,
+        categories: categories::validate_categories(&strings(t, "package", "categories")?)?,
+    })
+}
+
+fn dependency_table(root: &Table, section_name: &str, warnings: &mut Vec<String>) -> Result<BTreeMap<String, Dependency>, String> {
+    let Some(table) = section(root, section_name, &[], &mut Vec::new())? else {
+        return Ok(BTreeMap::new());
+    };
+    let mut deps = BTreeMap::new();
+    for (name, value) in table {
+        let at = format!("{}.{}", section_name, name);
+        if name.trim().is_empty() {
+            return Err(format!("`{}` has an empty dependency name", section_name));
+        }
+        let dep = match value {
+            Value::String(req) => Dependency::registry(requirement(&at, req)?),
+            Value::Table(t) => {
+                unknown_keys(t, &at, &["version", "features", "default-features", "optional", "git", "branch", "rev", "path"], warnings);
+                let dep = Dependency {
+                    version: string(t, &at, "version")?.map(|req| requirement(&at, &req)).transpose()?,
+                    features: strings(t, &at, "features")?,
+                    default_features: boolean(t, &at, "default-features")?.unwrap_or(true),
+                    optional: boolean(t, &at, "optional")?.unwrap_or(false),
+                    git: string(t, &at, "git")?,
+                    branch: string(t, &at, "branch")?,
+                    rev: string(t, &at, "rev")?,
+                    path: string(t, &at, "path")?,
+                };
+                if dep.git.is_some() && dep.path.is_some() {
+                    return Err(format!("`{}` cannot set both `git` and `path`", at));
+                }
+                if dep.git.is_none() && (dep.branch.is_some() || dep.rev.is_some()) {
+                    return Err(format!("`{}` sets `branch` or `rev` without `git`", at));
+                }
+                if dep.branch.is_some() && dep.rev.is_some() {
+                    return Err(format!("`{}` cannot set both `branch` and `rev`", at));
+                }
+                if dep.version.is_none() && dep.git.is_none() && dep.path.is_none() {
+                    return Err(format!("`{}` must set `version`, `git` or `path`", at));
+                }
+                dep
+            }
+            _ => return Err(format!("`{}` must be a version string or a table", at)),
+        };
+        deps.insert(name.clone(), dep);
+    }
+    Ok(deps)
+}
+
+fn requirement(at: &str, req: &str) -> Result<String, String> {
+    VersionReq::parse(req).map_err(|e| format!("`{}` has an invalid version requirement '{}': {}", at, req, e))?;
+    Ok(req.to_string())
+}
+
+fn features(root: &Table, dependencies: &BTreeMap<String, Dependency>) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let Some(table) = section(root, "features", &[], &mut Vec::new())? else {
+        return Ok(BTreeMap::new());
+    };
+    let mut features = BTreeMap::new();
+    for name in table.keys() {
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '+')) {
+            return Err(format!("`features.{}` is not a valid feature name", name));
+        }
+        features.insert(name.clone(), strings(table, "features", name)?);
+    }
+    for (name, enables) in &features {
+        for item in enables {
+            let known = if let Some(dep) = item.strip_prefix("dep:") {
+                match find_dependency(dependencies, dep) {
+                    Some((_, d)) if d.optional => true,
+                    Some((key, _)) => return Err(format!("`features.{}` enables `{}`, but `{}` is not optional", name, item, key)),
+                    None => false,
+                }
+            } else {
+                features.contains_key(item)
+                    || find_dependency(dependencies, item).is_some()
+                    || item
+                        .rsplit_once('/')
+                        .is_some_and(|(dep, _)| find_dependency(dependencies, dep.trim_end_matches('?')).is_some())
+            };
+            if !known {
+                return Err(format!("`features.{}` enables `{}`, which is neither a feature nor a dependency", name, item));
+            }
+        }
+    }
+    Ok(features)
+}
+
+/// An optional sub-table, with unknown keys reported.
+fn section<'a>(root: &'a Table, name: &str, known: &[&str], warnings: &mut Vec<String>) -> Result<Option<&'a Table>, String> {
+    match root.get(name) {
+        Some(Value::Table(t)) => {
+            if !known.is_empty() {
+                unknown_keys(t, name, known, warnings);
+            }
+            Ok(Some(t))
+        }
+        Some(_) => Err(format!("`{}` in zorb.toml must be a table", name)),
+        None => Ok(None),
+    }
+}
+
+fn unknown_keys(t: &Table, at: &str, known: &[&str], warnings: &mut Vec<String>) {
+    for key in t.keys().filter(|k| !known.contains(&k.as_str())) {
+        if at.is_empty() {
+            warnings.push(format!("Unknown section `{}` in zorb.toml", key));
+        } else {
+            warnings.push(format!("Unknown key `{}.{}` in zorb.toml", at, key));
+        }
+    }
+}
+
+fn string(t: &Table, at: &str, key: &str) -> Result<Option<String>, String> {
+    match t.get(key) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(format!("`{}.{}` in zorb.toml must be a string", at, key)),
+    }
+}
+
+/// An optional array of strings; a missing key is an empty list.
+fn strings(t: &Table, at: &str, key: &str) -> Result<Vec<String>, String> {
+    let invalid = || format!("`{}.{}` in zorb.toml must be an array of strings", at, key);
+    match t.get(key) {
+        None => Ok(Vec::new()),
+        Some(Value::Array(items)) => items.iter().map(|v| v.as_str().map(str::to_string).ok_or_else(invalid)).collect(),
+        Some(_) => Err(invalid()),
+    }
+}
+
+fn boolean(t: &Table, at: &str, key: &str) -> Result<Option<bool>, String> {
+    match t.get(key) {
+        None => Ok(None),
+        Some(Value::Boolean(b)) => Ok(Some(*b)),
+        Some(_) => Err(format!("`{}.{}` in zorb.toml must be true or false", at, key)),
+    }
+}
+
+fn integer(t: &Table, at: &str, key: &str, min: u32, max: u32) -> Result<Option<u32>, String> {
+    match t.get(key) {
+        None => Ok(None),
+        Some(Value::Integer(n)) if (min as i64..=max as i64).contains(n) => Ok(Some(*n as u32)),
+        Some(_) => Err(format!("`{}.{}` in zorb.toml must be a whole number from {} to {}", at, key, min, max)),
+    }
+}
+
+fn one_of(t: &Table, at: &str, key: &str, allowed: &[&str]) -> Result<Option<String>, String> {
+    match string(t, at, key)? {
+        Some(value) if !allowed.contains(&value.as_str()) => Err(format!(
+            "`{}.{}` in zorb.toml must be one of: {}",
+            at,
+            key,
+            allowed.join(", ")
+        )),
+        value => Ok(value),
+    }
+}
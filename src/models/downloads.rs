@@ -0,0 +1,40 @@
+// src/models/downloads.rs
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::FromRow;
+use super::Zorb;
+
+/// Longest range, in days, the time-series API returns at once.
+pub const MAX_SERIES_DAYS: i64 = 366;
+/// Range shown on the package page and returned when `from` is omitted.
+pub const DEFAULT_SERIES_DAYS: i64 = 90;
+
+/// Downloads of one version on one UTC day.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct DownloadDay {
+    pub version: String,
+    pub date: NaiveDate,
+    pub downloads: i64,
+}
+
+/// A package on the homepage trending list.
+#[derive(Debug, Clone, FromRow)]
+pub struct TrendingZorb {
+    /// Latest version, with `downloads` summed over every version.
+    #[sqlx(flatten)]
+    pub zorb: Zorb,
+    /// Downloads over the last 7 days.
+    pub recent_downloads: i64,
+}
+
+/// One `(date, downloads)` entry for every day from `from` to `to` inclusive,
+/// summing the `days` that fall on it, so charts get a gap-free series.
+pub fn daily_totals<'a>(days: impl IntoIterator<Item = &'a DownloadDay>, from: NaiveDate, to: NaiveDate) -> Vec<(NaiveDate, i64)> {
+    let mut series: Vec<(NaiveDate, i64)> = from.iter_days().take_while(|d| *d <= to).map(|d| (d, 0)).collect();
+    for day in days {
+        if let Some(entry) = usize::try_from((day.date - from).num_days()).ok().and_then(|i| series.get_mut(i)) {
+            entry.1 += day.downloads;
+        }
+    }
+    series
+}
@@ -0,0 +1,13 @@
+// src/models/files.rs
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A regular file inside a version's archive.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// Relative to the archive root, `/`-separated, without a leading `./`.
+    pub path: String,
+    pub size: i64,
+    /// Unix permission bits, e.g. `0o644`.
+    pub mode: i32,
+}
@@ -0,0 +1,113 @@
+// src/models/listing.rs
+use serde::Serialize;
+use sqlx::FromRow;
+use super::Zorb;
+
+/// Default and maximum page sizes for package listings.
+pub const DEFAULT_PER_PAGE: i64 = 20;
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// A validated 1-based page of a listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub number: i64,
+    pub size: i64,
+}
+
+impl Page {
+    /// Defaults to the first page of [`DEFAULT_PER_PAGE`]; the error is a message for the client.
+    pub fn new(number: Option<i64>, size: Option<i64>) -> Result<Page, String> {
+        let number = number.unwrap_or(1);
+        if number < 1 {
+            return Err("page must be 1 or greater".to_string());
+        }
+        let size = size.unwrap_or(DEFAULT_PER_PAGE);
+        if !(1..=MAX_PER_PAGE).contains(&size) {
+            return Err(format!("per_page must be between 1 and {}", MAX_PER_PAGE));
+        }
+        Ok(Page { number, size })
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.number - 1).saturating_mul(self.size)
+    }
+
+    /// Number of pages needed for `total` items.
+    pub fn count(&self, total: i64) -> i64 {
+        (total + self.size - 1) / self.size
+    }
+}
+
+/// Ordering of a package listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageSort {
+    /// Most downloaded first, summed over every version.
+    #[default]
+    Downloads,
+    /// Most recently published version first.
+    Updated,
+    /// Alphabetical by name.
+    Name,
+    /// Most recently created package first.
+    Newest,
+}
+
+impl PackageSort {
+    pub const ALL: [PackageSort; 4] = [PackageSort::Downloads, PackageSort::Updated, PackageSort::Name, PackageSort::Newest];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackageSort::Downloads => "downloads",
+            PackageSort::Updated => "updated",
+            PackageSort::Name => "name",
+            PackageSort::Newest => "newest",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<PackageSort> {
+        PackageSort::ALL.into_iter().find(|sort| sort.as_str() == s)
+    }
+
+    /// `ORDER BY` clause over the listing's `pkg` columns; `name` breaks ties so pages are stable.
+    pub fn order_by(&self) -> &'static str {
+        match self {
+            PackageSort::Downloads => "total_downloads DESC, name ASC",
+            PackageSort::Updated => "last_published DESC, name ASC",
+            PackageSort::Name => "name ASC",
+            PackageSort::Newest => "first_published DESC, name ASC",
+        }
+    }
+}
+
+/// Optional filters on a package listing; every one that is set must match.
+#[derive(Debug, Clone, Default)]
+pub struct PackageFilter {
+    /// Username of one of the package's owners.
+    pub owner: Option<String>,
+    /// Scope without the `@`, e.g. `data` for `@data/*`.
+    pub scope: Option<String>,
+    /// License expression, matched case-insensitively.
+    pub license: Option<String>,
+    /// One of the package's keywords, or a substring of its name or description.
+    pub keyword: Option<String>,
+    /// Category slug; a parent category also matches its subcategories.
+    pub category: Option<String>,
+}
+
+/// One package matched by a search, best match first.
+#[derive(Debug, Clone, FromRow)]
+pub struct SearchHit {
+    #[sqlx(flatten)]
+    pub zorb: Zorb,
+    /// Relevance blended with popularity; only meaningful relative to other hits.
+    pub score: f64,
+    /// Matching fragments of the description or README, with each match between
+    /// [`HIGHLIGHT_START`] and [`HIGHLIGHT_END`].
+    pub snippet: Option<String>,
+}
+
+/// Control characters that cannot appear in escaped output, so callers can
+/// escape the snippet first and then swap these for real highlight markup.
+pub const HIGHLIGHT_START: char = '\u{2}';
+pub const HIGHLIGHT_END: char = '\u{3}';
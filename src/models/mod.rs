@@ -1,6 +1,18 @@
 // src/models/mod.rs
 pub mod zorb;
 pub mod user;
+pub mod token;
+pub mod owner;
+pub mod listing;
+pub mod downloads;
+pub mod files;
+pub mod webhook;
 pub use zorb::Zorb;
 pub use zorb::NewZorb;
 pub use user::User;
+pub use token::{ApiToken, TokenScope};
+pub use owner::{PackageOwner, PublishAccess};
+pub use listing::{PackageFilter, PackageSort, SearchHit};
+pub use downloads::{DownloadDay, TrendingZorb};
+pub use files::FileEntry;
+pub use webhook::{Webhook, WebhookDelivery, WebhookEvent};
@@ -0,0 +1,26 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// One owner of a package name, joined with their public profile.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PackageOwner {
+    pub user_id: Uuid,
+    pub username: String,
+    pub provider: String,
+    pub avatar_url: Option<String>,
+    pub added_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Outcome of checking whether a user may publish to a package name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishAccess {
+    /// The name was unclaimed and now belongs to the publisher.
+    NewPackage,
+    /// The publisher already owns the name.
+    Owner,
+    /// The name belongs to someone else, or has versions but no owner yet.
+    NotOwner,
+}
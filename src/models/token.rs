@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// Prefix on every plaintext API token so leaked tokens are easy to grep for.
+pub const TOKEN_PREFIX: &str = "zorb_";
+
+/// What an API token is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenScope {
+    /// Publish the first version of a package name.
+    PublishNew,
+    /// Publish further versions of an existing package.
+    PublishUpdate,
+    /// Yank and un-yank published versions.
+    Yank,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 3] = [TokenScope::PublishNew, TokenScope::PublishUpdate, TokenScope::Yank];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::PublishNew => "publish-new",
+            TokenScope::PublishUpdate => "publish-update",
+            TokenScope::Yank => "yank",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<TokenScope> {
+        TokenScope::ALL.into_iter().find(|scope| scope.as_str() == s)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiToken {
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.iter().any(|s| s == scope.as_str())
+    }
+
+    /// A token is usable while it is neither revoked nor past its expiry.
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|exp| exp > Utc::now())
+    }
+}
+
+/// Generate a fresh plaintext token: `zorb_` + two v4 UUIDs as 64 hex chars,
+/// carrying 244 random bits (each UUID fixes 6 version/variant bits).
+pub fn generate_token() -> String {
+    format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// Hash a plaintext token for storage and lookup.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
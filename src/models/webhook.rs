@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// Prefix on every webhook signing secret.
+pub const SECRET_PREFIX: &str = "whsec_";
+
+/// A registry event a webhook can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A new version was published.
+    Published,
+    /// A version was yanked or restored.
+    Yanked,
+    /// An owner was added or removed.
+    OwnershipChanged,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 3] = [
+        WebhookEvent::Published,
+        WebhookEvent::Yanked,
+        WebhookEvent::OwnershipChanged,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::Published => "published",
+            WebhookEvent::Yanked => "yanked",
+            WebhookEvent::OwnershipChanged => "ownership_changed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<WebhookEvent> {
+        WebhookEvent::ALL.into_iter().find(|event| event.as_str() == s)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    /// `None` for a registry-wide webhook registered by an admin.
+    pub package: Option<String>,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: Vec<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One entry of a webhook's delivery log.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: serde_json::Value,
+    /// `pending` until delivered, or `failed` once every retry is used up.
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Generate a fresh signing secret (`whsec_` + 64 hex chars of randomness).
+pub fn generate_secret() -> String {
+    format!("{}{}{}", SECRET_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
@@ -4,6 +4,8 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use super::FileEntry;
+use crate::manifest::Manifest;
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct Zorb {
@@ -19,6 +21,12 @@ pub struct Zorb {
     pub updated_at: DateTime<Utc>,
     pub dependencies: JsonValue,
     pub readme: Option<String>,
+    pub yanked: bool,
+    /// SHA-256 of the `.zorb` archive, hex encoded.
+    pub checksum: Option<String>,
+    pub keywords: Vec<String>,
+    /// Slugs from the category registry in `crate::categories`.
+    pub categories: Vec<String>,
 }
 
 impl Zorb {
@@ -44,4 +52,13 @@ pub struct NewZorb {
     pub repository: Option<String>,
     pub dependencies: JsonValue,
     pub readme: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    /// Every regular file in the archive, stored as the version's file listing.
+    pub files: Vec<FileEntry>,
+    /// The full validated `zorb.toml`; `None` when the publish form supplied the metadata.
+    pub manifest: Option<Manifest>,
+    /// Questionable but accepted manifest content, such as unknown keys.
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
@@ -41,7 +41,7 @@ pub const POST_MVP_V1: &str = r#"
 - [ ] Automatic security scanning + SLSA Level 3
 - [ ] Organizations & private registries
 ## Phase 3: Core Engine
-- [ ] Full dependency graph + resolution engine
+- [x] Full dependency graph + resolution engine
 - [ ] Pre-built binaries for all targets (x86_64, aarch64, wasm, etc.)
 - [ ] AI semantic search
 ## Phase 4: Ecosystem Tools
@@ -0,0 +1,403 @@
+// src/resolver.rs — semver version selection and dependency graph resolution
+use semver::{Version, VersionReq};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::rc::Rc;
+use uuid::Uuid;
+use crate::manifest::{self, Dependency, Manifest};
+use crate::models::Zorb;
+
+/// Parse a dependency requirement as written in `zorb.toml`.
+/// A missing or empty requirement means "any version" (`*`).
+pub fn parse_req(req: Option<&str>) -> Result<VersionReq, semver::Error> {
+    match req.map(str::trim) {
+        None | Some("") => Ok(VersionReq::STAR),
+        Some(req) => VersionReq::parse(req),
+    }
+}
+
+/// Non-yanked versions with a valid semver, newest first.
+pub fn candidates(versions: &[Zorb]) -> Vec<(Version, &Zorb)> {
+    let mut parsed: Vec<(Version, &Zorb)> = versions
+        .iter()
+        .filter(|z| !z.yanked)
+        .filter_map(|z| Version::parse(&z.version).ok().map(|v| (v, z)))
+        .collect();
+    parsed.sort_by(|a, b| b.0.cmp(&a.0));
+    parsed
+}
+
+/// Highest non-yanked version satisfying `req`.
+///
+/// `VersionReq::matches` follows Cargo's rules, so prereleases are only
+/// considered when the requirement itself names a prerelease of the same
+/// `major.minor.patch` (e.g. `>=2.0.0-beta.1`).
+pub fn best_match<'a>(versions: &'a [Zorb], req: &VersionReq) -> Option<&'a Zorb> {
+    candidates(versions)
+        .into_iter()
+        .find(|(v, _)| req.matches(v))
+        .map(|(_, z)| z)
+}
+
+/// Upper bound on solver decisions before giving up on a pathological graph.
+const MAX_STEPS: usize = 100_000;
+
+/// What one version (or the root manifest) declares about its dependencies and
+/// features, keyed like `[dependencies]`.
+#[derive(Debug, Clone, Default)]
+pub struct Spec {
+    pub dependencies: BTreeMap<String, Dependency>,
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+/// The outcome of turning on a set of features: every feature that ends up
+/// active, and each enabled dependency with the features requested of it.
+#[derive(Debug, Clone, Default)]
+pub struct Activation {
+    pub features: BTreeSet<String>,
+    pub dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Spec {
+    /// From a stored manifest, or, for versions published without one, from the
+    /// flattened `dependencies` column: all required, with default features.
+    pub fn of(zorb: &Zorb, manifest: Option<&Manifest>) -> Spec {
+        match manifest {
+            Some(m) => Spec { dependencies: m.dependencies.clone(), features: m.features.clone() },
+            None => Spec::from_requirements(zorb.dependencies_map()),
+        }
+    }
+
+    pub fn from_requirements(requirements: impl IntoIterator<Item = (String, String)>) -> Spec {
+        let dependencies = requirements
+            .into_iter()
+            .map(|(name, req)| (name, Dependency::registry(req)))
+            .collect();
+        Spec { dependencies, features: BTreeMap::new() }
+    }
+
+    /// Close `requested` over the feature table. `default` may always be asked
+    /// for; any other name must be a feature or an optional dependency (which
+    /// is then enabled), otherwise it is returned as the error.
+    pub fn activate(&self, requested: &BTreeSet<String>) -> Result<Activation, String> {
+        let mut active = BTreeSet::new();
+        let mut enabled: BTreeSet<&str> = self
+            .dependencies
+            .iter()
+            .filter(|(_, d)| !d.optional)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let mut dep_features: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+        // `name?/feature` only applies if something else enables `name`
+        let mut weak: Vec<(&str, &str)> = Vec::new();
+        let find = |name: &str| manifest::find_dependency(&self.dependencies, name).map(|(key, _)| key.as_str());
+
+        let mut queue: Vec<&str> = requested.iter().map(String::as_str).collect();
+        while let Some(feature) = queue.pop() {
+            if active.contains(feature) {
+                continue;
+            }
+            let Some(items) = self.features.get(feature) else {
+                match find(feature) {
+                    Some(dep) if self.dependencies[dep].optional => {
+                        enabled.insert(dep);
+                        active.insert(feature.to_string());
+                    }
+                    _ if feature == "default" => {}
+                    _ => return Err(feature.to_string()),
+                }
+                continue;
+            };
+            active.insert(feature.to_string());
+            for item in items {
+                if let Some(dep) = item.strip_prefix("dep:").and_then(find) {
+                    enabled.insert(dep);
+                } else if self.features.contains_key(item) {
+                    queue.push(item);
+                } else if let Some(dep) = find(item) {
+                    enabled.insert(dep);
+                } else if let Some((name, dep_feature)) = item.rsplit_once('/') {
+                    let is_weak = name.ends_with('?');
+                    if let Some(dep) = find(name.trim_end_matches('?')) {
+                        if is_weak {
+                            weak.push((dep, dep_feature));
+                        } else {
+                            enabled.insert(dep);
+                            dep_features.entry(dep).or_default().insert(dep_feature.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        for (dep, dep_feature) in weak {
+            if enabled.contains(dep) {
+                dep_features.entry(dep).or_default().insert(dep_feature.to_string());
+            }
+        }
+
+        let dependencies = enabled
+            .into_iter()
+            .map(|dep| {
+                let mut features: BTreeSet<String> = self.dependencies[dep].features.iter().cloned().collect();
+                features.extend(dep_features.remove(dep).unwrap_or_default());
+                (dep.to_string(), features)
+            })
+            .collect();
+        Ok(Activation { features: active, dependencies })
+    }
+
+    /// Requirements on every dependency `activation` enables, reached through `path`.
+    fn requirements(&self, activation: &Activation, path: &[String]) -> Vec<Requirement> {
+        activation
+            .dependencies
+            .iter()
+            .map(|(name, features)| {
+                let dep = &self.dependencies[name];
+                Requirement {
+                    name: name.clone(),
+                    req: dep.version.clone().unwrap_or_else(|| "*".to_string()),
+                    features: features.iter().cloned().collect(),
+                    default_features: dep.default_features,
+                    path: path.to_vec(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The features a set of requirements asks of one package, `default` included
+/// unless every one of them opted out.
+fn requested_features<'r>(requirements: impl IntoIterator<Item = &'r Requirement>) -> BTreeSet<String> {
+    let mut requested = BTreeSet::new();
+    for r in requirements {
+        requested.extend(r.features.iter().cloned());
+        if r.default_features {
+            requested.insert("default".to_string());
+        }
+    }
+    requested
+}
+
+/// One requirement in the graph, with the path of `name@version` packages that led to it.
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    pub name: String,
+    pub req: String,
+    /// Features asked of the package, on top of `default` if `default_features`.
+    pub features: Vec<String>,
+    pub default_features: bool,
+    pub path: Vec<String>,
+}
+
+impl Requirement {
+    /// `root → a@1.2.0 → c ^1.0`
+    pub fn chain(&self) -> String {
+        let mut chain = vec!["root".to_string()];
+        chain.extend(self.path.iter().cloned());
+        chain.push(format!("{} {}", self.name, self.req));
+        chain.join(" → ")
+    }
+}
+
+#[derive(Debug)]
+pub enum ResolveError {
+    /// No package with this name exists.
+    NotFound(Box<Requirement>),
+    /// A requirement could not be parsed.
+    InvalidRequirement(Box<Requirement>, String),
+    /// The package exists but no non-yanked version satisfies the requirement.
+    NoMatch { requirement: Box<Requirement>, available: Vec<String> },
+    /// Two requirements on the same package cannot both be satisfied.
+    Conflict { selected: String, previous: Box<Requirement>, requirement: Box<Requirement> },
+    /// A feature that the selected version (or, without a requirement, the
+    /// root manifest) does not define.
+    UnknownFeature { feature: String, selected: Option<String>, requirement: Option<Box<Requirement>> },
+    TooComplex,
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolveError::NotFound(r) => write!(f, "Package '{}' not found (required by {})", r.name, r.chain()),
+            ResolveError::InvalidRequirement(r, e) => write!(f, "Invalid version requirement in {}: {}", r.chain(), e),
+            ResolveError::NoMatch { requirement, .. } => {
+                write!(f, "No version of '{}' matches {}", requirement.name, requirement.chain())
+            }
+            ResolveError::Conflict { selected, previous, requirement } => write!(
+                f,
+                "Conflicting requirements on '{}': {} (selected {}) clashes with {}",
+                requirement.name, previous.chain(), selected, requirement.chain()
+            ),
+            ResolveError::UnknownFeature { feature, selected, requirement: Some(r) } => write!(
+                f,
+                "'{}' {} has no feature '{}' (required by {})",
+                r.name, selected.as_deref().unwrap_or("?"), feature, r.chain()
+            ),
+            ResolveError::UnknownFeature { feature, .. } => write!(f, "The root manifest has no feature '{}'", feature),
+            ResolveError::TooComplex => write!(f, "Dependency graph too complex to resolve"),
+        }
+    }
+}
+
+/// A package in the solution, with the features unified across every
+/// requirement on it.
+#[derive(Debug, Clone)]
+pub struct Resolved<'a> {
+    pub zorb: &'a Zorb,
+    pub features: Vec<String>,
+}
+
+#[derive(Clone)]
+struct Selection<'a> {
+    version: Version,
+    zorb: &'a Zorb,
+    spec: Rc<Spec>,
+    /// The first requirement, reported in conflicts.
+    requirement: Requirement,
+    requested: BTreeSet<String>,
+    features: BTreeSet<String>,
+}
+
+type Candidate<'a> = (Version, &'a Zorb, Rc<Spec>);
+
+/// Backtracking solver over an in-memory snapshot of the registry.
+///
+/// Picks one version per package name, trying candidates newest first and
+/// backtracking to the most recent choice whenever a later requirement on an
+/// already selected package cannot be met. Features are unified per package:
+/// each new requirement adds its features to the selected version, which may
+/// enable optional dependencies and so add requirements of its own.
+pub struct Solver<'a> {
+    candidates: HashMap<&'a str, Vec<Candidate<'a>>>,
+    steps: usize,
+}
+
+impl<'a> Solver<'a> {
+    /// `registry` maps every reachable package name to all of its versions;
+    /// `manifests` holds the stored manifests of those that have one, by id.
+    pub fn new(registry: &'a HashMap<String, Vec<Zorb>>, manifests: &HashMap<Uuid, Manifest>) -> Self {
+        let candidates = registry
+            .iter()
+            .map(|(name, versions)| {
+                let versions = candidates(versions)
+                    .into_iter()
+                    .map(|(v, z)| (v, z, Rc::new(Spec::of(z, manifests.get(&z.id)))))
+                    .collect();
+                (name.as_str(), versions)
+            })
+            .collect();
+        Solver { candidates, steps: 0 }
+    }
+
+    /// Resolve the root manifest with `features` turned on into one version per
+    /// package, sorted by name.
+    pub fn solve(mut self, root: &Spec, features: &BTreeSet<String>) -> Result<Vec<Resolved<'a>>, ResolveError> {
+        let activation = root.activate(features).map_err(|feature| ResolveError::UnknownFeature {
+            feature,
+            selected: None,
+            requirement: None,
+        })?;
+        let pending: VecDeque<Requirement> = root.requirements(&activation, &[]).into();
+        let selected = self.step(pending, BTreeMap::new())?;
+        Ok(selected
+            .into_values()
+            .map(|s| Resolved { zorb: s.zorb, features: s.features.into_iter().collect() })
+            .collect())
+    }
+
+    fn step(
+        &mut self,
+        mut pending: VecDeque<Requirement>,
+        mut selected: BTreeMap<String, Selection<'a>>,
+    ) -> Result<BTreeMap<String, Selection<'a>>, ResolveError> {
+        // Requirements on packages that are already selected either hold or fail
+        // without a new decision, so drain them here instead of recursing. One
+        // that asks for new features may add requirements of its own.
+        let (requirement, req) = loop {
+            let Some(requirement) = pending.pop_front() else { return Ok(selected) };
+            self.steps += 1;
+            if self.steps > MAX_STEPS {
+                return Err(ResolveError::TooComplex);
+            }
+            let req = parse_req(Some(&requirement.req))
+                .map_err(|e| ResolveError::InvalidRequirement(Box::new(requirement.clone()), e.to_string()))?;
+            match selected.get_mut(&requirement.name) {
+                Some(sel) if req.matches(&sel.version) => {
+                    let requested = requested_features([&requirement]);
+                    if requested.is_subset(&sel.requested) {
+                        continue;
+                    }
+                    sel.requested.extend(requested);
+                    let activation = sel.spec.activate(&sel.requested).map_err(|feature| ResolveError::UnknownFeature {
+                        feature,
+                        selected: Some(sel.version.to_string()),
+                        requirement: Some(Box::new(requirement.clone())),
+                    })?;
+                    let mut path = sel.requirement.path.clone();
+                    path.push(format!("{}@{}", sel.zorb.name, sel.version));
+                    pending.extend(sel.spec.requirements(&activation, &path));
+                    sel.features = activation.features;
+                }
+                Some(sel) => {
+                    return Err(ResolveError::Conflict {
+                        selected: sel.version.to_string(),
+                        previous: Box::new(sel.requirement.clone()),
+                        requirement: Box::new(requirement),
+                    });
+                }
+                None => break (requirement, req),
+            }
+        };
+
+        let versions = match self.candidates.get(requirement.name.as_str()) {
+            Some(versions) => versions.clone(),
+            None => return Err(ResolveError::NotFound(Box::new(requirement))),
+        };
+        let matching: Vec<Candidate<'a>> = versions
+            .iter()
+            .filter(|(v, _, _)| req.matches(v))
+            .cloned()
+            .collect();
+        if matching.is_empty() {
+            let available = versions.iter().map(|(v, _, _)| v.to_string()).collect();
+            return Err(ResolveError::NoMatch { requirement: Box::new(requirement), available });
+        }
+
+        let requested = requested_features([&requirement]);
+        let mut first_error = None;
+        for (version, zorb, spec) in matching {
+            let activation = match spec.activate(&requested) {
+                Ok(activation) => activation,
+                Err(feature) => {
+                    first_error.get_or_insert(ResolveError::UnknownFeature {
+                        feature,
+                        selected: Some(version.to_string()),
+                        requirement: Some(Box::new(requirement.clone())),
+                    });
+                    continue;
+                }
+            };
+            let mut next = pending.clone();
+            let mut path = requirement.path.clone();
+            path.push(format!("{}@{}", zorb.name, version));
+            next.extend(spec.requirements(&activation, &path));
+
+            let mut branch = selected.clone();
+            branch.insert(requirement.name.clone(), Selection {
+                version,
+                zorb,
+                spec,
+                requirement: requirement.clone(),
+                requested: requested.clone(),
+                features: activation.features,
+            });
+            match self.step(next, branch) {
+                Ok(solution) => return Ok(solution),
+                Err(ResolveError::TooComplex) => return Err(ResolveError::TooComplex),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_error.expect("at least one candidate was tried"))
+    }
+}
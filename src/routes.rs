@@ -1,23 +1,55 @@
 // src/routes.rs
 use axum::Router;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post, put};
 use std::sync::Arc;
 use crate::state::AppState;
-use crate::handlers::docs;
 
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(crate::handlers::home::homepage))
         .route("/publish", get(crate::handlers::publish::publish_page))
+        .route("/feed.atom", get(crate::handlers::feeds::all_releases))
+        .route("/users/{username}/feed.atom", get(crate::handlers::feeds::user_releases))
+        .route("/@{scope}/feed.atom", get(crate::handlers::feeds::scope_releases))
+        .route("/@{scope}/{name}/feed.atom", get(crate::handlers::feeds::package_releases_scoped))
+        .route("/{name}/feed.atom", get(crate::handlers::feeds::package_releases))
         .route("/@{scope}/{name}", get(crate::handlers::detail::zorb_detail_scoped))
         .route("/{name}", get(crate::handlers::detail::zorb_detail))
         .route("/@{scope}/{name}/{version}/download", get(crate::handlers::download::download_zorb_scoped))
         .route("/{name}/{version}/download", get(crate::handlers::download::download_zorb))
+        .route("/@{scope}/{name}/{version}/source", get(crate::handlers::source::source_root_scoped))
+        .route("/@{scope}/{name}/{version}/source/{*path}", get(crate::handlers::source::source_file_scoped))
+        .route("/{name}/{version}/source", get(crate::handlers::source::source_root))
+        .route("/{name}/{version}/source/{*path}", get(crate::handlers::source::source_file))
         .route("/api/health", get(crate::handlers::home::health))
         .route("/api/zorbs", get(crate::handlers::home::list_zorbs))
         .route("/api/zorbs/new", post(crate::handlers::publish::publish_zorb))
-        .route("/api/search", get(crate::handlers::home::search_zorbs))
+        .route("/api/zorbs/@{scope}/{name}/owners", get(crate::handlers::owners::list_owners_scoped).put(crate::handlers::owners::add_owner_scoped))
+        .route("/api/zorbs/@{scope}/{name}/owners/{user_id}", delete(crate::handlers::owners::remove_owner_scoped))
+        .route("/api/zorbs/@{scope}/{name}/downloads", get(crate::handlers::stats::download_series_scoped))
+        .route("/api/zorbs/{name}/downloads", get(crate::handlers::stats::download_series))
+        .route("/api/zorbs/{name}/owners", get(crate::handlers::owners::list_owners).put(crate::handlers::owners::add_owner))
+        .route("/api/zorbs/{name}/owners/{user_id}", delete(crate::handlers::owners::remove_owner))
+        .route("/api/zorbs/@{scope}/{name}/webhooks", get(crate::handlers::webhooks::list_webhooks_scoped).post(crate::handlers::webhooks::create_webhook_scoped))
+        .route("/api/zorbs/@{scope}/{name}/webhooks/{id}", delete(crate::handlers::webhooks::delete_webhook_scoped))
+        .route("/api/zorbs/@{scope}/{name}/webhooks/{id}/deliveries", get(crate::handlers::webhooks::webhook_deliveries_scoped))
+        .route("/api/zorbs/{name}/webhooks", get(crate::handlers::webhooks::list_webhooks).post(crate::handlers::webhooks::create_webhook))
+        .route("/api/zorbs/{name}/webhooks/{id}", delete(crate::handlers::webhooks::delete_webhook))
+        .route("/api/zorbs/{name}/webhooks/{id}/deliveries", get(crate::handlers::webhooks::webhook_deliveries))
+        .route("/api/zorbs/@{scope}/{name}/{version}/yank", put(crate::handlers::yank::yank_scoped).delete(crate::handlers::yank::unyank_scoped))
+        .route("/api/zorbs/@{scope}/{name}/{version}/files", get(crate::handlers::source::list_files_scoped))
+        .route("/api/zorbs/{name}/{version}/files", get(crate::handlers::source::list_files))
+        .route("/api/zorbs/@{scope}/{name}/{version}/manifest", get(crate::handlers::source::get_manifest_scoped))
+        .route("/api/zorbs/{name}/{version}/manifest", get(crate::handlers::source::get_manifest))
+        .route("/api/zorbs/{name}/{version}/yank", put(crate::handlers::yank::yank).delete(crate::handlers::yank::unyank))
+        .route("/api/search", get(crate::handlers::search::search_zorbs))
         .route("/api/resolve", get(crate::handlers::resolve::resolve_package))
+        .route("/api/resolve/graph", post(crate::handlers::resolve::resolve_graph))
+        .route("/api/tokens", get(crate::handlers::tokens::list_tokens).post(crate::handlers::tokens::create_token))
+        .route("/api/tokens/{id}", delete(crate::handlers::tokens::revoke_token))
+        .route("/api/webhooks", get(crate::handlers::webhooks::list_global_webhooks).post(crate::handlers::webhooks::create_global_webhook))
+        .route("/api/webhooks/{id}", delete(crate::handlers::webhooks::delete_global_webhook))
+        .route("/api/webhooks/{id}/deliveries", get(crate::handlers::webhooks::global_webhook_deliveries))
         .route("/auth/github", get(crate::handlers::auth::github_login))
         .route("/auth/github/callback", get(crate::handlers::auth::github_callback))
         .route("/auth/google", get(crate::handlers::auth::google_login))
@@ -27,4 +59,6 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/auth/logout", get(crate::handlers::auth::logout))
         .route("/admin/seed", get(crate::handlers::home::seed_official))
         .route("/docs", get(crate::handlers::docs::docs_page))
+        .route("/categories", get(crate::handlers::categories::categories_page))
+        .route("/categories/{slug}", get(crate::handlers::categories::category_page))
 }
@@ -0,0 +1,492 @@
+// src/s3.rs — minimal S3 client with AWS Signature Version 4
+//
+// Speaks path-style URLs (`{endpoint}/{bucket}/{key}`), which AWS S3, Cloudflare R2
+// and MinIO all accept. Requests are signed in-process: no credentials ever leave
+// this process except inside the signature.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// Hash of an empty payload, sent with every bodiless request.
+pub const EMPTY_PAYLOAD_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+/// Objects larger than this are uploaded in parts.
+pub const DEFAULT_MULTIPART_THRESHOLD: usize = 16 * 1024 * 1024;
+/// Size of every part but the last; S3 requires at least 5 MiB.
+pub const DEFAULT_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// How long to wait for the service to accept a connection.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Upper bound on a whole request, body included. Downloads stream through
+/// this client, so it leaves room for a large archive on a slow link.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Why an S3 request failed.
+#[derive(Debug)]
+pub enum S3Error {
+    /// A conditional write found the key already taken (`412 Precondition Failed`).
+    AlreadyExists,
+    /// The service answered with an error status; `code` is the S3 error code if one was sent.
+    Status { status: StatusCode, code: Option<String>, message: String },
+    /// The request never got an answer.
+    Transport(String),
+}
+
+impl std::fmt::Display for S3Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            S3Error::AlreadyExists => write!(f, "object already exists"),
+            S3Error::Status { status, code: Some(code), message } => write!(f, "{} {}: {}", status, code, message),
+            S3Error::Status { status, code: None, message } => write!(f, "{}: {}", status, message),
+            S3Error::Transport(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// Size, entity tag and modification time of a stored object, from `HEAD`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectMeta {
+    pub len: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+// ─── Signing ───────────────────────────────────────────────────────────────
+
+/// Signs requests for one set of credentials in one region and service.
+#[derive(Debug, Clone)]
+pub struct Signer {
+    pub access_key: String,
+    pub secret_key: String,
+    pub region: String,
+    pub service: String,
+}
+
+/// The parts of a request that go into its signature.
+#[derive(Debug, Clone)]
+pub struct CanonicalRequest<'a> {
+    pub method: &'a str,
+    /// `host[:port]` exactly as sent in the `Host` header.
+    pub host: &'a str,
+    /// Absolute path, already URI-encoded (see [`encode_path`]).
+    pub path: &'a str,
+    /// Unencoded query parameters, in any order.
+    pub query: &'a [(&'a str, &'a str)],
+    /// Headers to sign besides `host`; names in any case.
+    pub headers: &'a [(&'a str, &'a str)],
+    /// Hex SHA-256 of the body, or `UNSIGNED-PAYLOAD`.
+    pub payload_hash: &'a str,
+}
+
+impl Signer {
+    pub fn new(access_key: &str, secret_key: &str, region: &str, service: &str) -> Self {
+        Self {
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+            region: region.to_string(),
+            service: service.to_string(),
+        }
+    }
+
+    /// `Authorization` header value for `request` made at `at`. The request must
+    /// also carry `x-amz-date` set to [`amz_date`] of the same instant.
+    pub fn authorization(&self, request: &CanonicalRequest, at: DateTime<Utc>) -> String {
+        let (signed_headers, signature) = self.sign(request, at);
+        format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            ALGORITHM, self.access_key, self.scope(at), signed_headers, signature
+        )
+    }
+
+    /// Query string that authorizes `GET host/path` for `expires_secs` from `at`,
+    /// without any headers; append it to the URL after `?`.
+    pub fn presign(&self, host: &str, path: &str, expires_secs: u64, at: DateTime<Utc>) -> String {
+        let credential = format!("{}/{}", self.access_key, self.scope(at));
+        let date = amz_date(at);
+        let expires = expires_secs.to_string();
+        let query = [
+            ("X-Amz-Algorithm", ALGORITHM),
+            ("X-Amz-Credential", credential.as_str()),
+            ("X-Amz-Date", date.as_str()),
+            ("X-Amz-Expires", expires.as_str()),
+            ("X-Amz-SignedHeaders", "host"),
+        ];
+        let request = CanonicalRequest {
+            method: "GET",
+            host,
+            path,
+            query: &query,
+            headers: &[],
+            payload_hash: "UNSIGNED-PAYLOAD",
+        };
+        let (_, signature) = self.sign(&request, at);
+        format!("{}&X-Amz-Signature={}", canonical_query(&query), signature)
+    }
+
+    /// Signed header list and hex signature.
+    fn sign(&self, request: &CanonicalRequest, at: DateTime<Utc>) -> (String, String) {
+        let mut headers: Vec<(String, String)> = request.headers
+            .iter()
+            .map(|(name, value)| (name.to_ascii_lowercase(), collapse_whitespace(value)))
+            .collect();
+        headers.push(("host".to_string(), request.host.to_string()));
+        headers.sort();
+        let signed_headers = headers.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>().join(";");
+        let canonical_headers: String = headers.iter().map(|(n, v)| format!("{}:{}\n", n, v)).collect();
+
+        let canonical = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            request.method,
+            request.path,
+            canonical_query(request.query),
+            canonical_headers,
+            signed_headers,
+            request.payload_hash
+        );
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date(at),
+            self.scope(at),
+            hex::encode(Sha256::digest(canonical.as_bytes()))
+        );
+
+        let date_key = hmac(format!("AWS4{}", self.secret_key).as_bytes(), at.format("%Y%m%d").to_string().as_bytes());
+        let region_key = hmac(&date_key, self.region.as_bytes());
+        let service_key = hmac(&region_key, self.service.as_bytes());
+        let signing_key = hmac(&service_key, b"aws4_request");
+        (signed_headers, hex::encode(hmac(&signing_key, string_to_sign.as_bytes())))
+    }
+
+    fn scope(&self, at: DateTime<Utc>) -> String {
+        format!("{}/{}/{}/aws4_request", at.format("%Y%m%d"), self.region, self.service)
+    }
+}
+
+/// `x-amz-date` form of a timestamp, e.g. `20150830T123600Z`.
+pub fn amz_date(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Percent-encode everything but unreserved characters (and `/` when `keep_slash`), as SigV4 requires.
+pub fn uri_encode(input: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(byte as char),
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Canonical, encoded path of `key` in `bucket` for path-style requests.
+pub fn encode_path(bucket: &str, key: &str) -> String {
+    format!("/{}/{}", uri_encode(bucket, false), uri_encode(key, true))
+}
+
+fn canonical_query(query: &[(&str, &str)]) -> String {
+    let mut pairs: Vec<(String, String)> = query
+        .iter()
+        .map(|(k, v)| (uri_encode(k, false), uri_encode(v, false)))
+        .collect();
+    pairs.sort();
+    pairs.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&")
+}
+
+fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// ─── Client ────────────────────────────────────────────────────────────────
+
+fn http_client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT.min(timeout))
+        .timeout(timeout)
+        .build()
+        .expect("Failed to build S3 HTTP client")
+}
+
+/// One bucket on an S3-compatible service.
+pub struct S3Client {
+    http: reqwest::Client,
+    /// `http(s)://host[:port]` with no trailing slash.
+    base_url: String,
+    /// `host[:port]` as reqwest will send it in `Host`.
+    host: String,
+    bucket: String,
+    signer: Signer,
+    multipart_threshold: usize,
+    part_size: usize,
+}
+
+impl S3Client {
+    /// `endpoint` is `host[:port]`; `use_ssl` picks https over http.
+    pub fn new(endpoint: &str, use_ssl: bool, bucket: &str, region: &str, access_key: &str, secret_key: &str) -> Result<Self, S3Error> {
+        let scheme = if use_ssl { "https" } else { "http" };
+        let base_url = format!("{}://{}", scheme, endpoint.trim_end_matches('/'));
+        let parsed = url::Url::parse(&base_url)
+            .map_err(|e| S3Error::Transport(format!("invalid endpoint {}: {}", endpoint, e)))?;
+        let host = match (parsed.host_str(), parsed.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(S3Error::Transport(format!("endpoint {} has no host", endpoint))),
+        };
+        Ok(Self {
+            http: http_client(DEFAULT_TIMEOUT),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            host,
+            bucket: bucket.to_string(),
+            signer: Signer::new(access_key, secret_key, region, "s3"),
+            multipart_threshold: DEFAULT_MULTIPART_THRESHOLD,
+            part_size: DEFAULT_PART_SIZE,
+        })
+    }
+
+    /// Give up on any request that takes longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.http = http_client(timeout);
+        self
+    }
+
+    /// Upload objects larger than `threshold` bytes in parts of `part_size`.
+    pub fn with_multipart(mut self, threshold: usize, part_size: usize) -> Self {
+        self.multipart_threshold = threshold;
+        self.part_size = part_size.max(1);
+        self
+    }
+
+    /// Store `data` at `key` unless the key already exists, in which case
+    /// nothing is written and [`S3Error::AlreadyExists`] is returned.
+    pub async fn put_object(&self, key: &str, data: &[u8]) -> Result<(), S3Error> {
+        if data.len() > self.multipart_threshold {
+            return self.put_multipart(key, data).await;
+        }
+        let response = self.send(Method::PUT, key, &[], &[("if-none-match", "*")], data.to_vec()).await?;
+        check(response).await.map(|_| ())
+    }
+
+    /// The object's bytes, or `None` if there is no such key.
+    pub async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>, S3Error> {
+        let response = self.send(Method::GET, key, &[], &[], Vec::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check(response).await?;
+        let bytes = response.bytes().await.map_err(|e| S3Error::Transport(e.to_string()))?;
+        Ok(Some(bytes.to_vec()))
+    }
+
+    /// The object from byte `offset` on, as a response to read in chunks; `None`
+    /// if there is no such key. `offset` must lie within the object.
+    pub async fn get_object_from(&self, key: &str, offset: u64) -> Result<Option<reqwest::Response>, S3Error> {
+        let range = format!("bytes={}-", offset);
+        let headers: &[(&str, &str)] = if offset > 0 { &[("range", &range)] } else { &[] };
+        let response = self.send(Method::GET, key, &[], headers, Vec::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        check(response).await.map(Some)
+    }
+
+    /// Size, ETag and modification time of the object, or `None` if there is no such key.
+    pub async fn head_object(&self, key: &str) -> Result<Option<ObjectMeta>, S3Error> {
+        let response = self.send(Method::HEAD, key, &[], &[], Vec::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check(response).await?;
+        let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let len = header("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+        let last_modified = header("last-modified")
+            .and_then(|v| DateTime::parse_from_rfc2822(&v).ok())
+            .map(|t| t.with_timezone(&Utc));
+        Ok(Some(ObjectMeta { len, etag: header("etag"), last_modified }))
+    }
+
+    /// Delete the object; deleting a missing key succeeds.
+    pub async fn delete_object(&self, key: &str) -> Result<(), S3Error> {
+        let response = self.send(Method::DELETE, key, &[], &[], Vec::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        check(response).await.map(|_| ())
+    }
+
+    /// Keys under `prefix`, following continuation tokens until the listing ends.
+    pub async fn list_objects(&self, prefix: &str) -> Result<Vec<String>, S3Error> {
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let response = check(self.send_bucket(Method::GET, &query, Vec::new()).await?).await?;
+            let body = response.text().await.map_err(|e| S3Error::Transport(e.to_string()))?;
+            keys.extend(xml_values(&body, "Key").into_iter().map(|k| xml_unescape(&k)));
+            match xml_values(&body, "NextContinuationToken").into_iter().next() {
+                Some(next) if xml_values(&body, "IsTruncated").first().map(String::as_str) == Some("true") => {
+                    token = Some(xml_unescape(&next));
+                }
+                _ => return Ok(keys),
+            }
+        }
+    }
+
+    /// A URL anyone can `GET` the object from for `expires_secs` seconds.
+    pub fn presigned_get_url(&self, key: &str, expires_secs: u64) -> String {
+        self.presigned_get_url_at(key, expires_secs, Utc::now())
+    }
+
+    /// [`presigned_get_url`](Self::presigned_get_url) as of a fixed instant.
+    pub fn presigned_get_url_at(&self, key: &str, expires_secs: u64, at: DateTime<Utc>) -> String {
+        let path = encode_path(&self.bucket, key);
+        let query = self.signer.presign(&self.host, &path, expires_secs, at);
+        format!("{}{}?{}", self.base_url, path, query)
+    }
+
+    async fn put_multipart(&self, key: &str, data: &[u8]) -> Result<(), S3Error> {
+        // Fail fast instead of uploading every part only to lose the race at completion
+        if self.head_object(key).await?.is_some() {
+            return Err(S3Error::AlreadyExists);
+        }
+        let created = check(self.send(Method::POST, key, &[("uploads", "")], &[], Vec::new()).await?).await?;
+        let body = created.text().await.map_err(|e| S3Error::Transport(e.to_string()))?;
+        let upload_id = xml_values(&body, "UploadId")
+            .into_iter()
+            .next()
+            .map(|id| xml_unescape(&id))
+            .ok_or_else(|| S3Error::Transport("CreateMultipartUpload returned no UploadId".to_string()))?;
+
+        let result = self.upload_parts(key, &upload_id, data).await;
+        if result.is_err() {
+            // Best effort: an abandoned upload only costs storage until the bucket's lifecycle rule clears it
+            let _ = self.send(Method::DELETE, key, &[("uploadId", &upload_id)], &[], Vec::new()).await;
+        }
+        result
+    }
+
+    async fn upload_parts(&self, key: &str, upload_id: &str, data: &[u8]) -> Result<(), S3Error> {
+        let mut completion = String::from("<CompleteMultipartUpload>");
+        for (index, chunk) in data.chunks(self.part_size).enumerate() {
+            let part_number = (index + 1).to_string();
+            let query = [("partNumber", part_number.as_str()), ("uploadId", upload_id)];
+            let response = check(self.send(Method::PUT, key, &query, &[], chunk.to_vec()).await?).await?;
+            let etag = response.headers().get("etag").and_then(|v| v.to_str().ok())
+                .ok_or_else(|| S3Error::Transport(format!("UploadPart {} returned no ETag", part_number)))?;
+            completion.push_str(&format!("<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>", part_number, etag));
+        }
+        completion.push_str("</CompleteMultipartUpload>");
+
+        let query = [("uploadId", upload_id)];
+        let response = self.send(Method::POST, key, &query, &[("if-none-match", "*")], completion.into_bytes()).await?;
+        let response = check(response).await?;
+        // CompleteMultipartUpload can fail after sending 200, with the error in the body
+        let body = response.text().await.map_err(|e| S3Error::Transport(e.to_string()))?;
+        if body.contains("<Error>") {
+            return Err(error_from_body(StatusCode::OK, &body));
+        }
+        Ok(())
+    }
+
+    async fn send(&self, method: Method, key: &str, query: &[(&str, &str)], headers: &[(&str, &str)], body: Vec<u8>) -> Result<reqwest::Response, S3Error> {
+        let path = encode_path(&self.bucket, key);
+        self.send_path(method, &path, query, headers, body).await
+    }
+
+    async fn send_bucket(&self, method: Method, query: &[(&str, &str)], body: Vec<u8>) -> Result<reqwest::Response, S3Error> {
+        let path = format!("/{}", uri_encode(&self.bucket, false));
+        self.send_path(method, &path, query, &[], body).await
+    }
+
+    async fn send_path(&self, method: Method, path: &str, query: &[(&str, &str)], headers: &[(&str, &str)], body: Vec<u8>) -> Result<reqwest::Response, S3Error> {
+        let at = Utc::now();
+        let date = amz_date(at);
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let mut signed: Vec<(&str, &str)> = headers.to_vec();
+        signed.push(("x-amz-content-sha256", &payload_hash));
+        signed.push(("x-amz-date", &date));
+        let authorization = self.signer.authorization(&CanonicalRequest {
+            method: method.as_str(),
+            host: &self.host,
+            path,
+            query,
+            headers: &signed,
+            payload_hash: &payload_hash,
+        }, at);
+
+        let mut url = format!("{}{}", self.base_url, path);
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&canonical_query(query));
+        }
+        let mut request = self.http.request(method, &url).header("authorization", authorization);
+        for (name, value) in &signed {
+            request = request.header(*name, *value);
+        }
+        request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| S3Error::Transport(e.to_string()))
+    }
+}
+
+/// Pass successful responses through; turn the rest into [`S3Error`].
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, S3Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    if status == StatusCode::PRECONDITION_FAILED {
+        return Err(S3Error::AlreadyExists);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(error_from_body(status, &body))
+}
+
+fn error_from_body(status: StatusCode, body: &str) -> S3Error {
+    let code = xml_values(body, "Code").into_iter().next();
+    let message = xml_values(body, "Message").into_iter().next()
+        .map(|m| xml_unescape(&m))
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or("request failed").to_string());
+    S3Error::Status { status, code, message }
+}
+
+/// Text of every `<tag>…</tag>` element, in document order. S3 responses are
+/// flat enough that this is all the XML support the client needs.
+fn xml_values(body: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut values = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else { break };
+        values.push(rest[..end].to_string());
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,77 @@
+// src/source_cache.rs — file contents shown by the source browser
+//
+// Showing one file means fetching and decompressing its whole archive, so files
+// are kept in memory by `(checksum, path)` once read. An archive never changes
+// under its checksum, so entries never go stale; the least recently shown are
+// dropped once the cache holds more than its budget.
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// A file within the archive whose checksum comes first.
+type SourceKey = (String, String);
+
+#[derive(Debug)]
+pub struct SourceCache {
+    /// Budget in bytes of file contents.
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Contents, and the tick of their last use.
+    files: HashMap<SourceKey, (Arc<Vec<u8>>, u64)>,
+    /// Keys by last use, oldest first.
+    by_use: BTreeMap<u64, SourceKey>,
+    bytes: usize,
+    tick: u64,
+}
+
+impl SourceCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Mutex::new(Entries::default()) }
+    }
+
+    pub fn get(&self, checksum: &str, path: &str) -> Option<Arc<Vec<u8>>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = &mut *entries;
+        entries.tick += 1;
+        let key = (checksum.to_string(), path.to_string());
+        let (contents, used) = entries.files.get_mut(&key)?;
+        entries.by_use.remove(used);
+        *used = entries.tick;
+        entries.by_use.insert(entries.tick, key);
+        Some(contents.clone())
+    }
+
+    /// Keep `contents` unless they alone exceed the budget, evicting the least
+    /// recently used files to make room.
+    pub fn insert(&self, checksum: &str, path: &str, contents: Vec<u8>) -> Arc<Vec<u8>> {
+        let contents = Arc::new(contents);
+        if contents.len() > self.capacity {
+            return contents;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = &mut *entries;
+        entries.tick += 1;
+        let key = (checksum.to_string(), path.to_string());
+        if let Some((old, used)) = entries.files.insert(key.clone(), (contents.clone(), entries.tick)) {
+            entries.by_use.remove(&used);
+            entries.bytes -= old.len();
+        }
+        entries.by_use.insert(entries.tick, key);
+        entries.bytes += contents.len();
+        while entries.bytes > self.capacity {
+            let Some((_, oldest)) = entries.by_use.pop_first() else { break };
+            if let Some((evicted, _)) = entries.files.remove(&oldest) {
+                entries.bytes -= evicted.len();
+            }
+        }
+        contents
+    }
+
+    /// Bytes of file contents held.
+    pub fn size(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).bytes
+    }
+}
@@ -2,7 +2,9 @@
 use sqlx::PgPool;
 use std::sync::Arc;
 use crate::config;
+use crate::downloads::DownloadCounter;
 use crate::models::user::UserBackend;
+use crate::source_cache::SourceCache;
 use crate::storage;
 use webauthn_rs::prelude::*;
 use url::Url;
@@ -13,6 +15,8 @@ pub struct AppState {
     pub backend: UserBackend,
     pub webauthn: Arc<Webauthn>, // NEW for Passkeys
     pub storage: Arc<storage::StorageBackend>,
+    pub downloads: Arc<DownloadCounter>,
+    pub source_cache: Arc<SourceCache>,
 }
 
 pub fn new() -> Arc<AppState> {
@@ -31,5 +35,9 @@ pub fn new() -> Arc<AppState> {
 
     let storage = storage::from_env();
 
-    Arc::new(AppState { db, backend, webauthn, storage })
+    let downloads = Arc::new(DownloadCounter::new());
+
+    let source_cache = Arc::new(SourceCache::new(config::source_cache_bytes()));
+
+    Arc::new(AppState { db, backend, webauthn, storage, downloads, source_cache })
 }
@@ -1,36 +1,149 @@
-// src/storage.rs — Abstract storage layer: local filesystem or S3-compatible (Cloudflare R2)
+// src/storage.rs — Abstract storage layer: local filesystem, S3-compatible (Cloudflare R2) or memory
 // Falls back to local filesystem when cloud credentials are absent.
-// Uses reqwest directly for S3 API calls (no extra crate needed).
+// S3 requests are signed in-process by the client in `crate::s3`.
 
-use std::sync::Arc;
+use crate::s3::{S3Client, S3Error};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncRead;
+
+/// Why a storage operation failed.
+#[derive(Debug)]
+pub enum StorageError {
+    /// The key is already taken; published blobs are immutable.
+    AlreadyExists(String),
+    Backend(String),
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::AlreadyExists(key) => write!(f, "{} already exists", key),
+            StorageError::Backend(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// Size and modification time of a stored object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectMetadata {
+    pub len: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// A stored object being read from some offset onwards.
+pub struct ObjectStream {
+    pub reader: Pin<Box<dyn AsyncRead + Send>>,
+    /// Length of the whole object, not just the part left to read.
+    pub len: u64,
+}
+
+/// Operations every storage backend supports. Keys are relative paths built by
+/// [`crate::utils::blob_key`], such as `"sha256/3f/3f9a…"`; a missing key is
+/// `Ok(None)` / `Ok(false)`, never an error.
+pub trait Storage: Send + Sync {
+    /// Store bytes at `key`. Never overwrites: an existing key fails with
+    /// [`StorageError::AlreadyExists`].
+    fn store(&self, key: &str, data: &[u8]) -> impl Future<Output = Result<(), StorageError>> + Send;
+
+    /// The whole object at `key`.
+    fn get(&self, key: &str) -> impl Future<Output = Result<Option<Vec<u8>>, StorageError>> + Send;
+
+    /// Stream the object at `key` starting `offset` bytes in, without loading it
+    /// into memory. `offset` must not exceed the object's length.
+    fn get_stream(&self, key: &str, offset: u64) -> impl Future<Output = Result<Option<ObjectStream>, StorageError>> + Send;
+
+    /// Whether an object is stored at `key`.
+    fn exists(&self, key: &str) -> impl Future<Output = Result<bool, StorageError>> + Send {
+        async move { Ok(self.metadata(key).await?.is_some()) }
+    }
+
+    /// Remove the object at `key`; removing a missing key succeeds.
+    fn delete(&self, key: &str) -> impl Future<Output = Result<(), StorageError>> + Send;
+
+    /// Every key starting with `prefix`, sorted.
+    fn list(&self, prefix: &str) -> impl Future<Output = Result<Vec<String>, StorageError>> + Send;
+
+    fn metadata(&self, key: &str) -> impl Future<Output = Result<Option<ObjectMetadata>, StorageError>> + Send;
+
+    /// A URL clients can fetch the object from.
+    fn download_url(&self, key: &str) -> String;
+}
 
 /// Unified storage backend for zorb packages.
 pub enum StorageBackend {
     Local(LocalStorage),
     S3(S3Storage),
+    Memory(MemoryStorage),
 }
 
 impl StorageBackend {
-    /// Store bytes at the given key (e.g. `"crypto/bsv58-0.2.0.zorb"`).
-    pub async fn store(&self, key: &str, data: &[u8]) -> Result<(), String> {
+    pub fn backend_name(&self) -> &str {
+        match self {
+            StorageBackend::Local(_) => "local",
+            StorageBackend::S3(_) => "s3",
+            StorageBackend::Memory(_) => "memory",
+        }
+    }
+}
+
+impl Storage for StorageBackend {
+    async fn store(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
         match self {
             StorageBackend::Local(s) => s.store(key, data).await,
             StorageBackend::S3(s) => s.store(key, data).await,
+            StorageBackend::Memory(s) => s.store(key, data).await,
         }
     }
 
-    /// Return a download URL for the given key.
-    pub fn download_url(&self, key: &str) -> String {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
         match self {
-            StorageBackend::Local(s) => s.download_url(key),
-            StorageBackend::S3(s) => s.download_url(key),
+            StorageBackend::Local(s) => s.get(key).await,
+            StorageBackend::S3(s) => s.get(key).await,
+            StorageBackend::Memory(s) => s.get(key).await,
         }
     }
 
-    pub fn backend_name(&self) -> &str {
+    async fn get_stream(&self, key: &str, offset: u64) -> Result<Option<ObjectStream>, StorageError> {
         match self {
-            StorageBackend::Local(_) => "local",
-            StorageBackend::S3(_) => "s3",
+            StorageBackend::Local(s) => s.get_stream(key, offset).await,
+            StorageBackend::S3(s) => s.get_stream(key, offset).await,
+            StorageBackend::Memory(s) => s.get_stream(key, offset).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match self {
+            StorageBackend::Local(s) => s.delete(key).await,
+            StorageBackend::S3(s) => s.delete(key).await,
+            StorageBackend::Memory(s) => s.delete(key).await,
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        match self {
+            StorageBackend::Local(s) => s.list(prefix).await,
+            StorageBackend::S3(s) => s.list(prefix).await,
+            StorageBackend::Memory(s) => s.list(prefix).await,
+        }
+    }
+
+    async fn metadata(&self, key: &str) -> Result<Option<ObjectMetadata>, StorageError> {
+        match self {
+            StorageBackend::Local(s) => s.metadata(key).await,
+            StorageBackend::S3(s) => s.metadata(key).await,
+            StorageBackend::Memory(s) => s.metadata(key).await,
+        }
+    }
+
+    fn download_url(&self, key: &str) -> String {
+        match self {
+            StorageBackend::Local(s) => s.download_url(key),
+            StorageBackend::S3(s) => s.download_url(key),
+            StorageBackend::Memory(s) => s.download_url(key),
         }
     }
 }
@@ -46,16 +159,119 @@ impl LocalStorage {
         Self { base_path: base_path.to_string() }
     }
 
-    async fn store(&self, key: &str, data: &[u8]) -> Result<(), String> {
+    fn path_of(&self, key: &str) -> String {
+        format!("{}/{}", self.base_path, key)
+    }
+}
+
+impl Storage for LocalStorage {
+    async fn store(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
         use std::path::Path;
         use tokio::fs;
-        let full_path = format!("{}/{}", self.base_path, key);
-        if let Some(parent) = Path::new(&full_path).parent() {
-            fs::create_dir_all(parent).await
-                .map_err(|e| format!("mkdir: {}", e))?;
+        use tokio::io::AsyncWriteExt;
+        let full_path = self.path_of(key);
+        let path = Path::new(&full_path);
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(StorageError::Backend(format!("invalid key: {}", key)));
+        };
+        fs::create_dir_all(parent).await
+            .map_err(|e| StorageError::Backend(format!("mkdir: {}", e)))?;
+        // Write and sync a temporary file next to the object, then link it into place,
+        // so the key only ever names a complete object. Linking fails if the key is
+        // taken, which makes the existence check and the create a single atomic step.
+        let temp = parent.join(format!(".{}.{}.tmp", name.to_string_lossy(), uuid::Uuid::new_v4()));
+        let written = async {
+            let mut file = fs::OpenOptions::new().write(true).create_new(true).open(&temp).await
+                .map_err(|e| format!("create: {}", e))?;
+            file.write_all(data).await.map_err(|e| format!("write: {}", e))?;
+            file.sync_all().await.map_err(|e| format!("sync: {}", e))
+        }.await;
+        let linked = match written {
+            Ok(()) => fs::hard_link(&temp, path).await.map_err(|e| match e.kind() {
+                std::io::ErrorKind::AlreadyExists => StorageError::AlreadyExists(key.to_string()),
+                _ => StorageError::Backend(format!("link: {}", e)),
+            }),
+            Err(e) => Err(StorageError::Backend(e)),
+        };
+        let _ = fs::remove_file(&temp).await;
+        linked
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match tokio::fs::read(self.path_of(key)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(StorageError::Backend(format!("read: {}", e))),
+        }
+    }
+
+    async fn get_stream(&self, key: &str, offset: u64) -> Result<Option<ObjectStream>, StorageError> {
+        use tokio::io::AsyncSeekExt;
+        let mut file = match tokio::fs::File::open(self.path_of(key)).await {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(StorageError::Backend(format!("open: {}", e))),
+        };
+        let len = file.metadata().await
+            .map_err(|e| StorageError::Backend(format!("stat: {}", e)))?
+            .len();
+        if offset > 0 {
+            file.seek(std::io::SeekFrom::Start(offset)).await
+                .map_err(|e| StorageError::Backend(format!("seek: {}", e)))?;
+        }
+        Ok(Some(ObjectStream { reader: Box::pin(file), len }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match tokio::fs::remove_file(self.path_of(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(StorageError::Backend(format!("remove: {}", e))),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        // Walk the whole tree: keys may contain `/`, and a prefix need not end at one
+        let mut keys = Vec::new();
+        let mut pending = vec![String::new()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(self.path_of(&dir)).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(StorageError::Backend(format!("list: {}", e))),
+            };
+            while let Some(entry) = entries.next_entry().await
+                .map_err(|e| StorageError::Backend(format!("list: {}", e)))?
+            {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                // Temporary files of writes in progress
+                if file_name.starts_with('.') {
+                    continue;
+                }
+                let key = format!("{}{}", dir, file_name);
+                let file_type = entry.file_type().await
+                    .map_err(|e| StorageError::Backend(format!("list: {}", e)))?;
+                if file_type.is_dir() {
+                    pending.push(format!("{}/", key));
+                } else if key.starts_with(prefix) {
+                    keys.push(key);
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn metadata(&self, key: &str) -> Result<Option<ObjectMetadata>, StorageError> {
+        match tokio::fs::metadata(self.path_of(key)).await {
+            Ok(meta) if meta.is_file() => Ok(Some(ObjectMetadata {
+                len: meta.len(),
+                last_modified: meta.modified().ok().map(DateTime::<Utc>::from),
+            })),
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(StorageError::Backend(format!("stat: {}", e))),
         }
-        fs::write(&full_path, data).await
-            .map_err(|e| format!("write: {}", e))
     }
 
     fn download_url(&self, key: &str) -> String {
@@ -65,84 +281,187 @@ impl LocalStorage {
 
 // ─── S3-compatible (Cloudflare R2 / AWS S3 / MinIO) ───────────────────────
 
+/// Lifetime of presigned download URLs handed out when the bucket has no public URL.
+const PRESIGNED_URL_TTL_SECS: u64 = 15 * 60;
+
 pub struct S3Storage {
-    bucket: String,
-    endpoint: String,
+    client: S3Client,
     public_url_base: String,
-    access_key: String,
-    secret_key: String,
-    use_ssl: bool,
 }
 
 impl S3Storage {
     pub fn new(
-        bucket: &str, endpoint: &str, public_url_base: &str,
+        bucket: &str, endpoint: &str, region: &str, public_url_base: &str,
         access_key: &str, secret_key: &str, use_ssl: bool,
-    ) -> Self {
-        Self {
-            bucket: bucket.to_string(),
-            endpoint: endpoint.to_string(),
-            public_url_base: public_url_base.to_string(),
-            access_key: access_key.to_string(),
-            secret_key: secret_key.to_string(),
-            use_ssl,
-        }
-    }
-
-    /// S3 PUT object using MinIO Client (mc) with built-in AWS SigV4 support.
-    /// mc's SigV4 implementation is battle-tested and works with all S3-compatible stores.
-    async fn store(&self, key: &str, data: &[u8]) -> Result<(), String> {
-        let mc_alias = "myminio";
-
-        // Write data to a temp file for mc
-        let tmpfile = format!("/tmp/zorb_upload_{}", std::process::id());
-        tokio::fs::write(&tmpfile, data).await
-            .map_err(|e| format!("Failed to write temp file: {}", e))?;
-
-        // Build the S3 endpoint URL for mc's alias
-        let scheme = if self.use_ssl { "https" } else { "http" };
-
-        // First set up the alias, then upload to the correct key
-        let setup_cmd = format!(
-            "mc alias set {} {}://{} {} {} && mc cp {} {}/{}/{}",
-            mc_alias,
-            scheme,
-            self.endpoint,
-            self.access_key,
-            self.secret_key,
-            tmpfile,
-            mc_alias,
-            self.bucket,
-            key
-        );
-
-        let output = tokio::process::Command::new("sh")
-            .args(["-c", &setup_cmd])
-            .output()
-            .await
-            .map_err(|e| format!("mc execution failed: {}", e))?;
-
-        // Cleanup temp file
-        let _ = tokio::fs::remove_file(&tmpfile).await;
-
-        if output.status.success() {
-            Ok(())
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            Err(format!("S3 PUT failed (exit={}): {} {}", output.status, stdout.trim(), stderr.trim()))
+    ) -> Result<Self, StorageError> {
+        let client = S3Client::new(endpoint, use_ssl, bucket, region, access_key, secret_key)
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+        Ok(Self { client, public_url_base: public_url_base.to_string() })
+    }
+}
+
+fn s3_error(operation: &str, key: &str, e: S3Error) -> StorageError {
+    StorageError::Backend(format!("S3 {} {} failed: {}", operation, key, e))
+}
+
+impl Storage for S3Storage {
+    /// Conditional PUT (`If-None-Match: *`), so a taken key is rejected by the
+    /// bucket itself rather than by a racy check beforehand.
+    async fn store(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
+        match self.client.put_object(key, data).await {
+            Ok(()) => Ok(()),
+            Err(S3Error::AlreadyExists) => Err(StorageError::AlreadyExists(key.to_string())),
+            Err(e) => Err(s3_error("PUT", key, e)),
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        self.client.get_object(key).await.map_err(|e| s3_error("GET", key, e))
+    }
+
+    async fn get_stream(&self, key: &str, offset: u64) -> Result<Option<ObjectStream>, StorageError> {
+        use tokio::io::AsyncWriteExt;
+        let Some(mut response) = self.client.get_object_from(key, offset).await
+            .map_err(|e| s3_error("GET", key, e))?
+        else {
+            return Ok(None);
+        };
+        let header = |name| response.headers().get(name).and_then(|v| v.to_str().ok());
+        // A ranged response carries the full length after the slash of Content-Range
+        let len = match header("content-range").and_then(|v| v.rsplit('/').next()) {
+            Some(total) => total.parse().ok(),
+            None => header("content-length").and_then(|v| v.parse().ok()),
         }
+        .ok_or_else(|| StorageError::Backend(format!("S3 GET {} returned no length", key)))?;
+
+        // Pump the body through a pipe so readers see a plain AsyncRead. A failed
+        // transfer closes the pipe early, which readers observe as a short body.
+        let (mut writer, reader) = tokio::io::duplex(64 * 1024);
+        let key = key.to_string();
+        tokio::spawn(async move {
+            loop {
+                match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        if writer.write_all(&chunk).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) => return,
+                    Err(e) => {
+                        tracing::warn!("S3 GET {} interrupted: {}", key, e);
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Some(ObjectStream { reader: Box::pin(reader), len }))
     }
 
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.client.delete_object(key).await.map_err(|e| s3_error("DELETE", key, e))
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let mut keys = self.client.list_objects(prefix).await.map_err(|e| s3_error("LIST", prefix, e))?;
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn metadata(&self, key: &str) -> Result<Option<ObjectMetadata>, StorageError> {
+        let meta = self.client.head_object(key).await.map_err(|e| s3_error("HEAD", key, e))?;
+        Ok(meta.map(|m| ObjectMetadata { len: m.len, last_modified: m.last_modified }))
+    }
+
+    /// The public (CDN) URL when one is configured, otherwise a short-lived presigned GET.
     fn download_url(&self, key: &str) -> String {
+        if self.public_url_base.is_empty() {
+            return self.client.presigned_get_url(key, PRESIGNED_URL_TTL_SECS);
+        }
         format!("{}/{}", self.public_url_base.trim_end_matches('/'), key)
     }
 }
 
+// ─── In-memory (tests, ephemeral registries) ──────────────────────────────
+
+struct MemoryObject {
+    data: Arc<Vec<u8>>,
+    stored_at: DateTime<Utc>,
+}
+
+/// Keeps every object in process memory; nothing survives a restart.
+#[derive(Default)]
+pub struct MemoryStorage {
+    objects: Mutex<BTreeMap<String, MemoryObject>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn objects(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, MemoryObject>> {
+        self.objects.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Storage for MemoryStorage {
+    async fn store(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
+        let mut objects = self.objects();
+        if objects.contains_key(key) {
+            return Err(StorageError::AlreadyExists(key.to_string()));
+        }
+        objects.insert(key.to_string(), MemoryObject { data: Arc::new(data.to_vec()), stored_at: Utc::now() });
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.objects().get(key).map(|o| o.data.to_vec()))
+    }
+
+    async fn get_stream(&self, key: &str, offset: u64) -> Result<Option<ObjectStream>, StorageError> {
+        let Some(data) = self.objects().get(key).map(|o| Arc::clone(&o.data)) else {
+            return Ok(None);
+        };
+        let len = data.len() as u64;
+        let mut cursor = std::io::Cursor::new(ArcBytes(data));
+        cursor.set_position(offset.min(len));
+        Ok(Some(ObjectStream { reader: Box::pin(cursor), len }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.objects().remove(key);
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        Ok(self.objects().keys().filter(|k| k.starts_with(prefix)).cloned().collect())
+    }
+
+    async fn metadata(&self, key: &str) -> Result<Option<ObjectMetadata>, StorageError> {
+        Ok(self.objects().get(key).map(|o| ObjectMetadata {
+            len: o.data.len() as u64,
+            last_modified: Some(o.stored_at),
+        }))
+    }
+
+    fn download_url(&self, key: &str) -> String {
+        format!("/uploads/{}", key)
+    }
+}
+
+/// Shared object bytes readable through a `Cursor` without copying them.
+struct ArcBytes(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for ArcBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 // ─── Factory ──────────────────────────────────────────────────────────────
 
-/// Build the storage backend from environment configuration.
-pub fn from_env() -> Arc<StorageBackend> {
+/// The S3-compatible backend configured by the `R2_*` (or `AWS_*`) variables, if any.
+fn s3_from_env() -> Option<S3Storage> {
     let bucket = std::env::var("R2_BUCKET").unwrap_or_default();
     let endpoint = std::env::var("R2_ENDPOINT").unwrap_or_default();
     let public_url = std::env::var("R2_PUBLIC_URL").unwrap_or_default();
@@ -152,20 +471,42 @@ pub fn from_env() -> Arc<StorageBackend> {
     let secret_key = std::env::var("R2_SECRET_ACCESS_KEY")
         .or_else(|_| std::env::var("AWS_SECRET_ACCESS_KEY"))
         .unwrap_or_default();
+    if bucket.is_empty() || access_key.is_empty() {
+        return None;
+    }
+    let use_ssl = std::env::var("R2_USE_SSL")
+        .unwrap_or_else(|_| "true".to_string())
+        .to_lowercase() == "true";
+    // R2 accepts "auto"; AWS and MinIO need the bucket's real region
+    let region = std::env::var("R2_REGION")
+        .or_else(|_| std::env::var("AWS_REGION"))
+        .unwrap_or_else(|_| "us-east-1".to_string());
+    let storage = S3Storage::new(&bucket, &endpoint, &region, &public_url, &access_key, &secret_key, use_ssl)
+        .unwrap_or_else(|e| panic!("Invalid S3 configuration: {}", e));
+    tracing::info!("Storage: S3-compatible ({})", endpoint);
+    Some(storage)
+}
 
-    if !bucket.is_empty() && !access_key.is_empty() {
-        let use_ssl = std::env::var("R2_USE_SSL")
-            .unwrap_or_else(|_| "true".to_string())
-            .to_lowercase() == "true";
-        let storage = S3Storage::new(&bucket, &endpoint, &public_url, &access_key, &secret_key, use_ssl);
-        tracing::info!("Storage: S3-compatible ({})", endpoint);
-        Arc::new(StorageBackend::S3(storage))
-    } else {
-        let upload_dir = std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "uploads".to_string());
-        tracing::info!("Storage: local ({})", upload_dir);
-        Arc::new(StorageBackend::Local(LocalStorage::new(&upload_dir)))
+/// Build the storage backend from environment configuration.
+pub fn from_env() -> Arc<StorageBackend> {
+    match s3_from_env() {
+        Some(storage) => Arc::new(StorageBackend::S3(storage)),
+        None => {
+            let upload_dir = std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "uploads".to_string());
+            tracing::info!("Storage: local ({})", upload_dir);
+            Arc::new(StorageBackend::Local(LocalStorage::new(&upload_dir)))
+        }
     }
 }
 
-// Crypto helper kept for reference; active S3 storage uses curl --aws-sigv4 directly
-// (which has a battle-tested SigV4 implementation).
+/// Build a backend from a command-line spec: `local:<dir>`, or `s3` for the
+/// bucket configured by the `R2_*` variables.
+pub fn from_spec(spec: &str) -> Result<StorageBackend, String> {
+    match spec.split_once(':') {
+        Some(("local", dir)) if !dir.is_empty() => Ok(StorageBackend::Local(LocalStorage::new(dir))),
+        None if spec == "s3" || spec == "r2" => s3_from_env()
+            .map(StorageBackend::S3)
+            .ok_or_else(|| "S3 storage needs R2_BUCKET and R2_ACCESS_KEY_ID (or AWS_ACCESS_KEY_ID)".to_string()),
+        _ => Err(format!("Unknown storage '{}': expected local:<dir> or s3", spec)),
+    }
+}
@@ -1,10 +1,7 @@
 // src/utils.rs
-use std::io::{Cursor, Read};
-use flate2::read::GzDecoder;
-use tar::Archive;
-use toml::Value;
-use serde_json;
-use crate::models::NewZorb;
+use crate::archive;
+use crate::manifest::{Manifest, ParsedManifest};
+use crate::models::{FileEntry, NewZorb};
 use semver::Version;
 
 const MAX_UPLOAD_SIZE: usize = 50 * 1024 * 1024; // 50 MB
@@ -29,11 +26,25 @@ const BLOCKED_WORDS: &[&str] = &[
     "adult", "ass", "cock", "vagina", "penis", "kill", "murder", "suicide",
 ];
 
+/// Filename offered to clients downloading `name@version`. Distinct names can
+/// flatten to the same filename, so it is never used as a storage key.
 pub fn zorb_filename(name: &str, version: &str) -> String {
-    let sanitized = name.replace('@', "").replace('/', "-").replace(' ', "-").to_lowercase();
+    let sanitized = name.replace('@', "").replace(['/', ' '], "-").to_lowercase();
     format!("{}-{}.zorb", sanitized, version)
 }
 
+/// Hex-encoded SHA-256 of a `.zorb` archive, as stored in `zorbs.checksum`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(data))
+}
+
+/// Storage key of the archive whose [`sha256_hex`] is `checksum`. Keys depend only
+/// on content, so identical archives share one blob and packages never collide.
+pub fn blob_key(checksum: &str) -> String {
+    format!("sha256/{}/{}", checksum.get(..2).unwrap_or(checksum), checksum)
+}
+
 pub fn validate_package_name(name: &str) -> Result<(), String> {
     if name.is_empty() {
         return Err("Package name cannot be empty".to_string());
@@ -82,118 +93,54 @@ pub fn validate_version(version: &str) -> Result<(), String> {
     }
 }
 
-fn is_safe_path(path: &str) -> bool {
-    !path.contains("..") &&
-    !path.starts_with('/') &&
-    !path.starts_with('\\') &&
-    !path.contains("/../") &&
-    !path.contains("\\..\\")
+/// Error from [`parse_zorb_toml`] when the archive has no manifest at all; the web
+/// publish form may then supply the metadata itself.
+pub const NO_ZORB_TOML: &str = "No zorb.toml found in the uploaded tarball";
+
+/// The `zorb.toml` nearest the archive root, for archives packed inside a
+/// top-level directory.
+pub fn manifest_entry(files: &[FileEntry]) -> Option<&FileEntry> {
+    files
+        .iter()
+        .filter(|f| f.path.ends_with("zorb.toml") || f.path.ends_with("Zorb.toml"))
+        .min_by_key(|f| f.path.matches('/').count())
 }
 
 pub fn parse_zorb_toml(file_bytes: &[u8]) -> Result<NewZorb, String> {
     if file_bytes.len() > MAX_UPLOAD_SIZE {
         return Err(format!("Upload too large. Maximum size is {} MB", MAX_UPLOAD_SIZE / 1024 / 1024));
     }
-    let cursor = Cursor::new(file_bytes);
-    let decoder = GzDecoder::new(cursor);
-    let mut archive = Archive::new(decoder);
-    let entries = match archive.entries() {
-        Ok(e) => e,
-        Err(e) => return Err(format!("Failed to read tar archive: {}", e)),
+    let files = archive::inspect(file_bytes, &archive::Limits::default()).map_err(|e| e.to_string())?;
+    let read = |path: &str| -> Result<Option<String>, String> {
+        let bytes = archive::read_file(file_bytes, path).map_err(|e| e.to_string())?;
+        Ok(bytes.and_then(|b| String::from_utf8(b).ok()))
     };
 
-    let mut zorb_name: Option<String> = None;
-    let mut zorb_version: Option<String> = None;
-    let mut zorb_description: Option<String> = None;
-    let mut zorb_license: Option<String> = None;
-    let mut zorb_repository: Option<String> = None;
-    let mut zorb_dependencies: serde_json::Value = serde_json::Value::Object(Default::default());
-    let mut zorb_readme: Option<String> = None;
-    let mut found_toml = false;
-
-    for entry_result in entries {
-        let mut entry = match entry_result {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
-        let path = match entry.path() {
-            Ok(p) => p.to_string_lossy().into_owned(),
-            Err(_) => continue,
-        };
-        if !is_safe_path(&path) {
-            return Err("Path traversal attempt detected in tarball".to_string());
-        }
-
-        // Extract README.md from root
-        let path_lower = path.to_lowercase();
-        if path_lower == "readme.md" {
-            let mut content = String::new();
-            if entry.read_to_string(&mut content).is_ok() && !content.is_empty() {
-                zorb_readme = Some(content);
-            }
-            continue;
-        }
-
-        if path.ends_with("zorb.toml") || path.ends_with("Zorb.toml") {
-            let mut content = String::new();
-            if let Err(e) = entry.read_to_string(&mut content) {
-                return Err(format!("Failed to read zorb.toml content: {}", e));
-            }
-            let parsed: Value = match toml::from_str(&content) {
-                Ok(v) => v,
-                Err(e) => return Err(format!("Failed to parse zorb.toml: {}", e)),
-            };
-            let package = match parsed.get("package").and_then(Value::as_table) {
-                Some(p) => p,
-                None => return Err("Missing [package] section in zorb.toml".to_string()),
-            };
-            let name = match package.get("name").and_then(Value::as_str) {
-                Some(n) => n.to_string(),
-                None => return Err("Missing 'name' field in zorb.toml [package]".to_string()),
-            };
-            let version = match package.get("version").and_then(Value::as_str) {
-                Some(v) => v.to_string(),
-                None => return Err("Missing 'version' field in zorb.toml [package]".to_string()),
-            };
-            validate_package_name(&name)?;
-            validate_version(&version)?;
-            zorb_name = Some(name);
-            zorb_version = Some(version);
-            zorb_description = package.get("description").and_then(Value::as_str).map(str::to_string);
-            zorb_license = package.get("license").and_then(Value::as_str).map(str::to_string);
-            zorb_repository = package.get("repository").and_then(Value::as_str).map(str::to_string);
-
-            // Extract [dependencies] section
-            if let Some(deps) = parsed.get("dependencies").and_then(Value::as_table) {
-                let mut map = serde_json::Map::new();
-                for (dep_name, dep_val) in deps {
-                    let version_str = match dep_val {
-                        Value::String(s) => s.clone(),
-                        Value::Table(t) => {
-                            t.get("version").and_then(Value::as_str).unwrap_or("*").to_string()
-                        }
-                        _ => dep_val.to_string(),
-                    };
-                    map.insert(dep_name.clone(), serde_json::Value::String(version_str));
-                }
-                zorb_dependencies = serde_json::Value::Object(map);
-            }
-
-            found_toml = true;
-        }
-    }
+    // Extract README.md from root
+    let readme = match files.iter().find(|f| f.path.to_lowercase() == "readme.md") {
+        Some(readme) => read(&readme.path)?.filter(|content| !content.is_empty()),
+        None => None,
+    };
 
-    if !found_toml {
-        return Err("No zorb.toml found in the uploaded tarball".to_string());
-    }
+    let manifest = manifest_entry(&files).ok_or_else(|| NO_ZORB_TOML.to_string())?;
+    let content = read(&manifest.path)?
+        .ok_or_else(|| "Failed to read zorb.toml content: not valid UTF-8".to_string())?;
+    let ParsedManifest { manifest, warnings } = Manifest::parse(&content)?;
+    validate_package_name(&manifest.package.name)?;
 
+    let package = manifest.package.clone();
     Ok(NewZorb {
-        name: zorb_name.unwrap(),
-        version: zorb_version.unwrap(),
-        description: zorb_description,
-        license: zorb_license,
-        repository: zorb_repository,
-        dependencies: zorb_dependencies,
-        readme: zorb_readme,
+        name: package.name,
+        version: package.version,
+        description: package.description,
+        license: package.license,
+        repository: package.repository,
+        dependencies: manifest.dependency_requirements(),
+        readme,
+        keywords: package.keywords,
+        categories: package.categories,
+        files,
+        manifest: Some(manifest),
+        warnings,
     })
 }
@@ -2,3 +2,5 @@ pub const HOME_HTML: &str = include_str!("home.html");
 pub const PUBLISH_HTML: &str = include_str!("publish.html");
 pub const DETAIL_HTML: &str = include_str!("detail.html");
 pub const DOCS_HTML: &str = include_str!("docs.html");
+pub const CATEGORIES_HTML: &str = include_str!("categories.html");
+pub const SOURCE_HTML: &str = include_str!("source.html");
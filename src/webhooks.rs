@@ -0,0 +1,298 @@
+// src/webhooks.rs — outgoing webhook queue
+//
+// Events are written to `webhook_deliveries` in the same request that caused
+// them, one row per subscribed webhook, and a background worker POSTs them.
+// Failed deliveries stay in the table and are retried with exponential backoff,
+// so the queue survives restarts and doubles as each webhook's delivery log.
+//
+// Receivers must be on public addresses: otherwise anyone who can register a
+// webhook could make the registry POST to its own network, or to the cloud
+// metadata service at 169.254.169.254. Operators list internal receivers in
+// `WEBHOOK_ALLOWED_HOSTS`.
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+use crate::config;
+use crate::models::WebhookEvent;
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the body, keyed by the webhook secret>`.
+pub const SIGNATURE_HEADER: &str = "X-Zorbs-Signature";
+/// Header carrying the event name, e.g. `published`.
+pub const EVENT_HEADER: &str = "X-Zorbs-Event";
+/// Header carrying the delivery id; it stays the same across retries.
+pub const DELIVERY_HEADER: &str = "X-Zorbs-Delivery";
+
+/// Deliveries are given up (`failed`) after this many attempts.
+pub const MAX_ATTEMPTS: i32 = 8;
+/// Wait before the first retry; doubled after every further failure.
+const FIRST_RETRY: Duration = Duration::from_secs(30);
+/// How long a claimed delivery is hidden from other workers while it is sent.
+const LEASE: Duration = Duration::from_secs(60);
+/// Deliveries sent per poll.
+const BATCH: i64 = 50;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Queue `event` for every webhook of `package` and every registry-wide webhook
+/// subscribed to it. `data` is merged into the payload next to `event`,
+/// `package` and `occurred_at`. Returns how many deliveries were queued.
+pub async fn enqueue(pool: &PgPool, event: WebhookEvent, package: &str, data: Value) -> Result<u64, sqlx::Error> {
+    let mut payload = json!({
+        "event": event.as_str(),
+        "package": package,
+        "occurred_at": Utc::now(),
+    });
+    if let (Some(payload), Value::Object(data)) = (payload.as_object_mut(), data) {
+        payload.extend(data);
+    }
+    let result = sqlx::query(
+        "INSERT INTO webhook_deliveries (webhook_id, event, payload)
+         SELECT id, $2, $3 FROM webhooks
+         WHERE (package = $1 OR package IS NULL) AND $2 = ANY(events)"
+    )
+    .bind(package)
+    .bind(event.as_str())
+    .bind(&payload)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// [`enqueue`] for handlers: the action the event reports has already happened,
+/// so a failure to queue is logged rather than failing the request.
+pub async fn notify(pool: &PgPool, event: WebhookEvent, package: &str, data: Value) {
+    if let Err(e) = enqueue(pool, event, package, data).await {
+        tracing::error!("Failed to queue '{}' webhooks for '{}': {}", event.as_str(), package, e);
+    }
+}
+
+/// The signature header value for `body`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Whether `ip` is reachable from the public internet, rather than loopback,
+/// a private or shared network, link-local, multicast or otherwise reserved.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified()
+                || ip.is_broadcast() || ip.is_documentation() || ip.is_multicast()
+                || a == 0 || a >= 240
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (b == 18 || b == 19)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+        }
+    }
+}
+
+fn is_allowed_host(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    config::webhook_allowed_hosts().iter().any(|h| h.eq_ignore_ascii_case(host))
+}
+
+/// Check that `url` may receive deliveries: its host is in `WEBHOOK_ALLOWED_HOSTS`,
+/// or every address it resolves to is public.
+pub async fn check_destination(url: &str) -> Result<(), String> {
+    let url = url::Url::parse(url).map_err(|e| e.to_string())?;
+    let host = url.host_str().ok_or("the URL has no host")?;
+    if is_allowed_host(host) {
+        return Ok(());
+    }
+    let addrs: Vec<SocketAddr> = match url.host() {
+        Some(url::Host::Ipv4(ip)) => vec![SocketAddr::new(ip.into(), 0)],
+        Some(url::Host::Ipv6(ip)) => vec![SocketAddr::new(ip.into(), 0)],
+        _ => tokio::net::lookup_host((host, url.port_or_known_default().unwrap_or(80))).await
+            .map_err(|e| format!("{} does not resolve: {}", host, e))?
+            .collect(),
+    };
+    match addrs.iter().find(|a| !is_public(a.ip())) {
+        Some(addr) => Err(format!("{} is not a public address", addr.ip())),
+        None if addrs.is_empty() => Err(format!("{} does not resolve", host)),
+        None => Ok(()),
+    }
+}
+
+/// Resolves receiver names for the delivery client, keeping only public addresses
+/// so that a name registered while public cannot later be pointed inside.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let allowed = is_allowed_host(&host);
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?
+                .filter(|a| allowed || is_public(a.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// The HTTP client deliveries are sent with. Redirects are not followed, since
+/// they could lead anywhere; a 3xx answer is a failed attempt.
+pub fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(std::sync::Arc::new(PublicResolver))
+        .user_agent(concat!("zorbs-webhooks/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .expect("Failed to build webhook HTTP client")
+}
+
+#[derive(sqlx::FromRow)]
+struct Due {
+    id: Uuid,
+    event: String,
+    payload: Value,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+/// Send every delivery that is due and return how many were attempted. A 2xx
+/// answer marks a delivery `delivered`; anything else schedules a retry, until
+/// the last attempt marks it `failed`.
+pub async fn deliver_due(pool: &PgPool, client: &reqwest::Client) -> Result<usize, sqlx::Error> {
+    deliver_due_within(pool, client, LEASE).await
+}
+
+/// [`deliver_due`] with deliveries leased for `lease`. The batch is sent at once and
+/// each attempt is cut off after [`TIMEOUT`] or half the lease, whichever is shorter,
+/// so every outcome is recorded before the lease ends and no other worker sends the
+/// same delivery again.
+pub async fn deliver_due_within(pool: &PgPool, client: &reqwest::Client, lease: Duration) -> Result<usize, sqlx::Error> {
+    // Push the claimed rows into the future so that a concurrent worker skips
+    // them; if this one dies mid-send they become due again once the lease ends.
+    let due: Vec<Due> = sqlx::query_as(
+        "UPDATE webhook_deliveries d SET next_attempt_at = NOW() + make_interval(secs => $2)
+         FROM webhooks w
+         WHERE d.webhook_id = w.id AND d.id IN (
+             SELECT id FROM webhook_deliveries
+             WHERE status = 'pending' AND next_attempt_at <= NOW()
+             ORDER BY next_attempt_at
+             LIMIT $1
+             FOR UPDATE SKIP LOCKED
+         )
+         RETURNING d.id, d.event, d.payload, d.attempts, w.url, w.secret"
+    )
+    .bind(BATCH)
+    .bind(lease.as_secs_f64())
+    .fetch_all(pool)
+    .await?;
+
+    let budget = TIMEOUT.min(lease / 2);
+    let attempted = due.len();
+    let mut sends = tokio::task::JoinSet::new();
+    for delivery in due {
+        let client = client.clone();
+        sends.spawn(async move {
+            let outcome = tokio::time::timeout(budget, send(&client, &delivery)).await
+                .unwrap_or_else(|_| (None, Some(format!("No answer within {:?}", budget))));
+            (delivery, outcome)
+        });
+    }
+    while let Some(sent) = sends.join_next().await {
+        match sent {
+            Ok((delivery, (status_code, error))) => record_attempt(pool, &delivery, status_code, error).await?,
+            // Sent again once its lease ends
+            Err(e) => tracing::error!("Webhook delivery task failed: {}", e),
+        }
+    }
+    Ok(attempted)
+}
+
+/// POST one delivery. Returns the receiver's status code, if it answered, and
+/// why the attempt failed, if it did.
+async fn send(client: &reqwest::Client, delivery: &Due) -> (Option<i32>, Option<String>) {
+    // Addresses written into the URL never reach the resolver
+    if let Err(e) = check_destination(&delivery.url).await {
+        return (None, Some(format!("Refused to deliver: {}", e)));
+    }
+    let body = delivery.payload.to_string().into_bytes();
+    let response = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(SIGNATURE_HEADER, sign(&delivery.secret, &body))
+        .body(body)
+        .send()
+        .await;
+    match response {
+        Ok(r) if r.status().is_success() => (Some(r.status().as_u16() as i32), None),
+        Ok(r) => (Some(r.status().as_u16() as i32), Some(format!("Receiver answered {}", r.status()))),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+async fn record_attempt(pool: &PgPool, delivery: &Due, status_code: Option<i32>, error: Option<String>) -> Result<(), sqlx::Error> {
+    let attempts = delivery.attempts + 1;
+    let status = match &error {
+        None => "delivered",
+        Some(_) if attempts >= MAX_ATTEMPTS => "failed",
+        Some(_) => "pending",
+    };
+    sqlx::query(
+        "UPDATE webhook_deliveries SET
+             status = $2,
+             attempts = $3,
+             last_status_code = $4,
+             last_error = $5,
+             next_attempt_at = NOW() + make_interval(secs => $6),
+             delivered_at = CASE WHEN $2 = 'delivered' THEN NOW() END
+         WHERE id = $1"
+    )
+    .bind(delivery.id)
+    .bind(status)
+    .bind(attempts)
+    .bind(status_code)
+    .bind(&error)
+    .bind(retry_delay(attempts).as_secs_f64())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Wait after the `attempts`-th failed attempt: 30s, 1m, 2m, … doubling each time.
+pub fn retry_delay(attempts: i32) -> Duration {
+    FIRST_RETRY * 2u32.pow(attempts.clamp(1, MAX_ATTEMPTS) as u32 - 1)
+}
+
+/// Send due deliveries every `period` until the runtime shuts down. Failures
+/// are logged and retried on the next tick.
+pub fn spawn_worker(pool: PgPool, period: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = client();
+        let mut ticker = tokio::time::interval(period);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = deliver_due(&pool, &client).await {
+                tracing::warn!("Failed to send webhook deliveries: {}", e);
+            }
+        }
+    })
+}
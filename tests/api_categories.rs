@@ -0,0 +1,129 @@
+// Integration tests: Package keywords, categories and category browse pages
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+fn manifest(name: &str, keywords: &str, categories: &str) -> Vec<u8> {
+    common::create_test_tarball_with_manifest(&format!(
+        r#"[package]
+name = "{}"
+version = "1.0.0"
+description = "Tagged package"
+license = "MIT"
+keywords = {}
+categories = {}
+"#, name, keywords, categories
+    ))
+}
+
+async fn publish(pool: &sqlx::PgPool, token: &str, file: &[u8]) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let boundary = "categories-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", file),
+    ]);
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/zorbs/new")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn get(pool: &sqlx::PgPool, uri: &str) -> (StatusCode, String) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+#[tokio::test]
+async fn test_publish_stores_keywords_and_categories() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    let file = manifest("tagged-http", r#"["HTTP", "server", "http"]"#, r#"["web-programming::http-server", "asynchronous"]"#);
+    let (status, json) = publish(&pool, &token, &file).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", json);
+    assert_eq!(json["keywords"], serde_json::json!(["http", "server"]), "Keywords are lowercased and deduplicated");
+    assert_eq!(json["categories"], serde_json::json!(["web-programming::http-server", "asynchronous"]));
+
+    let (_, body) = get(&pool, "/api/zorbs?keyword=server").await;
+    let listing: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(listing["zorbs"][0]["name"], "tagged-http");
+    assert_eq!(listing["zorbs"][0]["keywords"], serde_json::json!(["http", "server"]));
+
+    let (_, body) = get(&pool, "/tagged-http").await;
+    assert!(body.contains("/categories/web-programming::http-server"), "Detail page links its categories");
+}
+
+#[tokio::test]
+async fn test_publish_rejects_invalid_keywords_and_categories() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    let cases = [
+        (r#"["ok"]"#, r#"["not-a-category"]"#),
+        (r#"["a", "b", "c", "d", "e", "f"]"#, "[]"),
+        (r#"["has space"]"#, "[]"),
+        (r#"["waytoolongkeywordforzorbs"]"#, "[]"),
+        ("[]", r#"["core", "algorithms", "encoding", "database", "logging", "testing"]"#),
+    ];
+    for (keywords, categories) in cases {
+        let (status, json) = publish(&pool, &token, &manifest("bad-tags", keywords, categories)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} {} => {}", keywords, categories, json);
+        assert_eq!(json["code"], "invalid_package");
+    }
+}
+
+#[tokio::test]
+async fn test_category_pages_and_filter() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    for (name, categories) in [
+        ("cat-server", r#"["web-programming::http-server"]"#),
+        ("cat-client", r#"["web-programming::http-client"]"#),
+        ("cat-web", r#"["web-programming"]"#),
+        ("cat-db", r#"["database"]"#),
+    ] {
+        let (status, json) = publish(&pool, &token, &manifest(name, "[]", categories)).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", json);
+    }
+
+    let (_, body) = get(&pool, "/api/zorbs?category=web-programming&sort=name").await;
+    let listing: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(listing["total"], 3, "Parent categories include subcategories: {}", listing);
+    let (_, body) = get(&pool, "/api/zorbs?category=web-programming::http-client").await;
+    let listing: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(listing["total"], 1);
+    assert_eq!(listing["zorbs"][0]["name"], "cat-client");
+
+    let (status, body) = get(&pool, "/categories").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("Web Programming") && body.contains("HTTP Server"));
+
+    let (status, body) = get(&pool, "/categories/web-programming").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("3 packages"), "Page shows the package count");
+    assert!(body.contains("cat-server") && body.contains("cat-client") && body.contains("cat-web"));
+    assert!(!body.contains("cat-db"));
+
+    let (_, body) = get(&pool, "/categories/no-such-category").await;
+    assert!(body.contains("404"));
+}
@@ -0,0 +1,77 @@
+// Integration tests: Publish-time checks that every dependency resolves from the registry
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+async fn publish(pool: &sqlx::PgPool, toml: &str) -> (StatusCode, Value) {
+    let token = common::create_test_token(pool).await;
+    let app = common::build_test_app_from_pool(pool.clone());
+    let boundary = "deps-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", &common::create_test_tarball_with_manifest(toml)),
+    ]);
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/zorbs/new")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+const PACKAGE: &str = "[package]\nname = \"checked\"\nversion = \"1.0.0\"\nedition = \"2026\"\n\n";
+
+#[tokio::test]
+async fn test_path_and_git_dependencies_need_a_version() {
+    let pool = common::setup_database().await;
+    let (status, body) = publish(&pool, &format!(
+        "{}[dependencies]\n\"@local/util\" = {{ path = \"../util\" }}\nforked = {{ git = \"https://example.com/forked.git\", branch = \"main\" }}\n",
+        PACKAGE
+    )).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_package");
+    assert_eq!(
+        body["error"],
+        "`dependencies.@local/util` is a path dependency with no `version` to use from the registry; \
+         `dependencies.forked` is a git dependency with no `version` to use from the registry"
+    );
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM zorbs").fetch_one(&pool).await.unwrap();
+    assert_eq!(stored, 0);
+}
+
+#[tokio::test]
+async fn test_dependencies_must_exist_in_the_registry() {
+    let pool = common::setup_database().await;
+    common::create_test_package(&pool, "@known/lib", "1.0.0").await;
+    let (status, body) = publish(&pool, &format!(
+        "{}[dependencies]\n\"@known/lib\" = \"1\"\n\"@ghost/lib\" = \"1\"\n\n[dev-dependencies]\nphantom = \"0.1\"\n",
+        PACKAGE
+    )).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["error"],
+        "`dependencies.@ghost/lib` does not exist in the registry; `dev-dependencies.phantom` does not exist in the registry"
+    );
+}
+
+#[tokio::test]
+async fn test_version_fallbacks_and_local_dev_dependencies_are_accepted() {
+    let pool = common::setup_database().await;
+    common::create_test_package(&pool, "@local/util", "0.1.0").await;
+    let (status, body) = publish(&pool, &format!(
+        "{}[dependencies]\n\"@local/util\" = {{ path = \"../util\", version = \"0.1\" }}\n\n[dev-dependencies]\nfixtures = {{ path = \"tests/fixtures\" }}\n",
+        PACKAGE
+    )).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+}
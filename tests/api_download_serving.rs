@@ -0,0 +1,141 @@
+// Integration tests: Serving local archives with ETag, conditional and Range requests
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, Response, StatusCode},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+
+const ARCHIVE: &[u8] = b"0123456789abcdefghij";
+
+/// Seed `@serve/blob@1.0.0` with its checksum recorded and its archive in storage.
+async fn seed(pool: &sqlx::PgPool) -> String {
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, created_at, updated_at)
+         VALUES ($1, '@serve/blob', '1.0.0', 'Served', 'MIT', NULL, '{}'::jsonb, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .execute(pool)
+    .await
+    .expect("seed version");
+    common::store_test_blob(pool, "@serve/blob", "1.0.0", ARCHIVE).await
+}
+
+async fn download(pool: &sqlx::PgPool, headers: &[(header::HeaderName, &str)]) -> Response<Body> {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let mut request = Request::builder().uri("/@serve/blob/1.0.0/download");
+    for (name, value) in headers {
+        request = request.header(name, *value);
+    }
+    app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+}
+
+fn header_of(response: &Response<Body>, name: header::HeaderName) -> &str {
+    response.headers().get(name).map(|v| v.to_str().unwrap()).unwrap_or("")
+}
+
+async fn body_of(response: Response<Body>) -> Vec<u8> {
+    response.into_body().collect().await.unwrap().to_bytes().to_vec()
+}
+
+#[tokio::test]
+async fn test_local_archive_is_served_with_metadata() {
+    let pool = common::setup_database().await;
+    let checksum = seed(&pool).await;
+
+    let response = download(&pool, &[]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_of(&response, header::CONTENT_LENGTH), ARCHIVE.len().to_string());
+    assert_eq!(header_of(&response, header::ETAG), format!("\"{}\"", checksum));
+    assert_eq!(header_of(&response, header::ACCEPT_RANGES), "bytes");
+    assert_eq!(header_of(&response, header::CONTENT_DISPOSITION), "attachment; filename=\"serve-blob-1.0.0.zorb\"");
+    assert_eq!(body_of(response).await, ARCHIVE);
+
+    let response = download(&pool, &[(header::IF_NONE_MATCH, &format!("\"other\", W/\"{}\"", checksum))]).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(body_of(response).await.is_empty());
+    let response = download(&pool, &[(header::IF_NONE_MATCH, "\"stale\"")]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_range_requests() {
+    let pool = common::setup_database().await;
+    let checksum = seed(&pool).await;
+
+    let response = download(&pool, &[(header::RANGE, "bytes=5-9")]).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(header_of(&response, header::CONTENT_RANGE), "bytes 5-9/20");
+    assert_eq!(header_of(&response, header::CONTENT_LENGTH), "5");
+    assert_eq!(body_of(response).await, b"56789");
+
+    let response = download(&pool, &[(header::RANGE, "bytes=15-")]).await;
+    assert_eq!(body_of(response).await, b"fghij");
+    let response = download(&pool, &[(header::RANGE, "bytes=-3")]).await;
+    assert_eq!(body_of(response).await, b"hij");
+    let response = download(&pool, &[(header::RANGE, "bytes=18-100")]).await;
+    assert_eq!(header_of(&response, header::CONTENT_RANGE), "bytes 18-19/20");
+
+    let response = download(&pool, &[(header::RANGE, "bytes=20-")]).await;
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(header_of(&response, header::CONTENT_RANGE), "bytes */20");
+
+    // Multiple ranges and stale If-Range validators fall back to the whole archive
+    let response = download(&pool, &[(header::RANGE, "bytes=0-1,5-6")]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = download(&pool, &[(header::RANGE, "bytes=5-9"), (header::IF_RANGE, "\"stale\"")]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_of(response).await, ARCHIVE);
+    let etag = format!("\"{}\"", checksum);
+    let response = download(&pool, &[(header::RANGE, "bytes=5-9"), (header::IF_RANGE, &etag)]).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+}
+
+#[tokio::test]
+async fn test_only_full_downloads_are_counted() {
+    let pool = common::setup_database().await;
+    let checksum = seed(&pool).await;
+    let state = common::build_test_state(pool.clone());
+
+    let etag = format!("\"{}\"", checksum);
+    for headers in [
+        vec![],
+        vec![(header::RANGE, "bytes=0-9")],
+        vec![(header::RANGE, "bytes=10-")],
+        vec![(header::IF_NONE_MATCH, etag.as_str())],
+    ] {
+        let mut request = Request::builder().uri("/@serve/blob/1.0.0/download");
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        zorbs::build_app(state.clone()).oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    }
+    assert_eq!(state.downloads.pending(), 2, "Resumed transfers and revalidations are not counted");
+}
+
+#[tokio::test]
+async fn test_head_requests_are_not_counted() {
+    let pool = common::setup_database().await;
+    seed(&pool).await;
+    let state = common::build_test_state(pool.clone());
+
+    let request = Request::builder().method(Method::HEAD).uri("/@serve/blob/1.0.0/download");
+    let response = zorbs::build_app(state.clone()).oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_of(&response, header::CONTENT_LENGTH), ARCHIVE.len().to_string());
+    assert!(body_of(response).await.is_empty());
+    assert_eq!(state.downloads.pending(), 0, "A HEAD probe is not a download");
+}
+
+#[tokio::test]
+async fn test_missing_archive_is_not_found() {
+    let pool = common::setup_database().await;
+    let checksum = seed(&pool).await;
+    common::delete_test_blob(&zorbs::utils::blob_key(&checksum)).await;
+
+    let response = download(&pool, &[]).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,192 @@
+// Integration tests: Daily download history, the time-series API and trending
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+async fn seed(pool: &sqlx::PgPool, name: &str, version: &str, downloads: i64) {
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, downloads, created_at, updated_at)
+         VALUES ($1, $2, $3, 'Stats test', 'MIT', NULL, '{}'::jsonb, $4, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(name)
+    .bind(version)
+    .bind(downloads)
+    .execute(pool)
+    .await
+    .expect("seed version");
+}
+
+/// Record `downloads` for a version `days_ago` UTC days before today.
+async fn seed_day(pool: &sqlx::PgPool, name: &str, version: &str, days_ago: i32, downloads: i64) {
+    sqlx::query(
+        "INSERT INTO version_downloads (name, version, date, downloads)
+         VALUES ($1, $2, (NOW() AT TIME ZONE 'UTC')::DATE - $3, $4)"
+    )
+    .bind(name)
+    .bind(version)
+    .bind(days_ago)
+    .bind(downloads)
+    .execute(pool)
+    .await
+    .expect("seed daily downloads");
+}
+
+async fn get(pool: &sqlx::PgPool, uri: &str) -> (StatusCode, String) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+async fn get_json(pool: &sqlx::PgPool, uri: &str) -> (StatusCode, Value) {
+    let (status, body) = get(pool, uri).await;
+    (status, serde_json::from_str(&body).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn test_download_is_recorded_per_day() {
+    let pool = common::setup_database().await;
+    seed(&pool, "@stats/daily", "1.0.0", 0).await;
+    common::store_test_blob(&pool, "@stats/daily", "1.0.0", b"archive").await;
+
+    let state = common::build_test_state(pool.clone());
+    for _ in 0..3 {
+        let app = zorbs::build_app(state.clone());
+        app.oneshot(Request::builder().uri("/@stats/daily/1.0.0/download").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+    }
+    let (_, json) = get_json(&pool, "/api/zorbs/@stats/daily/downloads").await;
+    assert_eq!(json["total"], 0, "Downloads are buffered until flushed");
+    assert_eq!(state.downloads.flush(&pool).await.expect("flush"), 3);
+
+    let (status, json) = get_json(&pool, "/api/zorbs/@stats/daily/downloads").await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(json["total"], 3);
+    let daily = json["daily"].as_array().unwrap();
+    assert_eq!(daily.len(), 90, "Defaults to the last 90 days");
+    assert_eq!(daily.last().unwrap()["downloads"], 3, "Today's downloads are last");
+    assert_eq!(daily.last().unwrap()["date"], json["to"]);
+}
+
+#[tokio::test]
+async fn test_download_series_per_version() {
+    let pool = common::setup_database().await;
+    seed(&pool, "series-pkg", "1.0.0", 0).await;
+    seed(&pool, "series-pkg", "1.10.0", 0).await;
+    seed(&pool, "series-pkg", "1.2.0", 0).await;
+    seed_day(&pool, "series-pkg", "1.0.0", 3, 5).await;
+    seed_day(&pool, "series-pkg", "1.10.0", 3, 2).await;
+    seed_day(&pool, "series-pkg", "1.10.0", 1, 7).await;
+    seed_day(&pool, "series-pkg", "1.10.0", 40, 100).await;
+
+    let (_, all) = get_json(&pool, "/api/zorbs/series-pkg/downloads").await;
+    let to = all["to"].as_str().unwrap().to_string();
+    let from = chrono::NaiveDate::parse_from_str(&to, "%Y-%m-%d").unwrap() - chrono::Duration::days(6);
+
+    let (status, json) = get_json(&pool, &format!("/api/zorbs/series-pkg/downloads?from={}&to={}", from, to)).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(json["total"], 14);
+    let daily: Vec<i64> = json["daily"].as_array().unwrap().iter().map(|d| d["downloads"].as_i64().unwrap()).collect();
+    assert_eq!(daily, [0, 0, 0, 7, 0, 7, 0]);
+
+    let versions = json["versions"].as_array().unwrap();
+    let names: Vec<&str> = versions.iter().map(|v| v["version"].as_str().unwrap()).collect();
+    assert_eq!(names, ["1.10.0", "1.0.0"], "Only downloaded versions, newest first");
+    assert_eq!(versions[0]["total"], 9);
+    assert_eq!(versions[1]["daily"].as_array().unwrap().len(), 7);
+
+    let (status, json) = get_json(&pool, "/api/zorbs/series-pkg/downloads?from=2026-02-01&to=2026-01-01").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "bad_request");
+    let (status, _) = get_json(&pool, "/api/zorbs/series-pkg/downloads?from=2020-01-01&to=2026-01-01").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get_json(&pool, "/api/zorbs/series-pkg/downloads?from=yesterday").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, json) = get_json(&pool, "/api/zorbs/no-such-pkg/downloads").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "not_found");
+}
+
+#[tokio::test]
+async fn test_homepage_trending_and_weekly_downloads() {
+    let pool = common::setup_database().await;
+    seed(&pool, "steady-giant", "1.0.0", 1_000_000).await;
+    seed(&pool, "rising-star", "1.0.0", 60).await;
+    seed_day(&pool, "steady-giant", "1.0.0", 10, 500).await;
+    seed_day(&pool, "steady-giant", "1.0.0", 2, 10).await;
+    seed_day(&pool, "rising-star", "1.0.0", 1, 40).await;
+    seed_day(&pool, "rising-star", "1.0.0", 0, 20).await;
+
+    let (status, body) = get(&pool, "/").await;
+    assert_eq!(status, StatusCode::OK);
+    let rising = body.find("rising-star").expect("rising-star is trending");
+    let giant = body.find("steady-giant").expect("steady-giant is listed");
+    assert!(rising < giant, "Recent growth beats all-time downloads");
+    assert!(body.contains(r#"data-target="70">0</span> this week"#), "Weekly downloads pill");
+
+    let (_, body) = get(&pool, "/rising-star").await;
+    assert!(body.contains("download-chart"));
+    assert!(body.contains("60 in the last 90 days"));
+}
+
+#[tokio::test]
+async fn test_concurrent_downloads_are_counted_exactly() {
+    let pool = common::setup_database().await;
+    seed(&pool, "@async/burst", "1.0.0", 5).await;
+    seed(&pool, "@async/burst", "1.1.0", 0).await;
+    common::store_test_blob(&pool, "@async/burst", "1.0.0", b"archive").await;
+    common::store_test_blob(&pool, "@async/burst", "1.1.0", b"archive").await;
+    let state = common::build_test_state(pool.clone());
+    let app = zorbs::build_app(state.clone());
+
+    let requests = (0..200).map(|i| {
+        let app = app.clone();
+        let uri = if i % 4 == 0 { "/@async/burst/1.0.0/download" } else { "/@async/burst/1.1.0/download" };
+        tokio::spawn(async move {
+            app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap().status()
+        })
+    }).collect::<Vec<_>>();
+    // Flush while the burst is still arriving, as the background task would
+    let flusher = {
+        let state = state.clone();
+        tokio::spawn(async move {
+            for _ in 0..5 {
+                state.downloads.flush(&state.db).await.expect("flush");
+                tokio::task::yield_now().await;
+            }
+        })
+    };
+    for request in requests {
+        assert!(!request.await.unwrap().is_server_error());
+    }
+    flusher.await.unwrap();
+    state.downloads.flush(&pool).await.expect("final flush");
+    assert_eq!(state.downloads.pending(), 0);
+
+    let counts: Vec<(String, i64)> = sqlx::query_as("SELECT version, downloads FROM zorbs WHERE name = '@async/burst' ORDER BY version")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(counts, [("1.0.0".to_string(), 55), ("1.1.0".to_string(), 150)]);
+    let daily: i64 = sqlx::query_scalar("SELECT SUM(downloads)::BIGINT FROM version_downloads WHERE name = '@async/burst'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(daily, 200);
+
+    let (status, _) = get(&pool, "/@async/burst/9.9.9/download").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,84 @@
+// Integration tests: Error envelope and stable error codes
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+/// Helper: publish `file` (raw bytes) with an optional Bearer token.
+async fn publish(pool: &sqlx::PgPool, token: Option<&str>, file: &[u8]) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let boundary = "errors-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", file),
+    ]);
+    let mut builder = Request::builder()
+        .method(Method::POST)
+        .uri("/api/zorbs/new")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary));
+    if let Some(token) = token {
+        builder = builder.header("authorization", format!("Bearer {}", token));
+    }
+    send(app, builder.body(Body::from(body)).unwrap()).await
+}
+
+async fn send(app: axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Every error body carries a human `error` message and a machine `code`.
+fn assert_envelope(json: &Value, code: &str) {
+    assert!(json["error"].is_string(), "Missing error message: {}", json);
+    assert_eq!(json["code"], code, "Unexpected error code: {}", json);
+}
+
+#[tokio::test]
+async fn test_publish_error_codes() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let stranger = common::create_test_token_with_scopes(&pool, "mallory", &["publish-new", "publish-update"]).await;
+    let tarball = common::create_test_tarball("coded-pkg", "1.0.0");
+
+    let (status, json) = publish(&pool, None, &tarball).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_envelope(&json, "unauthorized");
+
+    let (status, json) = publish(&pool, Some(&token), &tarball).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", json);
+
+    let (status, json) = publish(&pool, Some(&token), &tarball).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_envelope(&json, "conflict");
+
+    let newer = common::create_test_tarball("coded-pkg", "1.0.1");
+    let (status, json) = publish(&pool, Some(&stranger), &newer).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_envelope(&json, "forbidden");
+
+    let (status, json) = publish(&pool, Some(&token), b"definitely not a tarball").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_envelope(&json, "invalid_package");
+}
+
+#[tokio::test]
+async fn test_not_found_error_codes() {
+    let pool = common::setup_database().await;
+
+    let app = common::build_test_app_from_pool(pool.clone());
+    let (status, json) = send(app, Request::builder().uri("/api/resolve?name=nope-pkg").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_envelope(&json, "not_found");
+
+    let app = common::build_test_app_from_pool(pool.clone());
+    let (status, json) = send(app, Request::builder().uri("/nope-pkg/1.0.0/download").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_envelope(&json, "not_found");
+}
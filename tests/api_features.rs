@@ -0,0 +1,163 @@
+// Integration tests: Feature metadata and feature-aware dependency resolution
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::{json, Value};
+use zorbs::manifest::Manifest;
+
+/// Insert a version as publishing would store it: the flattened `dependencies`
+/// and the full manifest.
+async fn seed_manifest(pool: &sqlx::PgPool, toml: &str) {
+    let manifest = Manifest::parse(toml).expect("valid test manifest").manifest;
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, dependencies, manifest, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(&manifest.package.name)
+    .bind(&manifest.package.version)
+    .bind(manifest.dependency_requirements())
+    .bind(serde_json::to_value(&manifest).unwrap())
+    .execute(pool)
+    .await
+    .expect("seed version with manifest");
+}
+
+async fn resolve(pool: &sqlx::PgPool, body: Value) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/resolve/graph")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+/// `name@version [features]` for every resolved package.
+fn resolved(json: &Value) -> Vec<String> {
+    json["packages"].as_array().expect("packages array").iter()
+        .map(|p| {
+            let features: Vec<&str> = p["features"].as_array().unwrap().iter().map(|f| f.as_str().unwrap()).collect();
+            format!("{}@{} [{}]", p["name"].as_str().unwrap(), p["version"].as_str().unwrap(), features.join(","))
+        })
+        .collect()
+}
+
+/// `ft-web` has an optional `ft-log`, enabled by its `logging` feature, which is on by default.
+async fn seed_web(pool: &sqlx::PgPool) {
+    seed_manifest(pool, "[package]\nname = \"ft-log\"\nversion = \"1.0.0\"\n\n[features]\ncolor = []\njson = []\n").await;
+    seed_manifest(pool, "[package]\nname = \"ft-web\"\nversion = \"1.0.0\"\n\n\
+        [dependencies]\nft-log = { version = \"1\", optional = true, features = [\"color\"] }\n\n\
+        [features]\ndefault = [\"logging\"]\nlogging = [\"dep:ft-log\"]\njson-logs = [\"logging\", \"ft-log/json\"]\n").await;
+}
+
+#[tokio::test]
+async fn test_optional_dependencies_follow_features() {
+    let pool = common::setup_database().await;
+    seed_web(&pool).await;
+
+    let (status, json) = resolve(&pool, json!({"dependencies": {"ft-web": "^1"}})).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(resolved(&json), ["ft-log@1.0.0 [color]", "ft-web@1.0.0 [default,logging]"]);
+
+    let (_, json) = resolve(&pool, json!({"dependencies": {"ft-web": {"version": "^1", "default-features": false}}})).await;
+    assert_eq!(resolved(&json), ["ft-web@1.0.0 []"], "Without `default` nothing enables ft-log");
+
+    let (_, json) = resolve(&pool, json!({
+        "dependencies": {"ft-web": {"version": "^1", "default-features": false, "features": ["json-logs"]}}
+    })).await;
+    assert_eq!(resolved(&json), ["ft-log@1.0.0 [color,json]", "ft-web@1.0.0 [json-logs,logging]"]);
+}
+
+#[tokio::test]
+async fn test_features_are_unified_across_the_graph() {
+    let pool = common::setup_database().await;
+    seed_web(&pool).await;
+    // Two dependents ask different things of ft-web; it is resolved once with both
+    seed_manifest(&pool, "[package]\nname = \"ft-quiet\"\nversion = \"1.0.0\"\n\n\
+        [dependencies]\nft-web = { version = \"1\", default-features = false }\n").await;
+    seed_manifest(&pool, "[package]\nname = \"ft-api\"\nversion = \"1.0.0\"\n\n\
+        [dependencies]\nft-web = { version = \"1\", default-features = false, features = [\"json-logs\"] }\n").await;
+
+    let (status, json) = resolve(&pool, json!({"dependencies": {"ft-quiet": "1"}})).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(resolved(&json), ["ft-quiet@1.0.0 []", "ft-web@1.0.0 []"]);
+
+    let (_, json) = resolve(&pool, json!({"dependencies": {"ft-quiet": "1", "ft-api": "1"}})).await;
+    assert_eq!(resolved(&json), [
+        "ft-api@1.0.0 []",
+        "ft-log@1.0.0 [color,json]",
+        "ft-quiet@1.0.0 []",
+        "ft-web@1.0.0 [json-logs,logging]",
+    ]);
+}
+
+#[tokio::test]
+async fn test_root_features_and_unknown_features() {
+    let pool = common::setup_database().await;
+    seed_web(&pool).await;
+
+    // The root manifest's own optional dependency, off unless a root feature enables it
+    let root = json!({
+        "dependencies": {"ft-web": {"version": "1", "optional": true}},
+        "features": {"web": ["dep:ft-web"]},
+    });
+    let (_, json) = resolve(&pool, root.clone()).await;
+    assert_eq!(resolved(&json), Vec::<String>::new());
+    let mut with_web = root.clone();
+    with_web["activate"] = json!(["web"]);
+    let (_, json) = resolve(&pool, with_web).await;
+    assert_eq!(resolved(&json), ["ft-log@1.0.0 [color]", "ft-web@1.0.0 [default,logging]"]);
+
+    let mut unknown = root;
+    unknown["activate"] = json!(["nope"]);
+    let (status, json) = resolve(&pool, unknown).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["error"], "The root manifest has no feature 'nope'");
+
+    let (status, json) = resolve(&pool, json!({"dependencies": {"ft-web": {"version": "1", "features": ["turbo"]}}})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["details"]["feature"], "turbo");
+    assert_eq!(json["details"]["requirement"]["name"], "ft-web");
+}
+
+#[tokio::test]
+async fn test_versions_without_the_feature_are_skipped() {
+    let pool = common::setup_database().await;
+    seed_manifest(&pool, "[package]\nname = \"ft-grow\"\nversion = \"1.0.0\"\n\n[features]\nfast = []\n").await;
+    seed_manifest(&pool, "[package]\nname = \"ft-grow\"\nversion = \"1.1.0\"\n").await;
+
+    let (status, json) = resolve(&pool, json!({"dependencies": {"ft-grow": {"version": "1", "features": ["fast"]}}})).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(resolved(&json), ["ft-grow@1.0.0 [fast]"]);
+}
+
+#[tokio::test]
+async fn test_detail_page_lists_features() {
+    let pool = common::setup_database().await;
+    seed_web(&pool).await;
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(Request::builder().uri("/ft-web").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let html = String::from_utf8(response.into_body().collect().await.unwrap().to_bytes().to_vec()).unwrap();
+    assert!(html.contains(r#"id="features""#));
+    assert!(html.contains("json-logs"));
+    assert!(html.contains("logging, ft-log/json"));
+    assert!(html.contains(">optional<"), "Optional dependencies are marked");
+}
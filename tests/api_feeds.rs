@@ -0,0 +1,115 @@
+// Integration tests: Atom feeds of new releases
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+
+async fn publish(pool: &sqlx::PgPool, token: &str, name: &str, version: &str, description: &str) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let boundary = "feed-boundary";
+    let toml = format!("[package]\nname = \"{}\"\nversion = \"{}\"\ndescription = \"{}\"\nkeywords = [\"http\"]\n", name, version, description);
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", &common::create_test_tarball_with_manifest(&toml)),
+    ]);
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/zorbs/new")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED, "publish {}@{}", name, version);
+}
+
+async fn feed(pool: &sqlx::PgPool, uri: &str) -> (StatusCode, String, String) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response.headers().get("content-type").map(|v| v.to_str().unwrap().to_string()).unwrap_or_default();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, content_type, String::from_utf8(body.to_vec()).unwrap())
+}
+
+/// Entry titles, in feed order.
+fn entries(xml: &str) -> Vec<&str> {
+    xml.split("<entry>").skip(1)
+        .map(|entry| &entry[entry.find("<title>").unwrap() + 7..entry.find("</title>").unwrap()])
+        .collect()
+}
+
+async fn seed(pool: &sqlx::PgPool) {
+    let ada = common::create_test_token_with_scopes(pool, "ada", &["publish-new", "publish-update", "yank"]).await;
+    let bob = common::create_test_token_with_scopes(pool, "bob", &["publish-new", "publish-update"]).await;
+    publish(pool, &ada, "@async/runtime", "1.0.0", "An async runtime").await;
+    publish(pool, &bob, "@http/client", "0.1.0", "HTTP client").await;
+    publish(pool, &ada, "plain", "2.0.0", "Fast & <small>").await;
+    publish(pool, &ada, "@async/runtime", "1.1.0", "An async runtime").await;
+    publish(pool, &bob, "@asyncx/other", "1.0.0", "Not in @async").await;
+}
+
+#[tokio::test]
+async fn test_all_releases_feed() {
+    let pool = common::setup_database().await;
+    seed(&pool).await;
+    let (status, content_type, xml) = feed(&pool, "/feed.atom").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/atom+xml; charset=utf-8");
+    assert!(xml.starts_with(r#"<?xml version="1.0" encoding="utf-8"?><feed xmlns="http://www.w3.org/2005/Atom">"#));
+    assert_eq!(entries(&xml), [
+        "@asyncx/other 1.0.0",
+        "@async/runtime 1.1.0",
+        "plain 2.0.0",
+        "@http/client 0.1.0",
+        "@async/runtime 1.0.0",
+    ]);
+    assert!(xml.contains("<summary>Fast &amp; &lt;small&gt;</summary>"), "Descriptions are escaped");
+    assert!(xml.contains(r#"<link rel="alternate" type="text/html" href="http://localhost:3000/@http/client"></link>"#));
+    assert!(xml.contains(r#"<category term="http"></category>"#));
+}
+
+#[tokio::test]
+async fn test_package_and_scope_feeds() {
+    let pool = common::setup_database().await;
+    seed(&pool).await;
+    let (status, _, xml) = feed(&pool, "/@async/runtime/feed.atom").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(entries(&xml), ["@async/runtime 1.1.0", "@async/runtime 1.0.0"]);
+    assert!(xml.contains("<title>@async/runtime releases</title>"));
+    assert!(xml.contains(r#"href="http://localhost:3000/@async/runtime/feed.atom""#));
+
+    let (_, _, xml) = feed(&pool, "/plain/feed.atom").await;
+    assert_eq!(entries(&xml), ["plain 2.0.0"]);
+
+    let (_, _, xml) = feed(&pool, "/@async/feed.atom").await;
+    assert_eq!(entries(&xml), ["@async/runtime 1.1.0", "@async/runtime 1.0.0"], "@asyncx is another scope");
+    let (_, _, xml) = feed(&pool, "/@http/feed.atom").await;
+    assert_eq!(entries(&xml), ["@http/client 0.1.0"]);
+
+    let (status, _, _) = feed(&pool, "/missing/feed.atom").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_user_feed_and_yanked_releases() {
+    let pool = common::setup_database().await;
+    seed(&pool).await;
+    let (status, _, xml) = feed(&pool, "/users/ada/feed.atom").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(entries(&xml), ["@async/runtime 1.1.0", "plain 2.0.0", "@async/runtime 1.0.0"]);
+
+    sqlx::query("UPDATE zorbs SET yanked = TRUE WHERE name = 'plain'").execute(&pool).await.unwrap();
+    let (_, _, xml) = feed(&pool, "/users/ada/feed.atom").await;
+    assert_eq!(entries(&xml), ["@async/runtime 1.1.0", "@async/runtime 1.0.0"], "Yanked releases are left out");
+    let (status, _, xml) = feed(&pool, "/plain/feed.atom").await;
+    assert_eq!(status, StatusCode::OK, "A package whose every version is yanked still has a feed");
+    assert!(entries(&xml).is_empty());
+}
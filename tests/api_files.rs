@@ -0,0 +1,199 @@
+// Integration tests: Per-version file listings and the source browser
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+async fn publish(pool: &sqlx::PgPool, token: &str, file: &[u8]) -> StatusCode {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let boundary = "files-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", file),
+    ]);
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/zorbs/new")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::from(body))
+        .unwrap();
+    app.oneshot(request).await.unwrap().status()
+}
+
+async fn get(pool: &sqlx::PgPool, uri: &str) -> (StatusCode, String) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+fn listing(files: &Value) -> Vec<(String, i64, i64)> {
+    files.as_array().unwrap().iter().map(|f| {
+        (f["path"].as_str().unwrap().to_string(), f["size"].as_i64().unwrap(), f["mode"].as_i64().unwrap())
+    }).collect()
+}
+
+const LIB: &[u8] = b"// <b>not bold</b>\npub fn answer() -> i32 {\n    42\n}\n";
+
+#[tokio::test]
+async fn test_file_listing_is_recorded_at_publish() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let tarball = common::create_test_tarball_with_files("@files/listed", "1.0.0", &[
+        ("./src/lib.z", 0o644, LIB),
+        ("bin/run.sh", 0o755, b"#!/bin/sh\n"),
+        ("assets/logo.png", 0o644, b"\x89PNG\r\n\x1a\n\0\0"),
+    ]);
+    assert_eq!(publish(&pool, &token, &tarball).await, StatusCode::CREATED);
+
+    let (status, body) = get(&pool, "/api/zorbs/@files/listed/1.0.0/files").await;
+    assert_eq!(status, StatusCode::OK);
+    let json: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["name"], "@files/listed");
+    let files = listing(&json["files"]);
+    let toml_size = files.iter().find(|f| f.0 == "zorb.toml").unwrap().1;
+    assert_eq!(files, vec![
+        ("assets/logo.png".to_string(), 10, 0o644),
+        ("bin/run.sh".to_string(), 10, 0o755),
+        ("src/lib.z".to_string(), LIB.len() as i64, 0o644),
+        ("zorb.toml".to_string(), toml_size, 0o644),
+    ], "Sorted by path, with any ./ prefix dropped");
+    assert_eq!(json["total_size"], 20 + LIB.len() as i64 + toml_size);
+
+    let (status, _) = get(&pool, "/api/zorbs/@files/listed/9.9.9/files").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_source_browser_renders_files_and_directories() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let tarball = common::create_test_tarball_with_files("browsed", "0.1.0", &[
+        ("src/lib.z", 0o644, LIB),
+        ("src/nested/deep.z", 0o644, b"pub fn deep() {}\n"),
+        ("assets/logo.png", 0o644, b"\x89PNG\r\n\x1a\n\0\0"),
+    ]);
+    assert_eq!(publish(&pool, &token, &tarball).await, StatusCode::CREATED);
+
+    let (status, page) = get(&pool, "/browsed/0.1.0/source/src/lib.z").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains(r##"id="L4" href="#L4""##), "Every line has an anchor");
+    assert!(!page.contains(r##"id="L5""##));
+    assert!(page.contains(r#"class="language-rust""#));
+    assert!(page.contains("&lt;b&gt;not bold&lt;/b&gt;"), "Contents are escaped");
+    assert!(page.contains(r#"href="/browsed/0.1.0/source/src""#), "Breadcrumbs link to directories");
+
+    let (_, page) = get(&pool, "/browsed/0.1.0/source/assets/logo.png").await;
+    assert!(page.contains("Binary file, not shown."));
+
+    let (_, root) = get(&pool, "/browsed/0.1.0/source").await;
+    assert!(root.contains(r#"href="/browsed/0.1.0/source/src""#));
+    assert!(root.contains(r#"href="/browsed/0.1.0/source/zorb.toml""#));
+    assert!(!root.contains("lib.z"), "Only direct children are listed");
+
+    let (_, src) = get(&pool, "/browsed/0.1.0/source/src").await;
+    assert!(src.contains(r#"href="/browsed/0.1.0/source/src/nested""#));
+    assert!(src.contains(r#"href="/browsed/0.1.0/source/src/lib.z""#));
+
+    let (status, missing) = get(&pool, "/browsed/0.1.0/source/src/nope.z").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(missing.contains("404"));
+    let (status, _) = get(&pool, "/browsed/9.9.9/source").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, detail) = get(&pool, "/browsed").await;
+    assert!(detail.contains(r#"href="/browsed/0.1.0/source""#), "The package page links to the browser");
+}
+
+#[tokio::test]
+async fn test_scoped_source_browser() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let tarball = common::create_test_tarball_with_files("@files/scoped", "2.0.0", &[("src/lib.z", 0o644, LIB)]);
+    assert_eq!(publish(&pool, &token, &tarball).await, StatusCode::CREATED);
+
+    let (status, page) = get(&pool, "/@files/scoped/2.0.0/source/src/lib.z").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains(r##"id="L1""##));
+    assert!(page.contains(r#"href="/@files/scoped/2.0.0/source/src""#));
+}
+
+#[tokio::test]
+async fn test_shown_files_are_cached() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let tarball = common::create_test_tarball_with_files("cached", "1.0.0", &[("src/lib.z", 0o644, LIB)]);
+    assert_eq!(publish(&pool, &token, &tarball).await, StatusCode::CREATED);
+    let state = common::build_test_state(pool.clone());
+    let show = |uri: &'static str| {
+        let app = zorbs::build_app(state.clone());
+        async move {
+            let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, String::from_utf8_lossy(&body).into_owned())
+        }
+    };
+
+    let (status, first) = show("/cached/1.0.0/source/src/lib.z").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state.source_cache.size(), LIB.len());
+    let checksum = zorbs::utils::sha256_hex(&tarball);
+    common::delete_test_blob(&zorbs::utils::blob_key(&checksum)).await;
+
+    let (status, again) = show("/cached/1.0.0/source/src/lib.z").await;
+    assert_eq!(status, StatusCode::OK, "The archive is not read again");
+    assert_eq!(first, again);
+}
+
+#[test]
+fn test_source_cache_evicts_least_recently_used() {
+    let cache = zorbs::source_cache::SourceCache::new(10);
+    cache.insert("a", "one", vec![1; 4]);
+    cache.insert("a", "two", vec![2; 4]);
+    assert!(cache.get("a", "one").is_some());
+    cache.insert("b", "one", vec![3; 4]);
+    assert!(cache.get("a", "two").is_none(), "The least recently used file goes first");
+    assert!(cache.get("a", "one").is_some());
+    assert!(cache.get("b", "one").is_some());
+    assert_eq!(cache.size(), 8);
+    cache.insert("c", "huge", vec![0; 11]);
+    assert!(cache.get("c", "huge").is_none(), "Files over the budget are not kept");
+    assert_eq!(cache.size(), 8);
+}
+
+#[tokio::test]
+async fn test_listing_of_older_versions_is_read_from_the_archive() {
+    let pool = common::setup_database().await;
+    // Published before listings were recorded
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, created_at, updated_at)
+         VALUES ($1, 'unlisted', '1.0.0', 'Old', 'MIT', NULL, '{}'::jsonb, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .execute(&pool)
+    .await
+    .unwrap();
+    let tarball = common::create_test_tarball_with_files("unlisted", "1.0.0", &[("src/lib.z", 0o600, LIB)]);
+    common::store_test_blob(&pool, "unlisted", "1.0.0", &tarball).await;
+
+    let (status, body) = get(&pool, "/api/zorbs/unlisted/1.0.0/files").await;
+    assert_eq!(status, StatusCode::OK);
+    let json: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(listing(&json["files"])[0], ("src/lib.z".to_string(), LIB.len() as i64, 0o600));
+
+    let recorded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM version_files WHERE name = 'unlisted'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(recorded, 2, "The listing is recorded once read");
+}
@@ -0,0 +1,137 @@
+// Integration tests: Paginated, sortable and filterable /api/zorbs listing
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+/// Helper: insert one version, `age_days` old, with `downloads` downloads.
+async fn seed(pool: &sqlx::PgPool, name: &str, version: &str, license: &str, description: &str, downloads: i64, age_days: i32) {
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, downloads, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, NULL, '{}'::jsonb, $6, NOW() - make_interval(days => $7), NOW() - make_interval(days => $7))"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(name)
+    .bind(version)
+    .bind(description)
+    .bind(license)
+    .bind(downloads)
+    .bind(age_days)
+    .execute(pool)
+    .await
+    .expect("seed version");
+}
+
+async fn list(pool: &sqlx::PgPool, uri: &str) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+fn names(json: &Value) -> Vec<String> {
+    json["zorbs"]
+        .as_array()
+        .expect("zorbs array")
+        .iter()
+        .map(|z| z["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_listing_one_entry_per_package_with_pagination() {
+    let pool = common::setup_database().await;
+    for i in 0..5 {
+        seed(&pool, &format!("page-pkg-{}", i), "1.0.0", "MIT", "Paged", 10 * i, 0).await;
+    }
+    seed(&pool, "page-pkg-0", "1.1.0", "MIT", "Paged", 100, 0).await;
+
+    let (status, json) = list(&pool, "/api/zorbs?per_page=2&sort=name").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total"], 5, "Versions of one package count once: {}", json);
+    assert_eq!(json["pages"], 3);
+    assert_eq!(names(&json), ["page-pkg-0", "page-pkg-1"]);
+    assert_eq!(json["zorbs"][0]["version"], "1.1.0");
+    assert_eq!(json["zorbs"][0]["downloads"], 100, "Downloads are summed over versions");
+    assert!(json["prev"].is_null());
+
+    let next = json["next"].as_str().expect("next link").to_string();
+    let (_, json) = list(&pool, &next).await;
+    assert_eq!(names(&json), ["page-pkg-2", "page-pkg-3"]);
+
+    let (_, json) = list(&pool, "/api/zorbs?per_page=2&sort=name&page=3").await;
+    assert_eq!(names(&json), ["page-pkg-4"]);
+    assert!(json["next"].is_null());
+
+    let (status, json) = list(&pool, "/api/zorbs?per_page=1000").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "bad_request");
+}
+
+#[tokio::test]
+async fn test_listing_sorts() {
+    let pool = common::setup_database().await;
+    seed(&pool, "sort-old", "1.0.0", "MIT", "Old", 500, 30).await;
+    seed(&pool, "sort-old", "2.0.0", "MIT", "Old", 0, 1).await;
+    seed(&pool, "sort-new", "1.0.0", "MIT", "New", 10, 2).await;
+    seed(&pool, "sort-mid", "1.0.0", "MIT", "Mid", 50, 10).await;
+
+    let (_, json) = list(&pool, "/api/zorbs").await;
+    assert_eq!(names(&json), ["sort-old", "sort-mid", "sort-new"], "Default sort is downloads");
+    let (_, json) = list(&pool, "/api/zorbs?sort=updated").await;
+    assert_eq!(names(&json), ["sort-old", "sort-new", "sort-mid"]);
+    let (_, json) = list(&pool, "/api/zorbs?sort=newest").await;
+    assert_eq!(names(&json), ["sort-new", "sort-mid", "sort-old"]);
+    let (_, json) = list(&pool, "/api/zorbs?sort=name").await;
+    assert_eq!(names(&json), ["sort-mid", "sort-new", "sort-old"]);
+
+    let (status, json) = list(&pool, "/api/zorbs?sort=stars").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"].as_str().unwrap().contains("downloads"));
+}
+
+#[tokio::test]
+async fn test_listing_filters() {
+    let pool = common::setup_database().await;
+    seed(&pool, "@data/serde", "1.0.0", "MIT", "Serialization framework", 5, 0).await;
+    seed(&pool, "@data/csv", "1.0.0", "Apache-2.0", "CSV reader", 3, 0).await;
+    seed(&pool, "@net/http", "1.0.0", "MIT", "HTTP client", 1, 0).await;
+    seed(&pool, "datapkg", "1.0.0", "MIT", "Not scoped", 0, 0).await;
+
+    let (_, json) = list(&pool, "/api/zorbs?scope=@data&sort=name").await;
+    assert_eq!(names(&json), ["@data/csv", "@data/serde"]);
+    let (_, json) = list(&pool, "/api/zorbs?scope=data%2F*&sort=name").await;
+    assert_eq!(json["total"], 2);
+
+    let (_, json) = list(&pool, "/api/zorbs?license=mit&sort=name").await;
+    assert_eq!(names(&json), ["@data/serde", "@net/http", "datapkg"]);
+
+    let (_, json) = list(&pool, "/api/zorbs?keyword=SERIAL").await;
+    assert_eq!(names(&json), ["@data/serde"]);
+
+    let (_, json) = list(&pool, "/api/zorbs?scope=data&license=MIT").await;
+    assert_eq!(names(&json), ["@data/serde"]);
+
+    common::create_test_token_with_scopes(&pool, "alice", &[]).await;
+    let alice = common::test_user_id(&pool, "alice").await;
+    sqlx::query("INSERT INTO zorb_owners (package_name, user_id) VALUES ('@net/http', $1), ('datapkg', $1)")
+        .bind(alice)
+        .execute(&pool)
+        .await
+        .expect("seed owners");
+    let (_, json) = list(&pool, "/api/zorbs?owner=alice&sort=name&per_page=1").await;
+    assert_eq!(json["total"], 2);
+    assert_eq!(names(&json), ["@net/http"]);
+    let next = json["next"].as_str().expect("next link");
+    assert!(next.contains("owner=alice"), "Next link keeps the filters: {}", next);
+}
@@ -0,0 +1,219 @@
+// Integration tests: Typed zorb.toml parsing and the per-version manifest API
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::{json, Value};
+use zorbs::manifest::Manifest;
+
+async fn publish(pool: &sqlx::PgPool, token: &str, file: &[u8]) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let boundary = "manifest-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", file),
+    ]);
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/zorbs/new")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn get(pool: &sqlx::PgPool, uri: &str) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+const FULL: &str = r#"
+[package]
+name = "@web/server"
+version = "1.2.0"
+edition = "2026"
+authors = "Ada <ada@example.com>"
+description = "A server"
+license = "MIT"
+repository = "https://github.com/web/server"
+keywords = ["http"]
+categories = ["network-programming"]
+
+[dependencies]
+"@async/tokio" = { version = "^1.42", features = ["full"] }
+"@log/tracing" = { version = "0.2", optional = true, default-features = false }
+"@local/util" = { path = "../util", version = "0.1" }
+
+[dev-dependencies]
+"@test/mock" = "0.3"
+
+[features]
+default = ["logging"]
+logging = ["dep:tracing", "@async/tokio/tracing"]
+
+[fmt]
+style = "zeta-pretty"
+max_width = 120
+
+[lint]
+tool = "zippy"
+level = "pedantic"
+deny = ["unsafe_code"]
+
+[build]
+script = "build.zeta"
+
+[workspace]
+members = ["crates/*"]
+"#;
+
+#[test]
+fn test_full_manifest_is_parsed() {
+    let parsed = Manifest::parse(FULL).expect("valid manifest");
+    assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+    let m = parsed.manifest;
+    assert_eq!(m.package.authors, ["Ada <ada@example.com>"]);
+    assert_eq!(m.package.edition, "2026");
+    let tracing = &m.dependencies["@log/tracing"];
+    assert!(tracing.optional && !tracing.default_features);
+    assert_eq!(m.dependencies["@local/util"].path.as_deref(), Some("../util"));
+    assert_eq!(m.dev_dependencies["@test/mock"].version.as_deref(), Some("0.3"));
+    assert_eq!(m.features["logging"], ["dep:tracing", "@async/tokio/tracing"]);
+    assert_eq!(m.fmt.unwrap().max_width, Some(120));
+    assert_eq!(m.lint.unwrap().deny, ["unsafe_code"]);
+    assert_eq!(m.workspace.unwrap().members, ["crates/*"]);
+}
+
+#[test]
+fn test_invalid_manifests_name_the_offending_key() {
+    let package = "[package]\nname = \"bad\"\nversion = \"1.0.0\"\nedition = \"2026\"\n";
+    for (rest, error) in [
+        ("edition = 2026\n", "Failed to parse zorb.toml"),
+        ("[dependencies]\nfoo = { features = \"x\", version = \"1\" }\n", "`dependencies.foo.features` in zorb.toml must be an array of strings"),
+        ("[dependencies]\nfoo = \"not a version\"\n", "`dependencies.foo` has an invalid version requirement 'not a version'"),
+        ("[dependencies]\nfoo = { features = [] }\n", "`dependencies.foo` must set `version`, `git` or `path`"),
+        ("[dependencies]\nfoo = { version = \"1\", branch = \"main\" }\n", "`dependencies.foo` sets `branch` or `rev` without `git`"),
+        ("[dependencies]\nfoo = 3\n", "`dependencies.foo` must be a version string or a table"),
+        ("[dev-dependencies]\nfoo = { version = \"1\", optional = true }\n", "`dev-dependencies.foo.optional`: dev-dependencies cannot be optional"),
+        ("[dependencies]\nfoo = \"1\"\n[features]\nx = [\"dep:foo\"]\n", "`features.x` enables `dep:foo`, but `foo` is not optional"),
+        ("[features]\nx = [\"nothing\"]\n", "`features.x` enables `nothing`, which is neither a feature nor a dependency"),
+        ("[fmt]\nstyle = \"loose\"\n", "`fmt.style` in zorb.toml must be one of: zeta-strict, zeta-pretty, custom"),
+        ("[fmt]\nmax_width = 0\n", "`fmt.max_width` in zorb.toml must be a whole number from 1 to 1000"),
+        ("[workspace]\nmembers = \"crates/*\"\n", "`workspace.members` in zorb.toml must be an array of strings"),
+    ] {
+        let err = Manifest::parse(&format!("{}{}", package, rest)).expect_err(rest);
+        assert!(err.starts_with(error), "{:?}: {}", rest, err);
+    }
+    let err = Manifest::parse("[package]\nname = \"bad\"\nversion = \"1.0.0\"\nedition = \"2015\"\n").unwrap_err();
+    assert!(err.contains("`package.edition` '2015' is not a known edition"), "{}", err);
+}
+
+#[test]
+fn test_unknown_keys_are_warnings() {
+    let parsed = Manifest::parse(
+        "[package]\nname = \"loose\"\nversion = \"1.0.0\"\npublish = false\n\n[dependencies]\nfoo = { version = \"1\", default_features = false }\n\n[profile]\nopt = 3\n",
+    )
+    .expect("unknown keys are accepted");
+    assert_eq!(parsed.warnings, [
+        "Unknown section `profile` in zorb.toml",
+        "Unknown key `package.publish` in zorb.toml",
+        "`package.edition` is not set; assuming 2026",
+        "Unknown key `dependencies.foo.default_features` in zorb.toml",
+    ]);
+}
+
+#[tokio::test]
+async fn test_manifest_is_stored_and_served() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    for name in ["@async/tokio", "@log/tracing", "@local/util", "@test/mock"] {
+        common::create_test_package(&pool, name, "9.0.0").await;
+    }
+    let tarball = common::create_test_tarball_with_manifest(FULL);
+    let (status, body) = publish(&pool, &token, &tarball).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert_eq!(body["warnings"], json!([]));
+
+    let (status, body) = get(&pool, "/api/zorbs/@web/server/1.2.0/manifest").await;
+    assert_eq!(status, StatusCode::OK);
+    let manifest = &body["manifest"];
+    assert_eq!(manifest["package"]["authors"], json!(["Ada <ada@example.com>"]));
+    assert_eq!(manifest["dependencies"]["@log/tracing"], json!({
+        "version": "0.2", "features": [], "default-features": false, "optional": true
+    }));
+    assert_eq!(manifest["dev-dependencies"]["@test/mock"]["version"], "0.3");
+    assert_eq!(manifest["features"]["default"], json!(["logging"]));
+    assert_eq!(manifest["build"]["script"], "build.zeta");
+
+    // The flattened map the resolver reads is unchanged
+    let deps: Value = sqlx::query_scalar("SELECT dependencies FROM zorbs WHERE name = '@web/server'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(deps, json!({"@async/tokio": "^1.42", "@log/tracing": "0.2", "@local/util": "0.1"}));
+
+    let (status, _) = get(&pool, "/api/zorbs/@web/server/9.9.9/manifest").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_publish_rejects_invalid_manifest_and_reports_warnings() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    let bad = common::create_test_tarball_with_manifest(
+        "[package]\nname = \"strict\"\nversion = \"1.0.0\"\n\n[dependencies]\nfoo = { version = \"1\", optional = \"yes\" }\n",
+    );
+    let (status, body) = publish(&pool, &token, &bad).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "`dependencies.foo.optional` in zorb.toml must be true or false");
+
+    let loose = common::create_test_tarball_with_manifest("[package]\nname = \"strict\"\nversion = \"1.0.0\"\nhomepage = \"x\"\n");
+    let (status, body) = publish(&pool, &token, &loose).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["warnings"], json!([
+        "Unknown key `package.homepage` in zorb.toml",
+        "`package.edition` is not set; assuming 2026",
+    ]));
+}
+
+#[tokio::test]
+async fn test_manifest_of_older_versions_is_read_from_the_archive() {
+    let pool = common::setup_database().await;
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, created_at, updated_at)
+         VALUES ($1, 'older', '1.0.0', 'Old', 'MIT', NULL, '{}'::jsonb, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .execute(&pool)
+    .await
+    .unwrap();
+    let tarball = common::create_test_tarball_with_manifest(
+        "[package]\nname = \"older\"\nversion = \"1.0.0\"\n\n[features]\nextra = []\n",
+    );
+    common::store_test_blob(&pool, "older", "1.0.0", &tarball).await;
+
+    let (status, body) = get(&pool, "/api/zorbs/older/1.0.0/manifest").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["manifest"]["features"], json!({"extra": []}));
+
+    let stored: bool = sqlx::query_scalar("SELECT manifest IS NOT NULL FROM zorbs WHERE name = 'older'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(stored, "The manifest is recorded once read");
+}
@@ -0,0 +1,190 @@
+// Integration tests: Package ownership (publish rights, owner management)
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+/// Helper: publish a tarball as the holder of `token`.
+async fn publish_as(pool: &sqlx::PgPool, token: &str, name: &str, version: &str) -> StatusCode {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let tarball = common::create_test_tarball(name, version);
+    let boundary = "owner-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", &tarball),
+    ]);
+    app.oneshot(
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/zorbs/new")
+            .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::from(body))
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+    .status()
+}
+
+/// Helper: send a JSON request to the owners API.
+async fn owners_request(pool: &sqlx::PgPool, method: Method, uri: &str, token: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("authorization", format!("Bearer {}", token));
+    let body = match body {
+        Some(json) => {
+            builder = builder.header("content-type", "application/json");
+            Body::from(json.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.oneshot(builder.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn test_publisher_becomes_owner() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let user_id = common::test_user_id(&pool, "tester").await;
+
+    assert_eq!(publish_as(&pool, &token, "@alice/widgets", "1.0.0").await, StatusCode::CREATED);
+
+    let owner_id: (Option<uuid::Uuid>,) = sqlx::query_as("SELECT owner_id FROM zorbs WHERE name = $1")
+        .bind("@alice/widgets")
+        .fetch_one(&pool)
+        .await
+        .expect("fetch zorb");
+    assert_eq!(owner_id.0, Some(user_id), "owner_id should record the publisher");
+
+    let (status, json) = owners_request(&pool, Method::GET, "/api/zorbs/@alice/widgets/owners", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["owners"].as_array().unwrap().len(), 1);
+    assert_eq!(json["owners"][0]["username"], "tester");
+}
+
+#[tokio::test]
+async fn test_non_owner_cannot_publish_new_version() {
+    let pool = common::setup_database().await;
+    let alice = common::create_test_token_with_scopes(&pool, "alice", &["publish-new", "publish-update"]).await;
+    let mallory = common::create_test_token_with_scopes(&pool, "mallory", &["publish-new", "publish-update"]).await;
+
+    assert_eq!(publish_as(&pool, &alice, "owned-pkg", "1.0.0").await, StatusCode::CREATED);
+    assert_eq!(publish_as(&pool, &mallory, "owned-pkg", "1.0.1").await, StatusCode::FORBIDDEN,
+        "Only owners may publish further versions");
+    assert_eq!(publish_as(&pool, &alice, "owned-pkg", "1.0.1").await, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_unowned_legacy_package_needs_an_admin_assigned_owner() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let other = common::create_test_token_with_scopes(&pool, "other", &["publish-new", "publish-update"]).await;
+    let admin = common::create_test_token_with_scopes(&pool, "admin", &["publish-update"]).await;
+    // A version published before ownership was recorded: no owner_id, no zorb_owners row
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, downloads, created_at, updated_at)
+         VALUES ($1, 'legacy-pkg', '1.0.0', 'Seeded without owner', 'MIT', NULL, '{}'::jsonb, 0, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .execute(&pool)
+    .await
+    .expect("seed legacy package");
+
+    assert_eq!(publish_as(&pool, &token, "legacy-pkg", "1.0.1").await, StatusCode::FORBIDDEN);
+    assert_eq!(publish_as(&pool, &other, "legacy-pkg", "1.0.1").await, StatusCode::FORBIDDEN, "Nobody takes over an ownerless package");
+    let owners: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM zorb_owners WHERE package_name = 'legacy-pkg'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(owners, 0);
+    let (status, json) = owners_request(&pool, Method::GET, "/api/zorbs/legacy-pkg/owners", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["owners"], serde_json::json!([]));
+    let (status, _) = owners_request(&pool, Method::GET, "/api/zorbs/never-published/owners", &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let assign = serde_json::json!({"username": "tester"});
+    let (status, _) = owners_request(&pool, Method::PUT, "/api/zorbs/legacy-pkg/owners", &admin, Some(assign.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "Only admins assign owners they do not share");
+    zorbs::db::queries::set_admin(&pool, "admin", None, true).await.unwrap();
+    let (status, _) = owners_request(&pool, Method::PUT, "/api/zorbs/legacy-pkg/owners", &admin, Some(assign.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = owners_request(&pool, Method::PUT, "/api/zorbs/never-published/owners", &admin, Some(assign)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "Admins cannot reserve names");
+
+    assert_eq!(publish_as(&pool, &token, "legacy-pkg", "1.0.1").await, StatusCode::CREATED);
+    assert_eq!(publish_as(&pool, &other, "legacy-pkg", "1.0.2").await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_owner_can_add_and_remove_owners() {
+    let pool = common::setup_database().await;
+    let alice = common::create_test_token_with_scopes(&pool, "alice", &["publish-new", "publish-update"]).await;
+    let bob = common::create_test_token_with_scopes(&pool, "bob", &["publish-new", "publish-update"]).await;
+    let alice_id = common::test_user_id(&pool, "alice").await;
+    let bob_id = common::test_user_id(&pool, "bob").await;
+
+    assert_eq!(publish_as(&pool, &alice, "shared-pkg", "1.0.0").await, StatusCode::CREATED);
+
+    // bob is not yet an owner and cannot add himself
+    let (status, _) = owners_request(&pool, Method::PUT, "/api/zorbs/shared-pkg/owners", &bob,
+        Some(serde_json::json!({"username": "bob"}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = owners_request(&pool, Method::PUT, "/api/zorbs/shared-pkg/owners", &alice,
+        Some(serde_json::json!({"username": "bob"}))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(publish_as(&pool, &bob, "shared-pkg", "1.1.0").await, StatusCode::CREATED,
+        "Added owner should be able to publish");
+
+    let (status, json) = owners_request(&pool, Method::GET, "/api/zorbs/shared-pkg/owners", &alice, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["owners"].as_array().unwrap().len(), 2);
+
+    let uri = format!("/api/zorbs/shared-pkg/owners/{}", alice_id);
+    let (status, _) = owners_request(&pool, Method::DELETE, &uri, &bob, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(publish_as(&pool, &alice, "shared-pkg", "1.2.0").await, StatusCode::FORBIDDEN,
+        "Removed owner should lose publish rights");
+
+    // the last owner cannot be removed
+    let uri = format!("/api/zorbs/shared-pkg/owners/{}", bob_id);
+    let (status, _) = owners_request(&pool, Method::DELETE, &uri, &bob, None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_concurrent_removals_keep_an_owner() {
+    let pool = common::setup_database().await;
+    let alice = common::create_test_token_with_scopes(&pool, "alice", &["publish-new", "publish-update"]).await;
+    common::create_test_token_with_scopes(&pool, "bob", &["publish-update"]).await;
+    let alice_id = common::test_user_id(&pool, "alice").await;
+    let bob_id = common::test_user_id(&pool, "bob").await;
+    assert_eq!(publish_as(&pool, &alice, "raced-pkg", "1.0.0").await, StatusCode::CREATED);
+
+    for _ in 0..20 {
+        zorbs::db::queries::add_owner(&pool, "raced-pkg", bob_id, alice_id).await.unwrap();
+        zorbs::db::queries::add_owner(&pool, "raced-pkg", alice_id, alice_id).await.unwrap();
+        let (a, b) = tokio::join!(
+            zorbs::db::queries::remove_owner(&pool, "raced-pkg", alice_id),
+            zorbs::db::queries::remove_owner(&pool, "raced-pkg", bob_id),
+        );
+        assert!(a.unwrap() ^ b.unwrap(), "Exactly one of two concurrent removals succeeds");
+        let owners: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM zorb_owners WHERE package_name = 'raced-pkg'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(owners, 1);
+    }
+}
@@ -47,14 +47,15 @@ async fn seed_test_packages(pool: &sqlx::PgPool) {
 #[tokio::test]
 async fn test_publish_package() {
     let pool = common::setup_database().await;
-    let mut app = common::build_test_app_from_pool(pool.clone());
+    let token = common::create_test_token(&pool).await;
+    let app = common::build_test_app_from_pool(pool.clone());
 
     // Create a valid tarball
     let tarball = common::create_test_tarball("my-zeta-lib", "0.1.0");
 
     // Build multipart body manually
     let boundary = "test-boundary-12345";
-    let body = build_multipart_body(boundary, &[
+    let body = common::build_multipart_body(boundary, &[
         ("file", "my-zeta-lib-0.1.0.tar.gz", "application/octet-stream", &tarball),
         ("name", "", "text/plain", b""),
         ("version", "", "text/plain", b""),
@@ -66,6 +67,7 @@ async fn test_publish_package() {
                 .method(Method::POST)
                 .uri("/api/zorbs/new")
                 .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::from(body))
                 .unwrap(),
         )
@@ -83,13 +85,58 @@ async fn test_publish_package() {
     assert!(json["id"].is_string(), "Should return a UUID");
 }
 
+#[tokio::test]
+async fn test_publish_records_checksum() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    let tarball = common::create_test_tarball("checksum-lib", "0.1.0");
+    let expected = zorbs::utils::sha256_hex(&tarball);
+
+    let boundary = "checksum-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "checksum-lib-0.1.0.tar.gz", "application/octet-stream", &tarball),
+    ]);
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/zorbs/new")
+                .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["checksum"], expected.as_str());
+
+    // Exposed by the resolve API, the listing API and the detail page
+    for uri in ["/api/resolve?name=checksum-lib", "/api/zorbs", "/checksum-lib"] {
+        let app = common::build_test_app_from_pool(pool.clone());
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body_bytes).contains(&expected),
+            "{} should include the checksum", uri);
+    }
+}
+
 #[tokio::test]
 async fn test_publish_missing_file_returns_400() {
     let pool = common::setup_database().await;
-    let mut app = common::build_test_app_from_pool(pool);
+    let token = common::create_test_token(&pool).await;
+    let app = common::build_test_app_from_pool(pool);
 
     let boundary = "test-boundary-2";
-    let body = build_multipart_body(boundary, &[
+    let body = common::build_multipart_body(boundary, &[
         ("name", "", "text/plain", b"test-pkg"),
         ("version", "", "text/plain", b"1.0.0"),
     ]);
@@ -100,6 +147,7 @@ async fn test_publish_missing_file_returns_400() {
                 .method(Method::POST)
                 .uri("/api/zorbs/new")
                 .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::from(body))
                 .unwrap(),
         )
@@ -113,13 +161,14 @@ async fn test_publish_missing_file_returns_400() {
 #[tokio::test]
 async fn test_publish_invalid_tarball_returns_400() {
     let pool = common::setup_database().await;
-    let mut app = common::build_test_app_from_pool(pool);
+    let token = common::create_test_token(&pool).await;
+    let app = common::build_test_app_from_pool(pool);
 
     // Invalid bytes (not a tar.gz)
     let garbage = b"not a valid tarball";
 
     let boundary = "test-boundary-3";
-    let body = build_multipart_body(boundary, &[
+    let body = common::build_multipart_body(boundary, &[
         ("file", "bad.zorb", "application/octet-stream", garbage),
     ]);
 
@@ -129,6 +178,7 @@ async fn test_publish_invalid_tarball_returns_400() {
                 .method(Method::POST)
                 .uri("/api/zorbs/new")
                 .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::from(body))
                 .unwrap(),
         )
@@ -149,16 +199,17 @@ async fn test_publish_invalid_tarball_returns_400() {
 }
 
 #[tokio::test]
-async fn test_publish_duplicate_overwrites() {
+async fn test_publish_duplicate_version_returns_409() {
     let pool = common::setup_database().await;
-    let mut app = common::build_test_app_from_pool(pool.clone());
+    let token = common::create_test_token(&pool).await;
+    let app = common::build_test_app_from_pool(pool.clone());
 
+    common::create_test_package(&pool, "my-package", "1.0.0").await;
     let tarball1 = common::create_test_tarball("overwrite-test", "1.0.0");
-    let tarball2 = common::create_test_tarball("overwrite-test", "1.0.0");
 
     // First publish
     let boundary1 = "boundary-dup-1";
-    let body1 = build_multipart_body(boundary1, &[
+    let body1 = common::build_multipart_body(boundary1, &[
         ("file", "overwrite-test-1.0.0.tar.gz", "application/octet-stream", &tarball1),
     ]);
     let resp1 = app
@@ -167,6 +218,7 @@ async fn test_publish_duplicate_overwrites() {
                 .method(Method::POST)
                 .uri("/api/zorbs/new")
                 .header("content-type", format!("multipart/form-data; boundary={}", boundary1))
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::from(body1))
                 .unwrap(),
         )
@@ -174,10 +226,11 @@ async fn test_publish_duplicate_overwrites() {
         .unwrap();
     assert_eq!(resp1.status(), StatusCode::CREATED);
 
-    // Second publish (same name+version) — should not error (upsert behavior)
-    let mut app2 = common::build_test_app_from_pool(pool.clone());
+    // Second publish (same name+version, different contents) — versions are immutable
+    let tarball2 = common::create_test_tarball_with_deps("overwrite-test", "1.0.0", &[("my-package", "^1.0")]);
+    let app2 = common::build_test_app_from_pool(pool.clone());
     let boundary2 = "boundary-dup-2";
-    let body2 = build_multipart_body(boundary2, &[
+    let body2 = common::build_multipart_body(boundary2, &[
         ("file", "overwrite-test-1.0.0.tar.gz", "application/octet-stream", &tarball2),
     ]);
     let resp2 = app2
@@ -186,14 +239,127 @@ async fn test_publish_duplicate_overwrites() {
                 .method(Method::POST)
                 .uri("/api/zorbs/new")
                 .header("content-type", format!("multipart/form-data; boundary={}", boundary2))
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::from(body2))
                 .unwrap(),
         )
         .await
         .unwrap();
-    // Should still create/upsert — ON CONFLICT DO UPDATE
-    assert_eq!(resp2.status(), StatusCode::CREATED,
-        "Duplicate publish should upsert (ON CONFLICT DO UPDATE)");
+    assert_eq!(resp2.status(), StatusCode::CONFLICT,
+        "Duplicate publish must be rejected");
+    let body_bytes = resp2.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert!(json["error"].as_str().unwrap().contains("overwrite-test@1.0.0"));
+
+    // Neither the row nor the stored blob changed
+    let row: (Option<String>, serde_json::Value) = sqlx::query_as(
+        "SELECT checksum, dependencies FROM zorbs WHERE name = 'overwrite-test' AND version = '1.0.0'"
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("fetch zorb");
+    assert_eq!(row.0.as_deref(), Some(zorbs::utils::sha256_hex(&tarball1).as_str()));
+    assert_eq!(row.1, serde_json::json!({}));
+    let stored = common::stored_blob(&zorbs::utils::blob_key(&zorbs::utils::sha256_hex(&tarball1))).await.expect("stored blob");
+    assert_eq!(stored, tarball1, "Stored blob must not be overwritten");
+}
+
+/// Publish `tarball` with `token` and return the response status.
+async fn publish(pool: &sqlx::PgPool, token: &str, tarball: &[u8]) -> StatusCode {
+    let boundary = "boundary-publish";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "package.tar.gz", "application/octet-stream", tarball),
+    ]);
+    common::build_test_app_from_pool(pool.clone())
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/zorbs/new")
+                .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_publish_reuses_identical_blob() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    // The same archive is already stored (e.g. left behind by an earlier failure)
+    let tarball = common::create_test_tarball("stale-blob", "1.0.0");
+    let key = zorbs::utils::blob_key(&zorbs::utils::sha256_hex(&tarball));
+    common::store_raw_blob(&key, &tarball).await;
+
+    assert_eq!(publish(&pool, &token, &tarball).await, StatusCode::CREATED);
+    assert_eq!(common::stored_keys().await, vec![key.clone()], "Stored once, under its digest");
+    assert_eq!(common::stored_blob(&key).await.unwrap(), tarball);
+}
+
+#[tokio::test]
+async fn test_publish_replaces_truncated_blob() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    // Left by a write cut short before blobs were written atomically
+    let tarball = common::create_test_tarball("truncated-blob", "1.0.0");
+    let key = zorbs::utils::blob_key(&zorbs::utils::sha256_hex(&tarball));
+    common::store_raw_blob(&key, &tarball[..tarball.len() / 2]).await;
+
+    assert_eq!(publish(&pool, &token, &tarball).await, StatusCode::CREATED);
+    assert_eq!(common::stored_blob(&key).await.unwrap(), tarball, "The damaged blob is not shared");
+}
+
+#[tokio::test]
+async fn test_publish_holds_the_blob_lock_until_commit() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let tarball = common::create_test_tarball("locked-blob", "1.0.0");
+    let checksum = zorbs::utils::sha256_hex(&tarball);
+
+    // Stands in for an orphan cleanup of the same archive, which checks and deletes under this lock
+    let mut cleanup = pool.begin().await.unwrap();
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('blob:' || $1))")
+        .bind(&checksum)
+        .execute(&mut *cleanup)
+        .await
+        .unwrap();
+    let publishing = tokio::spawn({
+        let (pool, token, tarball) = (pool.clone(), token.clone(), tarball.clone());
+        async move { publish(&pool, &token, &tarball).await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    assert!(!publishing.is_finished(), "The publish waits for the cleanup");
+    assert!(common::stored_blob(&zorbs::utils::blob_key(&checksum)).await.is_none(), "Nothing is stored while the cleanup runs");
+    cleanup.commit().await.unwrap();
+    assert_eq!(publishing.await.unwrap(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_names_that_flatten_alike_do_not_collide() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    // Both flatten to left-pad-util-1.0.0.zorb, which used to be their shared storage key
+    let first = common::create_test_tarball("@left-pad/util", "1.0.0");
+    let second = common::create_test_tarball("@left/pad-util", "1.0.0");
+    assert_eq!(publish(&pool, &token, &first).await, StatusCode::CREATED);
+    assert_eq!(publish(&pool, &token, &second).await, StatusCode::CREATED);
+
+    for (uri, expected) in [("/@left-pad/util/1.0.0/download", &first), ("/@left/pad-util/1.0.0/download", &second)] {
+        let response = common::build_test_app_from_pool(pool.clone())
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], &expected[..], "{} serves its own archive", uri);
+    }
+    assert_eq!(common::stored_keys().await.len(), 2);
 }
 
 // ─── List & Search tests ────────────────────────────────────────
@@ -422,16 +588,12 @@ async fn test_download_increments_counter() {
     let pool = common::setup_database().await;
     seed_test_packages(&pool).await;
 
-    // Create a tarball in the upload dir so the file exists
-    let upload_dir = "uploads";
-    tokio::fs::create_dir_all(upload_dir).await.unwrap();
+    // Store a tarball so the file exists
     let tarball = common::create_test_tarball("my-package", "1.0.0");
-    let filename = "my-package-1.0.0.zorb";
-    tokio::fs::write(format!("{}/{}", upload_dir, filename), &tarball)
-        .await
-        .unwrap();
+    common::store_test_blob(&pool, "my-package", "1.0.0", &tarball).await;
 
-    let app = common::build_test_app_from_pool(pool.clone());
+    let state = common::build_test_state(pool.clone());
+    let app = zorbs::build_app(state.clone());
 
     let response = app
         .oneshot(
@@ -446,7 +608,8 @@ async fn test_download_increments_counter() {
     assert_eq!(response.status(), StatusCode::OK,
         "Download should return 200 when file exists");
 
-    // Check that download count was incremented
+    // Check that download count was incremented once buffered counts are flushed
+    state.downloads.flush(&pool).await.expect("flush downloads");
     let row: (i64,) = sqlx::query_as(
         "SELECT downloads FROM zorbs WHERE name = $1 AND version = $2"
     )
@@ -457,9 +620,6 @@ async fn test_download_increments_counter() {
     .expect("fetch download count");
 
     assert!(row.0 >= 1, "Download count should be incremented (got {})", row.0);
-
-    // Cleanup
-    let _ = tokio::fs::remove_file(format!("{}/{}", upload_dir, filename)).await;
 }
 
 #[tokio::test]
@@ -467,13 +627,8 @@ async fn test_download_scoped_package() {
     let pool = common::setup_database().await;
     seed_test_packages(&pool).await;
 
-    let upload_dir = "uploads";
-    tokio::fs::create_dir_all(upload_dir).await.unwrap();
-    let filename = "async-tokio-0.3.10.zorb";
     let tarball = common::create_test_tarball("@async/tokio", "0.3.10");
-    tokio::fs::write(format!("{}/{}", upload_dir, filename), &tarball)
-        .await
-        .unwrap();
+    common::store_test_blob(&pool, "@async/tokio", "0.3.10", &tarball).await;
 
     let app = common::build_test_app_from_pool(pool);
 
@@ -489,34 +644,4 @@ async fn test_download_scoped_package() {
 
     assert_eq!(response.status(), StatusCode::OK,
         "Scoped package download should work");
-
-    // Cleanup
-    let _ = tokio::fs::remove_file(format!("{}/{}", upload_dir, filename)).await;
-}
-
-// ─── Multipart body builder ─────────────────────────────────────
-
-fn build_multipart_body(boundary: &str, fields: &[(&str, &str, &str, &[u8])]) -> Vec<u8> {
-    // fields: (name, filename, content_type, data)
-    let mut body = Vec::new();
-    for (name, filename, content_type, data) in fields {
-        let mut header = String::new();
-        header += &format!("--{}\r\n", boundary);
-        header += &format!("Content-Disposition: form-data; name=\"{}\"", name);
-        if !filename.is_empty() {
-            header += &format!("; filename=\"{}\"", filename);
-        }
-        header += "\r\n";
-        if !content_type.is_empty() {
-            header += &format!("Content-Type: {}\r\n", content_type);
-        }
-        header += "\r\n";
-        body.extend_from_slice(header.as_bytes());
-        body.extend_from_slice(data);
-        body.extend_from_slice(b"\r\n");
-    }
-    // Closing boundary
-    let footer = format!("--{}--\r\n", boundary);
-    body.extend_from_slice(footer.as_bytes());
-    body
 }
@@ -159,3 +159,192 @@ async fn test_resolve_empty_name() {
     }
     // Otherwise it's a validation rejection which is fine
 }
+
+async fn seed_versions(pool: &sqlx::PgPool, name: &str, versions: &[&str]) {
+    for version in versions {
+        sqlx::query(
+            "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, downloads, created_at, updated_at)
+             VALUES ($1, $2, $3, 'Semver test package', 'MIT', NULL, '{}'::jsonb, 0, NOW(), NOW())"
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(name)
+        .bind(version)
+        .execute(pool)
+        .await
+        .expect("seed version");
+    }
+}
+
+async fn resolve_uri(pool: &sqlx::PgPool, uri: &str) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn test_resolve_honours_requirement() {
+    let pool = common::setup_database().await;
+    // Inserted out of order so created_at cannot stand in for semver ordering
+    seed_versions(&pool, "semver-pkg", &["1.4.2", "2.0.0", "1.10.0", "1.3.9", "0.2.5"]).await;
+
+    for (req, expected) in [("%5E1.4", "1.10.0"), ("~1.4", "1.4.2"), ("*", "2.0.0"), ("~0.2", "0.2.5"), ("%3C1.4", "1.3.9")] {
+        let (status, json) = resolve_uri(&pool, &format!("/api/resolve?name=semver-pkg&req={}", req)).await;
+        assert_eq!(status, StatusCode::OK, "req {} should resolve: {}", req, json);
+        assert_eq!(json["version"], expected, "req {}", req);
+    }
+
+    // Without a requirement the highest version wins, not the most recent upload
+    let (_, json) = resolve_uri(&pool, "/api/resolve?name=semver-pkg").await;
+    assert_eq!(json["version"], "2.0.0");
+
+    // `version=` is accepted as an alias for `req=`
+    let (_, json) = resolve_uri(&pool, "/api/resolve?name=semver-pkg&version=%5E1").await;
+    assert_eq!(json["version"], "1.10.0");
+}
+
+#[tokio::test]
+async fn test_resolve_prerelease_requires_opt_in() {
+    let pool = common::setup_database().await;
+    seed_versions(&pool, "pre-pkg", &["1.0.0", "2.0.0-beta.1", "2.0.0-beta.2"]).await;
+
+    let (_, json) = resolve_uri(&pool, "/api/resolve?name=pre-pkg").await;
+    assert_eq!(json["version"], "1.0.0", "Prereleases must not match by default");
+
+    let (_, json) = resolve_uri(&pool, "/api/resolve?name=pre-pkg&req=%3E%3D1").await;
+    assert_eq!(json["version"], "1.0.0");
+
+    let (status, json) = resolve_uri(&pool, "/api/resolve?name=pre-pkg&req=%3E%3D2.0.0-beta.1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["version"], "2.0.0-beta.2");
+}
+
+#[tokio::test]
+async fn test_resolve_unsatisfiable_lists_available_versions() {
+    let pool = common::setup_database().await;
+    seed_versions(&pool, "old-pkg", &["1.0.0", "1.1.0"]).await;
+
+    let (status, json) = resolve_uri(&pool, "/api/resolve?name=old-pkg&req=%5E2").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["code"], "unresolvable");
+    assert_eq!(json["details"]["available"], serde_json::json!(["1.1.0", "1.0.0"]));
+
+    let (status, json) = resolve_uri(&pool, "/api/resolve?name=old-pkg&req=not-a-req").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "bad_request");
+    assert!(json["error"].as_str().unwrap().contains("not-a-req"));
+
+    let (status, _) = resolve_uri(&pool, "/api/resolve?name=missing-pkg&req=%5E1").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+// ─── Dependency graph resolution ────────────────────────────────
+
+async fn seed_with_deps(pool: &sqlx::PgPool, name: &str, version: &str, deps: Value) {
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, downloads, created_at, updated_at)
+         VALUES ($1, $2, $3, 'Graph test package', 'MIT', NULL, $4, 0, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(name)
+    .bind(version)
+    .bind(deps)
+    .execute(pool)
+    .await
+    .expect("seed version with deps");
+}
+
+async fn resolve_graph(pool: &sqlx::PgPool, dependencies: Value) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/resolve/graph")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::json!({"dependencies": dependencies}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+fn resolved_versions(json: &Value) -> Vec<(String, String)> {
+    json["packages"].as_array().expect("packages array").iter()
+        .map(|p| (p["name"].as_str().unwrap().to_string(), p["version"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_resolve_graph_transitive_and_deduplicated() {
+    let pool = common::setup_database().await;
+    seed_with_deps(&pool, "g-web", "1.0.0", serde_json::json!({"g-log": "^1.2", "@g/http": "~0.3"})).await;
+    seed_with_deps(&pool, "@g/http", "0.3.1", serde_json::json!({"g-log": "*"})).await;
+    seed_with_deps(&pool, "@g/http", "0.4.0", serde_json::json!({})).await;
+    for version in ["1.1.0", "1.3.0", "2.0.0"] {
+        seed_with_deps(&pool, "g-log", version, serde_json::json!({})).await;
+    }
+
+    let (status, json) = resolve_graph(&pool, serde_json::json!({"g-web": "^1", "g-log": {"version": "^1"}})).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(resolved_versions(&json), vec![
+        ("@g/http".to_string(), "0.3.1".to_string()),
+        ("g-log".to_string(), "1.3.0".to_string()),
+        ("g-web".to_string(), "1.0.0".to_string()),
+    ]);
+    let http = &json["packages"][0];
+    assert_eq!(http["download_url"], "/@g/http/0.3.1/download");
+    assert!(http.get("checksum").is_some());
+}
+
+#[tokio::test]
+async fn test_resolve_graph_backtracks() {
+    let pool = common::setup_database().await;
+    // The newest bt-a needs bt-c ^2, which the root's bt-c ^1 rules out
+    seed_with_deps(&pool, "bt-a", "2.0.0", serde_json::json!({"bt-c": "^2"})).await;
+    seed_with_deps(&pool, "bt-a", "1.0.0", serde_json::json!({"bt-c": "^1"})).await;
+    seed_with_deps(&pool, "bt-c", "1.0.0", serde_json::json!({})).await;
+    seed_with_deps(&pool, "bt-c", "2.0.0", serde_json::json!({})).await;
+
+    let (status, json) = resolve_graph(&pool, serde_json::json!({"bt-a": "*", "bt-c": "^1"})).await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    assert_eq!(resolved_versions(&json), vec![
+        ("bt-a".to_string(), "1.0.0".to_string()),
+        ("bt-c".to_string(), "1.0.0".to_string()),
+    ]);
+}
+
+#[tokio::test]
+async fn test_resolve_graph_conflict_names_the_chain() {
+    let pool = common::setup_database().await;
+    seed_with_deps(&pool, "cf-x", "1.0.0", serde_json::json!({"cf-z": "^1"})).await;
+    seed_with_deps(&pool, "cf-y", "1.0.0", serde_json::json!({"cf-z": "^2"})).await;
+    seed_with_deps(&pool, "cf-z", "1.0.0", serde_json::json!({})).await;
+    seed_with_deps(&pool, "cf-z", "2.0.0", serde_json::json!({})).await;
+
+    let (status, json) = resolve_graph(&pool, serde_json::json!({"cf-x": "^1", "cf-y": "^1"})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["details"]["conflict"]["name"], "cf-z");
+    let error = json["error"].as_str().unwrap();
+    assert!(error.contains("cf-x@1.0.0") && error.contains("cf-y@1.0.0"),
+        "Conflict should name both requirement chains: {}", error);
+}
+
+#[tokio::test]
+async fn test_resolve_graph_missing_dependency() {
+    let pool = common::setup_database().await;
+    seed_with_deps(&pool, "needs-ghost", "1.0.0", serde_json::json!({"ghost-pkg": "^1"})).await;
+
+    let (status, json) = resolve_graph(&pool, serde_json::json!({"needs-ghost": "^1"})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "not_found");
+    assert_eq!(json["details"]["requirement"]["name"], "ghost-pkg");
+    assert_eq!(json["details"]["requirement"]["path"], serde_json::json!(["needs-ghost@1.0.0"]));
+}
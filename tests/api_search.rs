@@ -0,0 +1,131 @@
+// Integration tests: Full-text and fuzzy package search
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+async fn seed(pool: &sqlx::PgPool, name: &str, version: &str, description: &str, readme: Option<&str>, downloads: i64) {
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, readme, downloads, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, 'MIT', NULL, '{}'::jsonb, $5, $6, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(name)
+    .bind(version)
+    .bind(description)
+    .bind(readme)
+    .bind(downloads)
+    .execute(pool)
+    .await
+    .expect("seed version");
+}
+
+async fn search(pool: &sqlx::PgPool, query: &str) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/search?{}", query))
+                .header("accept", "application/json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+fn names(json: &Value) -> Vec<String> {
+    json["results"]
+        .as_array()
+        .expect("results array")
+        .iter()
+        .map(|r| r["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn seed_catalog(pool: &sqlx::PgPool) {
+    seed(pool, "@data/serde", "1.0.0", "Serialization framework for Zeta", None, 900).await;
+    seed(pool, "@data/serde", "1.1.0", "Serialization framework for Zeta", None, 100).await;
+    seed(pool, "@async/tokio", "1.42.0", "The async runtime that powers Zeta", None, 5000).await;
+    seed(pool, "jsonish", "0.3.0", "Loose JSON parser",
+        Some("# jsonish\n\nParses almost-JSON and hands it to serde for serialization."), 10).await;
+    seed(pool, "pixels", "0.1.0", "Image decoding", None, 50).await;
+}
+
+#[tokio::test]
+async fn test_search_one_result_per_package_ranked_by_relevance() {
+    let pool = common::setup_database().await;
+    seed_catalog(&pool).await;
+
+    let (status, json) = search(&pool, "q=serde").await;
+    assert_eq!(status, StatusCode::OK);
+    let found = names(&json);
+    assert_eq!(found.iter().filter(|n| *n == "@data/serde").count(), 1,
+        "Each package appears once: {:?}", found);
+    assert_eq!(found[0], "@data/serde", "Name match ranks first: {:?}", found);
+    assert!(found.contains(&"jsonish".to_string()), "README matches are found: {:?}", found);
+    assert!(!found.contains(&"pixels".to_string()));
+    assert_eq!(json["results"][0]["version"], "1.1.0");
+    assert_eq!(json["results"][0]["downloads"], 1000);
+}
+
+#[tokio::test]
+async fn test_search_tolerates_typos_and_prefixes() {
+    let pool = common::setup_database().await;
+    seed_catalog(&pool).await;
+
+    let (_, json) = search(&pool, "q=tokoi").await;
+    assert_eq!(names(&json).first().map(String::as_str), Some("@async/tokio"), "{}", json);
+
+    let (_, json) = search(&pool, "q=pix").await;
+    assert_eq!(names(&json).first().map(String::as_str), Some("pixels"), "{}", json);
+
+    let (_, json) = search(&pool, "q=serializing").await;
+    assert!(names(&json).contains(&"@data/serde".to_string()), "Stemmed matches: {}", json);
+}
+
+#[tokio::test]
+async fn test_search_highlights_snippets() {
+    let pool = common::setup_database().await;
+    seed_catalog(&pool).await;
+    seed(&pool, "risky", "1.0.0", "Runtime <script>alert(1)</script> helpers", None, 0).await;
+
+    let (_, json) = search(&pool, "q=runtime").await;
+    let snippets: Vec<&str> = json["results"].as_array().unwrap().iter()
+        .filter_map(|r| r["snippet"].as_str())
+        .collect();
+    assert!(snippets.iter().any(|s| s.contains("<mark>runtime</mark>")), "{:?}", snippets);
+    assert!(snippets.iter().all(|s| !s.contains("<script>")), "Snippets are escaped: {:?}", snippets);
+}
+
+#[tokio::test]
+async fn test_search_paginates() {
+    let pool = common::setup_database().await;
+    for i in 0..5 {
+        seed(&pool, &format!("widget-{}", i), "1.0.0", "A widget toolkit", None, i).await;
+    }
+
+    let (_, json) = search(&pool, "q=widget&per_page=2").await;
+    assert_eq!(json["total"], 5);
+    assert_eq!(json["pages"], 3);
+    assert_eq!(names(&json).len(), 2);
+    let next = json["next"].as_str().expect("next link").to_string();
+    assert!(next.contains("q=widget"));
+
+    let (_, json) = search(&pool, "q=widget&per_page=2&page=3").await;
+    assert_eq!(names(&json).len(), 1);
+    assert!(json["next"].is_null());
+
+    let (status, json) = search(&pool, "q=widget&page=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "bad_request");
+}
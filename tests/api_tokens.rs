@@ -0,0 +1,236 @@
+// Integration tests: API tokens and authenticated publishing
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+/// Helper: publish a tarball with an optional Authorization header value.
+async fn publish_with_auth(app: axum::Router, auth: Option<String>, tarball: Vec<u8>) -> (StatusCode, Value) {
+    let boundary = "token-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", &tarball),
+    ]);
+    let mut builder = Request::builder()
+        .method(Method::POST)
+        .uri("/api/zorbs/new")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary));
+    if let Some(auth) = auth {
+        builder = builder.header("authorization", auth);
+    }
+    let response = app
+        .oneshot(builder.body(Body::from(body)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body_bytes).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn test_publish_without_token_returns_401() {
+    let pool = common::setup_database().await;
+    let app = common::build_test_app_from_pool(pool);
+
+    let tarball = common::create_test_tarball("anon-pkg", "1.0.0");
+    let (status, json) = publish_with_auth(app, None, tarball).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "Anonymous publish must be rejected");
+    assert!(json["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_publish_with_unknown_token_returns_401() {
+    let pool = common::setup_database().await;
+    let app = common::build_test_app_from_pool(pool);
+
+    let tarball = common::create_test_tarball("bad-token-pkg", "1.0.0");
+    let (status, _) = publish_with_auth(app, Some("Bearer zorb_not-a-real-token".to_string()), tarball).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_publish_with_valid_token_succeeds() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let app = common::build_test_app_from_pool(pool.clone());
+
+    let tarball = common::create_test_tarball("token-pkg", "1.0.0");
+    let (status, json) = publish_with_auth(app, Some(format!("Bearer {}", token)), tarball).await;
+
+    assert_eq!(status, StatusCode::CREATED, "Valid token should publish: {:?}", json);
+
+    let last_used: (Option<chrono::DateTime<chrono::Utc>>,) = sqlx::query_as(
+        "SELECT last_used_at FROM api_tokens WHERE token_hash = $1"
+    )
+    .bind(zorbs::models::token::hash_token(&token))
+    .fetch_one(&pool)
+    .await
+    .expect("fetch token");
+    assert!(last_used.0.is_some(), "Token use should be recorded");
+}
+
+#[tokio::test]
+async fn test_revoked_and_expired_tokens_are_rejected() {
+    let pool = common::setup_database().await;
+    let revoked = common::create_test_token(&pool).await;
+    let expired = common::create_test_token(&pool).await;
+
+    sqlx::query("UPDATE api_tokens SET revoked_at = NOW() WHERE token_hash = $1")
+        .bind(zorbs::models::token::hash_token(&revoked))
+        .execute(&pool)
+        .await
+        .expect("revoke token");
+    sqlx::query("UPDATE api_tokens SET expires_at = NOW() - INTERVAL '1 day' WHERE token_hash = $1")
+        .bind(zorbs::models::token::hash_token(&expired))
+        .execute(&pool)
+        .await
+        .expect("expire token");
+
+    for token in [revoked, expired] {
+        let app = common::build_test_app_from_pool(pool.clone());
+        let tarball = common::create_test_tarball("stale-token-pkg", "1.0.0");
+        let (status, _) = publish_with_auth(app, Some(format!("Bearer {}", token)), tarball).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[tokio::test]
+async fn test_token_scopes_are_enforced() {
+    let pool = common::setup_database().await;
+    let full = common::create_test_token(&pool).await;
+    let update_only = common::create_test_token_with_scopes(&pool, "tester", &["publish-update"]).await;
+
+    // publish-update cannot claim a new name
+    let app = common::build_test_app_from_pool(pool.clone());
+    let tarball = common::create_test_tarball("scoped-token-pkg", "1.0.0");
+    let (status, json) = publish_with_auth(app, Some(format!("Bearer {}", update_only)), tarball).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "publish-update token must not publish new names");
+    assert!(json["error"].as_str().unwrap().contains("publish-new"));
+
+    let app = common::build_test_app_from_pool(pool.clone());
+    let tarball = common::create_test_tarball("scoped-token-pkg", "1.0.0");
+    let (status, _) = publish_with_auth(app, Some(format!("Bearer {}", full)), tarball).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // ...but it can push a new version of an existing one
+    let app = common::build_test_app_from_pool(pool.clone());
+    let tarball = common::create_test_tarball("scoped-token-pkg", "1.1.0");
+    let (status, _) = publish_with_auth(app, Some(format!("Bearer {}", update_only)), tarball).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_token_management_requires_session() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let app = common::build_test_app_from_pool(pool);
+
+    // API tokens cannot be used to mint or list other tokens
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/tokens")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+/// Helper: send a JSON request to a token route, signed in through `cookie`.
+async fn session_request(app: &axum::Router, cookie: &str, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let mut builder = Request::builder().method(method).uri(uri).header("cookie", cookie);
+    let body = match body {
+        Some(json) => {
+            builder = builder.header("content-type", "application/json");
+            Body::from(json.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.clone().oneshot(builder.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body_bytes).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn test_session_creates_lists_and_revokes_tokens() {
+    let pool = common::setup_database().await;
+    zorbs::db::find_or_create_user(&pool, "github", "tester", "tester", None, None).await.unwrap();
+    let app = common::build_test_app_with_login(pool.clone());
+    let cookie = common::session_cookie(&app, "tester").await;
+
+    let (status, created) = session_request(&app, &cookie, Method::POST, "/api/tokens", Some(serde_json::json!({
+        "name": "ci",
+        "scopes": ["publish-new", "publish-update"],
+        "expires_in_days": 30
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let token = created["token"].as_str().unwrap().to_string();
+    let id = created["id"].as_str().unwrap().to_string();
+    assert!(token.starts_with(zorbs::models::token::TOKEN_PREFIX));
+
+    let (status, listed) = session_request(&app, &cookie, Method::GET, "/api/tokens", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let tokens = listed["tokens"].as_array().unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0]["name"], "ci");
+    assert_eq!(tokens[0]["scopes"], serde_json::json!(["publish-new", "publish-update"]));
+    assert!(!listed.to_string().contains(&token), "plaintext must never be listed");
+    assert!(tokens[0].get("token_hash").is_none());
+
+    let tarball = common::create_test_tarball("session-token-pkg", "1.0.0");
+    let (status, _) = publish_with_auth(app.clone(), Some(format!("Bearer {}", token)), tarball).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) = session_request(&app, &cookie, Method::DELETE, &format!("/api/tokens/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, listed) = session_request(&app, &cookie, Method::GET, "/api/tokens", None).await;
+    assert!(!listed["tokens"][0]["revoked_at"].is_null());
+
+    let tarball = common::create_test_tarball("session-token-pkg", "1.1.0");
+    let (status, json) = publish_with_auth(app.clone(), Some(format!("Bearer {}", token)), tarball).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "revoked token must not publish");
+    assert!(json["error"].as_str().unwrap().contains("expired or revoked"));
+
+    // An id the user doesn't hold finds nothing
+    let (status, _) = session_request(&app, &cookie, Method::DELETE, &format!("/api/tokens/{}", uuid::Uuid::new_v4()), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_session_created_token_stops_working_once_expired() {
+    let pool = common::setup_database().await;
+    zorbs::db::find_or_create_user(&pool, "github", "tester", "tester", None, None).await.unwrap();
+    let app = common::build_test_app_with_login(pool.clone());
+    let cookie = common::session_cookie(&app, "tester").await;
+
+    let (status, created) = session_request(&app, &cookie, Method::POST, "/api/tokens", Some(serde_json::json!({
+        "name": "short-lived",
+        "scopes": ["publish-new"],
+        "expires_in_days": 1
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let token = created["token"].as_str().unwrap().to_string();
+
+    sqlx::query("UPDATE api_tokens SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(uuid::Uuid::parse_str(created["id"].as_str().unwrap()).unwrap())
+        .execute(&pool)
+        .await
+        .expect("expire token");
+
+    let tarball = common::create_test_tarball("expired-session-pkg", "1.0.0");
+    let (status, json) = publish_with_auth(app, Some(format!("Bearer {}", token)), tarball).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "expired token must not publish");
+    assert!(json["error"].as_str().unwrap().contains("expired or revoked"));
+}
@@ -9,9 +9,9 @@ use tower::ServiceExt;
 use serde_json::Value;
 // ─── Package name validation ────────────────────────────────────
 /// Helper: attempt to publish a tarball and return the response status + body.
-async fn try_publish(app: &mut axum::Router, tarball: Vec<u8>) -> (StatusCode, Vec<u8>) {
+async fn try_publish(app: &mut axum::Router, token: &str, tarball: Vec<u8>) -> (StatusCode, Vec<u8>) {
     let boundary = "val-boundary";
-    let body = build_multipart_body(boundary, &[
+    let body = common::build_multipart_body(boundary, &[
         ("file", "pkg.tar.gz", "application/octet-stream", &tarball),
     ]);
     let response = app
@@ -20,6 +20,7 @@ async fn try_publish(app: &mut axum::Router, tarball: Vec<u8>) -> (StatusCode, V
                 .method(Method::POST)
                 .uri("/api/zorbs/new")
                 .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::from(body))
                 .unwrap(),
         )
@@ -32,6 +33,7 @@ async fn try_publish(app: &mut axum::Router, tarball: Vec<u8>) -> (StatusCode, V
 #[tokio::test]
 async fn test_validate_valid_package_name_succeeds() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     // Valid names
     let valid_names = [
@@ -43,7 +45,7 @@ async fn test_validate_valid_package_name_succeeds() {
     ];
     for name in &valid_names {
         let tarball = common::create_test_tarball(name, "1.0.0");
-        let (status, _body) = try_publish(&mut app, tarball).await;
+        let (status, _body) = try_publish(&mut app, &token, tarball).await;
         assert_eq!(
             status,
             StatusCode::CREATED,
@@ -55,6 +57,7 @@ async fn test_validate_valid_package_name_succeeds() {
 #[tokio::test]
 async fn test_validate_invalid_package_name_fails() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     // Names with invalid characters
     let invalid_names = [
@@ -65,7 +68,7 @@ async fn test_validate_invalid_package_name_fails() {
     ];
     for name in &invalid_names {
         let tarball = common::create_test_tarball(name, "1.0.0");
-        let (status, body) = try_publish(&mut app, tarball).await;
+        let (status, body) = try_publish(&mut app, &token, tarball).await;
         // Should reject with either BAD_REQUEST (400) or possibly INTERNAL_SERVER_ERROR
         // The handler currently catches the validation error in parse_zorb_toml
         assert_ne!(
@@ -76,7 +79,7 @@ async fn test_validate_invalid_package_name_fails() {
         );
         let body_str = String::from_utf8_lossy(&body);
         assert!(
-            body_str.contains("Package name") || body_str.contains("error") || body_str.len() > 0,
+            body_str.contains("Package name") || body_str.contains("error") || !body_str.is_empty(),
             "Should return an error for invalid name '{}': {}",
             name,
             body_str
@@ -86,11 +89,12 @@ async fn test_validate_invalid_package_name_fails() {
 #[tokio::test]
 async fn test_validate_reserved_names_fail() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     let reserved = ["admin", "root", "system", "google", "facebook", "tesla", "nvidia", "intel"];
     for name in &reserved {
         let tarball = common::create_test_tarball(name, "1.0.0");
-        let (status, _body) = try_publish(&mut app, tarball).await;
+        let (status, _body) = try_publish(&mut app, &token, tarball).await;
         assert_ne!(
             status,
             StatusCode::CREATED,
@@ -102,11 +106,12 @@ async fn test_validate_reserved_names_fail() {
 #[tokio::test]
 async fn test_validate_reserved_scopes_fail() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     let reserved_scopes = ["@admin/pkg", "@root/pkg", "@system/pkg", "@google/pkg", "@nvidia/pkg"];
     for name in &reserved_scopes {
         let tarball = common::create_test_tarball(name, "1.0.0");
-        let (status, _body) = try_publish(&mut app, tarball).await;
+        let (status, _body) = try_publish(&mut app, &token, tarball).await;
         assert_ne!(
             status,
             StatusCode::CREATED,
@@ -118,12 +123,13 @@ async fn test_validate_reserved_scopes_fail() {
 #[tokio::test]
 async fn test_validate_scoped_name_format() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     // Valid scoped names
     let valid = ["@mypkg/my-lib", "@my_scope/my_pkg", "@my/pkg"];
     for name in &valid {
         let tarball = common::create_test_tarball(name, "1.0.0");
-        let (status, _body) = try_publish(&mut app, tarball).await;
+        let (status, _body) = try_publish(&mut app, &token, tarball).await;
         assert_eq!(
             status,
             StatusCode::CREATED,
@@ -135,12 +141,13 @@ async fn test_validate_scoped_name_format() {
 #[tokio::test]
 async fn test_validate_invalid_scoped_formats_fail() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     // Invalid scoped names
     let invalid = ["@/name", "@scope/name/extra", "@-h/name", "@h-/name"];
     for name in &invalid {
         let tarball = common::create_test_tarball(name, "1.0.0");
-        let (status, _body) = try_publish(&mut app, tarball).await;
+        let (status, _body) = try_publish(&mut app, &token, tarball).await;
         assert_ne!(
             status,
             StatusCode::CREATED,
@@ -152,11 +159,12 @@ async fn test_validate_invalid_scoped_formats_fail() {
 #[tokio::test]
 async fn test_validate_version_semver() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     let valid_versions = ["0.0.1", "1.0.0", "2.3.4", "10.20.30", "0.1.0-alpha", "1.0.0+build"];
     for ver in &valid_versions {
         let tarball = common::create_test_tarball("test-pkg", ver);
-        let (status, _body) = try_publish(&mut app, tarball).await;
+        let (status, _body) = try_publish(&mut app, &token, tarball).await;
         assert_eq!(
             status,
             StatusCode::CREATED,
@@ -168,11 +176,12 @@ async fn test_validate_version_semver() {
 #[tokio::test]
 async fn test_validate_invalid_version_fails() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     let invalid_versions = ["1.0", "v1.0.0", "latest", "1.0.0.0", "abc", "1", ""];
     for ver in &invalid_versions {
         let tarball = common::create_test_tarball("test-pkg", ver);
-        let (status, _body) = try_publish(&mut app, tarball).await;
+        let (status, _body) = try_publish(&mut app, &token, tarball).await;
         assert_ne!(
             status,
             StatusCode::CREATED,
@@ -184,12 +193,13 @@ async fn test_validate_invalid_version_fails() {
 #[tokio::test]
 async fn test_validate_blocked_words_fail() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     // Names containing blocked words
     let blocked = ["kill-switch", "my-nazi-pkg", "contains-ass-as-name"];
     for name in &blocked {
         let tarball = common::create_test_tarball(name, "1.0.0");
-        let (status, _body) = try_publish(&mut app, tarball).await;
+        let (status, _body) = try_publish(&mut app, &token, tarball).await;
         assert_ne!(
             status,
             StatusCode::CREATED,
@@ -201,6 +211,7 @@ async fn test_validate_blocked_words_fail() {
 #[tokio::test]
 async fn test_validate_missing_zorb_toml_fails() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
     let mut app = common::build_test_app_from_pool(pool);
     // Create a tar.gz WITHOUT a zorb.toml
     let mut buf = Vec::new();
@@ -220,7 +231,7 @@ async fn test_validate_missing_zorb_toml_fails() {
             .expect("append to tar");
         tar.finish().expect("finish tar");
     }
-    let (status, body) = try_publish(&mut app, buf).await;
+    let (status, body) = try_publish(&mut app, &token, buf).await;
     assert_eq!(status, StatusCode::BAD_REQUEST,
         "Tarball without zorb.toml should be rejected");
     let body_str = String::from_utf8_lossy(&body);
@@ -231,10 +242,13 @@ async fn test_validate_missing_zorb_toml_fails() {
 #[tokio::test]
 async fn test_package_with_dependencies() {
     let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    common::create_test_package(&pool, "@core/once_cell", "1.19.0").await;
+    common::create_test_package(&pool, "@data/serde", "1.0.0").await;
     let mut app = common::build_test_app_from_pool(pool);
     let deps = &[("@core/once_cell", "^1.0"), ("@data/serde", "^1.0")];
     let tarball = common::create_test_tarball_with_deps("my-app", "0.1.0", deps);
-    let (status, body) = try_publish(&mut app, tarball).await;
+    let (status, body) = try_publish(&mut app, &token, tarball).await;
     assert_eq!(status, StatusCode::CREATED,
         "Package with valid deps should publish");
     let json: Value = serde_json::from_slice(&body).unwrap();
@@ -262,26 +276,3 @@ async fn test_empty_database_returns_empty_trending() {
         "Homepage should render even with empty DB: {}",
         &html[..300]);
 }
-// ─── Multipart body builder ─────────────────────────────────────
-fn build_multipart_body(boundary: &str, fields: &[(&str, &str, &str, &[u8])]) -> Vec<u8> {
-    let mut body = Vec::new();
-    for (name, filename, content_type, data) in fields {
-        let mut header = String::new();
-        header += &format!("--{}\r\n", boundary);
-        header += &format!("Content-Disposition: form-data; name=\"{}\"", name);
-        if !filename.is_empty() {
-            header += &format!("; filename=\"{}\"", filename);
-        }
-        header += "\r\n";
-        if !content_type.is_empty() {
-            header += &format!("Content-Type: {}\r\n", content_type);
-        }
-        header += "\r\n";
-        body.extend_from_slice(header.as_bytes());
-        body.extend_from_slice(data);
-        body.extend_from_slice(b"\r\n");
-    }
-    let footer = format!("--{}--\r\n", boundary);
-    body.extend_from_slice(footer.as_bytes());
-    body
-}
@@ -0,0 +1,392 @@
+// Integration tests: Webhook registration, signed deliveries, retries and the delivery log
+
+mod common;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{HeaderMap, Method, Request, StatusCode},
+    routing::post,
+    Router,
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use zorbs::db::queries;
+use zorbs::webhooks;
+
+/// A local HTTP endpoint that records every delivery and answers with the
+/// queued status codes, then 200.
+#[derive(Clone, Default)]
+struct Receiver {
+    received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+    answers: Arc<Mutex<VecDeque<StatusCode>>>,
+}
+
+/// Receivers listen on loopback, which only an allowlist lets webhooks reach.
+fn allow_local_receivers() {
+    std::env::set_var("WEBHOOK_ALLOWED_HOSTS", "127.0.0.1");
+}
+
+impl Receiver {
+    async fn start() -> (Receiver, String) {
+        allow_local_receivers();
+        let receiver = Receiver::default();
+        let app = Router::new()
+            .route("/hook", post(|State(r): State<Receiver>, headers: HeaderMap, body: Bytes| async move {
+                r.received.lock().unwrap().push((headers, body));
+                r.answers.lock().unwrap().pop_front().unwrap_or(StatusCode::OK)
+            }))
+            .with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (receiver, url)
+    }
+
+    fn answer(&self, status: StatusCode) {
+        self.answers.lock().unwrap().push_back(status);
+    }
+
+    /// Event name and JSON body of every delivery received so far.
+    fn events(&self) -> Vec<(String, Value)> {
+        self.received.lock().unwrap().iter()
+            .map(|(headers, body)| (
+                headers[webhooks::EVENT_HEADER].to_str().unwrap().to_string(),
+                serde_json::from_slice(body).unwrap(),
+            ))
+            .collect()
+    }
+}
+
+async fn call(pool: &sqlx::PgPool, method: Method, uri: &str, token: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("authorization", format!("Bearer {}", token));
+    let body = match body {
+        Some(json) => {
+            request = request.header("content-type", "application/json");
+            Body::from(json.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn publish(pool: &sqlx::PgPool, token: &str, name: &str, version: &str) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let boundary = "webhook-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", &common::create_test_tarball(name, version)),
+    ]);
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/zorbs/new")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED, "publish {}@{}", name, version);
+}
+
+/// Make every pending delivery due now, as if its backoff had elapsed.
+async fn skip_backoff(pool: &sqlx::PgPool) {
+    sqlx::query("UPDATE webhook_deliveries SET next_attempt_at = NOW() WHERE status = 'pending'")
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_published_event_is_delivered_and_signed() {
+    let pool = common::setup_database().await;
+    let (receiver, url) = Receiver::start().await;
+    let token = common::create_test_token(&pool).await;
+    publish(&pool, &token, "hooked", "1.0.0").await;
+
+    let (status, hook) = call(&pool, Method::POST, "/api/zorbs/hooked/webhooks", &token, Some(json!({
+        "url": url, "events": ["published"],
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", hook);
+    let secret = hook["secret"].as_str().unwrap().to_string();
+    assert!(secret.starts_with("whsec_"));
+
+    publish(&pool, &token, "hooked", "1.1.0").await;
+    let (status, _) = call(&pool, Method::PUT, "/api/zorbs/hooked/1.0.0/yank", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let client = webhooks::client();
+    assert_eq!(webhooks::deliver_due(&pool, &client).await.unwrap(), 1, "Only the subscribed event is queued");
+    let received = receiver.received.lock().unwrap().clone();
+    let (headers, body) = &received[0];
+    assert_eq!(headers[webhooks::EVENT_HEADER], "published");
+    assert_eq!(headers[webhooks::SIGNATURE_HEADER].to_str().unwrap(), webhooks::sign(&secret, body));
+    assert!(headers[webhooks::SIGNATURE_HEADER].to_str().unwrap().starts_with("sha256="));
+    let payload: Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(payload["event"], "published");
+    assert_eq!(payload["package"], "hooked");
+    assert_eq!(payload["version"], "1.1.0");
+
+    // Nothing is sent twice
+    assert_eq!(webhooks::deliver_due(&pool, &client).await.unwrap(), 0);
+
+    let (status, log) = call(&pool, Method::GET, &format!("/api/zorbs/hooked/webhooks/{}/deliveries", hook["id"].as_str().unwrap()), &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let entry = &log["deliveries"][0];
+    assert_eq!(entry["status"], "delivered");
+    assert_eq!(entry["attempts"], 1);
+    assert_eq!(entry["last_status_code"], 200);
+    assert_eq!(entry["id"].as_str().unwrap(), headers[webhooks::DELIVERY_HEADER].to_str().unwrap());
+
+    let (_, list) = call(&pool, Method::GET, "/api/zorbs/hooked/webhooks", &token, None).await;
+    assert_eq!(list["webhooks"][0]["events"], json!(["published"]));
+    assert!(list["webhooks"][0].get("secret").is_none(), "The secret is only shown at creation");
+}
+
+#[tokio::test]
+async fn test_failed_deliveries_are_retried_with_backoff() {
+    let pool = common::setup_database().await;
+    let (receiver, url) = Receiver::start().await;
+    let token = common::create_test_token(&pool).await;
+    publish(&pool, &token, "flaky", "1.0.0").await;
+    let (_, hook) = call(&pool, Method::POST, "/api/zorbs/flaky/webhooks", &token, Some(json!({"url": url}))).await;
+    assert_eq!(hook["events"], json!(["published", "yanked", "ownership_changed"]));
+    let log_uri = format!("/api/zorbs/flaky/webhooks/{}/deliveries", hook["id"].as_str().unwrap());
+
+    receiver.answer(StatusCode::INTERNAL_SERVER_ERROR);
+    call(&pool, Method::PUT, "/api/zorbs/flaky/1.0.0/yank", &token, None).await;
+    let client = webhooks::client();
+    assert_eq!(webhooks::deliver_due(&pool, &client).await.unwrap(), 1);
+
+    let (_, log) = call(&pool, Method::GET, &log_uri, &token, None).await;
+    let entry = &log["deliveries"][0];
+    assert_eq!(entry["status"], "pending");
+    assert_eq!(entry["attempts"], 1);
+    assert_eq!(entry["last_status_code"], 500);
+    assert_eq!(entry["last_error"], "Receiver answered 500 Internal Server Error");
+    let wait: f64 = sqlx::query_scalar("SELECT EXTRACT(EPOCH FROM next_attempt_at - NOW())::FLOAT8 FROM webhook_deliveries")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!((25.0..=30.0).contains(&wait), "First retry after 30s, got {}", wait);
+    assert_eq!(webhooks::deliver_due(&pool, &client).await.unwrap(), 0, "Not due during the backoff");
+
+    skip_backoff(&pool).await;
+    assert_eq!(webhooks::deliver_due(&pool, &client).await.unwrap(), 1);
+    let (_, log) = call(&pool, Method::GET, &log_uri, &token, None).await;
+    assert_eq!(log["deliveries"][0]["status"], "delivered");
+    assert_eq!(log["deliveries"][0]["attempts"], 2);
+    let bodies: Vec<Value> = receiver.events().into_iter().map(|(_, body)| body).collect();
+    assert_eq!(bodies[0], bodies[1], "A retry resends the same payload");
+    assert_eq!(bodies[0]["yanked"], true);
+
+    assert_eq!(webhooks::retry_delay(2).as_secs(), 60);
+    assert_eq!(webhooks::retry_delay(7).as_secs(), 30 * 64);
+}
+
+#[tokio::test]
+async fn test_deliveries_fail_after_the_last_attempt() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    publish(&pool, &token, "gone", "1.0.0").await;
+    allow_local_receivers();
+    // Nothing listens on this port once the listener is dropped
+    let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+    let (_, hook) = call(&pool, Method::POST, "/api/zorbs/gone/webhooks", &token, Some(json!({
+        "url": format!("http://{}/hook", closed), "events": ["yanked"],
+    }))).await;
+    let (status, _) = call(&pool, Method::PUT, "/api/zorbs/gone/1.0.0/yank", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let client = webhooks::client();
+    for _ in 0..webhooks::MAX_ATTEMPTS {
+        skip_backoff(&pool).await;
+        assert_eq!(webhooks::deliver_due(&pool, &client).await.unwrap(), 1);
+    }
+    skip_backoff(&pool).await;
+    assert_eq!(webhooks::deliver_due(&pool, &client).await.unwrap(), 0);
+
+    let (_, log) = call(&pool, Method::GET, &format!("/api/zorbs/gone/webhooks/{}/deliveries", hook["id"].as_str().unwrap()), &token, None).await;
+    let entry = &log["deliveries"][0];
+    assert_eq!(entry["status"], "failed");
+    assert_eq!(entry["attempts"], webhooks::MAX_ATTEMPTS);
+    assert_eq!(entry["last_status_code"], Value::Null);
+    assert!(entry["last_error"].as_str().is_some_and(|e| !e.is_empty()));
+    assert_eq!(entry["payload"]["version"], "1.0.0");
+}
+
+#[tokio::test]
+async fn test_redirects_are_not_followed() {
+    let pool = common::setup_database().await;
+    let (receiver, url) = Receiver::start().await;
+    let app = Router::new().route("/moved", post(move || {
+        let url = url.clone();
+        async move { (StatusCode::TEMPORARY_REDIRECT, [("location", url)]) }
+    }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let moved = format!("http://{}/moved", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let token = common::create_test_token(&pool).await;
+    publish(&pool, &token, "redirected", "1.0.0").await;
+    let (_, hook) = call(&pool, Method::POST, "/api/zorbs/redirected/webhooks", &token, Some(json!({"url": moved}))).await;
+
+    publish(&pool, &token, "redirected", "1.1.0").await;
+    assert_eq!(webhooks::deliver_due(&pool, &webhooks::client()).await.unwrap(), 1);
+    assert!(receiver.events().is_empty(), "The redirect target is never called");
+    let (_, log) = call(&pool, Method::GET, &format!("/api/zorbs/redirected/webhooks/{}/deliveries", hook["id"].as_str().unwrap()), &token, None).await;
+    assert_eq!(log["deliveries"][0]["status"], "pending");
+    assert_eq!(log["deliveries"][0]["last_status_code"], 307);
+}
+
+#[tokio::test]
+async fn test_slow_receivers_do_not_outlast_the_lease() {
+    let pool = common::setup_database().await;
+    allow_local_receivers();
+    let calls = Arc::new(Mutex::new(0));
+    let app = Router::new().route("/slow", post({
+        let calls = calls.clone();
+        move || async move {
+            *calls.lock().unwrap() += 1;
+            tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+            StatusCode::OK
+        }
+    }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let slow = format!("http://{}/slow", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let token = common::create_test_token(&pool).await;
+    publish(&pool, &token, "sluggish", "1.0.0").await;
+    for _ in 0..4 {
+        call(&pool, Method::POST, "/api/zorbs/sluggish/webhooks", &token, Some(json!({"url": slow}))).await;
+    }
+    publish(&pool, &token, "sluggish", "1.1.0").await;
+
+    // Sent one after another, the batch would take 12s against a 2s lease
+    let lease = std::time::Duration::from_secs(2);
+    let started = std::time::Instant::now();
+    assert_eq!(webhooks::deliver_due_within(&pool, &webhooks::client(), lease).await.unwrap(), 4);
+    assert!(started.elapsed() < lease, "The batch finished within its lease: {:?}", started.elapsed());
+    tokio::time::sleep(lease).await;
+    assert_eq!(webhooks::deliver_due_within(&pool, &webhooks::client(), lease).await.unwrap(), 0, "Nothing is claimed twice");
+    assert_eq!(*calls.lock().unwrap(), 4);
+
+    let attempts: Vec<(i32, Option<String>)> = sqlx::query_as("SELECT attempts, last_error FROM webhook_deliveries")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert!(attempts.iter().all(|(n, error)| *n == 1 && error.as_deref() == Some("No answer within 1s")), "{:?}", attempts);
+}
+
+#[tokio::test]
+async fn test_admins_register_registry_wide_webhooks() {
+    let pool = common::setup_database().await;
+    let (receiver, url) = Receiver::start().await;
+    let owner = common::create_test_token(&pool).await;
+    let admin = common::create_test_token_with_scopes(&pool, "admin", &["publish-update"]).await;
+    publish(&pool, &owner, "tracked", "1.0.0").await;
+
+    let (status, _) = call(&pool, Method::POST, "/api/webhooks", &admin, Some(json!({"url": url}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(queries::set_admin(&pool, "admin", None, true).await.unwrap(), ["github"]);
+    let (status, hook) = call(&pool, Method::POST, "/api/webhooks", &admin, Some(json!({
+        "url": url, "events": ["ownership_changed", "yanked", "published"],
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", hook);
+    assert_eq!(hook["package"], Value::Null);
+
+    let helper = common::create_test_token_with_scopes(&pool, "helper", &["publish-update"]).await;
+    let helper_id = common::test_user_id(&pool, "helper").await;
+    call(&pool, Method::PUT, "/api/zorbs/tracked/owners", &owner, Some(json!({"user_id": helper_id}))).await;
+    call(&pool, Method::DELETE, &format!("/api/zorbs/tracked/owners/{}", helper_id), &owner, None).await;
+    call(&pool, Method::PUT, "/api/zorbs/tracked/owners", &helper, Some(json!({"user_id": helper_id}))).await;
+    call(&pool, Method::PUT, "/api/zorbs/tracked/1.0.0/yank", &owner, None).await;
+    call(&pool, Method::DELETE, "/api/zorbs/tracked/1.0.0/yank", &owner, None).await;
+    publish(&pool, &owner, "other", "1.0.0").await;
+
+    assert_eq!(webhooks::deliver_due(&pool, &webhooks::client()).await.unwrap(), 5);
+    let mut events: Vec<(String, Value)> = receiver.events();
+    events.sort_by_key(|(_, body)| body["occurred_at"].as_str().unwrap().to_string());
+    let summary: Vec<String> = events.iter()
+        .map(|(event, body)| format!("{} {} {}", event, body["package"].as_str().unwrap(), body.get("action").or(body.get("yanked")).unwrap_or(&Value::Null)))
+        .collect();
+    assert_eq!(summary, [
+        "ownership_changed tracked \"added\"",
+        "ownership_changed tracked \"removed\"",
+        "yanked tracked true",
+        "yanked tracked false",
+        "published other null",
+    ], "The removed helper could not add themselves back");
+
+    let (status, log) = call(&pool, Method::GET, &format!("/api/webhooks/{}/deliveries", hook["id"].as_str().unwrap()), &admin, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(log["deliveries"].as_array().unwrap().len(), 5);
+    let (status, _) = call(&pool, Method::GET, "/api/webhooks", &owner, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "Only admins see registry-wide hooks");
+}
+
+#[tokio::test]
+async fn test_admin_rights_need_an_unambiguous_account() {
+    let pool = common::setup_database().await;
+    assert!(queries::set_admin(&pool, "nobody", None, true).await.unwrap().is_empty());
+    common::create_test_token_with_scopes(&pool, "shared", &["publish-update"]).await;
+    let google = zorbs::db::find_or_create_user(&pool, "google", "g-shared", "shared", None, None).await.unwrap();
+
+    assert_eq!(queries::set_admin(&pool, "shared", None, true).await.unwrap(), ["github", "google"]);
+    assert!(!queries::is_admin(&pool, google.id).await.unwrap(), "Nothing changes while the name is ambiguous");
+    assert_eq!(queries::set_admin(&pool, "shared", Some("google"), true).await.unwrap(), ["google"]);
+    assert!(queries::is_admin(&pool, google.id).await.unwrap());
+    assert!(!queries::is_admin(&pool, common::test_user_id(&pool, "shared").await).await.unwrap());
+    queries::set_admin(&pool, "shared", Some("google"), false).await.unwrap();
+    assert!(!queries::is_admin(&pool, google.id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_webhook_management_is_checked() {
+    let pool = common::setup_database().await;
+    let owner = common::create_test_token(&pool).await;
+    let stranger = common::create_test_token_with_scopes(&pool, "stranger", &["publish-new", "publish-update"]).await;
+    let yank_only = common::create_test_token_with_scopes(&pool, "tester", &["yank"]).await;
+    publish(&pool, &owner, "@team/guarded", "1.0.0").await;
+    publish(&pool, &stranger, "elsewhere", "1.0.0").await;
+    let hooks = "/api/zorbs/@team/guarded/webhooks";
+    // An address rather than a name, so registering does not depend on DNS
+    let valid = json!({"url": "https://93.184.215.14/zorbs"});
+
+    let (status, _) = call(&pool, Method::POST, hooks, &stranger, Some(valid.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = call(&pool, Method::POST, hooks, &yank_only, Some(valid.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    for (body, error) in [
+        (json!({"url": "ftp://ci.example.com"}), "Webhook URL 'ftp://ci.example.com' must be an http or https URL"),
+        (json!({"url": "not a url"}), "Invalid webhook URL 'not a url': relative URL without a base"),
+        (json!({"url": "https://ci.example.com", "events": []}), "A webhook needs at least one event"),
+        (json!({"url": "https://ci.example.com", "events": ["downloaded"]}),
+            "Unknown event 'downloaded'; expected one of: published, yanked, ownership_changed"),
+        (json!({"url": "https://ci.example.com", "events": ["deprecated"]}),
+            "Unknown event 'deprecated'; expected one of: published, yanked, ownership_changed"),
+    ] {
+        let (status, json) = call(&pool, Method::POST, hooks, &owner, Some(body)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"], error);
+    }
+
+    let (status, hook) = call(&pool, Method::POST, hooks, &owner, Some(valid)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = hook["id"].as_str().unwrap();
+    let (status, _) = call(&pool, Method::GET, &format!("/api/zorbs/elsewhere/webhooks/{}/deliveries", id), &stranger, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "Another package's webhook is not found");
+    let (status, _) = call(&pool, Method::DELETE, &format!("/api/zorbs/elsewhere/webhooks/{}", id), &stranger, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = call(&pool, Method::DELETE, &format!("{}/{}", hooks, id), &owner, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, list) = call(&pool, Method::GET, hooks, &owner, None).await;
+    assert_eq!(list["webhooks"], json!([]));
+}
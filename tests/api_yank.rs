@@ -0,0 +1,149 @@
+// Integration tests: Yanking and un-yanking versions
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::Value;
+
+/// Helper: publish a tarball as the holder of `token`.
+async fn publish_as(pool: &sqlx::PgPool, token: &str, name: &str, version: &str) -> StatusCode {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let tarball = common::create_test_tarball(name, version);
+    let boundary = "yank-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", &tarball),
+    ]);
+    app.oneshot(
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/zorbs/new")
+            .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::from(body))
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+    .status()
+}
+
+/// Helper: PUT (yank) or DELETE (un-yank) `/api/zorbs/{name}/{version}/yank`.
+async fn yank_request(pool: &sqlx::PgPool, method: Method, name: &str, version: &str, token: Option<&str>) -> StatusCode {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(format!("/api/zorbs/{}/{}/yank", name, version));
+    if let Some(token) = token {
+        builder = builder.header("authorization", format!("Bearer {}", token));
+    }
+    app.oneshot(builder.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn resolve(pool: &sqlx::PgPool, name: &str) -> Value {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/resolve?name={}", name))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_yank_skips_version_in_resolve() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    assert_eq!(publish_as(&pool, &token, "yank-pkg", "1.0.0").await, StatusCode::CREATED);
+    assert_eq!(publish_as(&pool, &token, "yank-pkg", "1.1.0").await, StatusCode::CREATED);
+    assert_eq!(resolve(&pool, "yank-pkg").await["version"], "1.1.0");
+
+    assert_eq!(yank_request(&pool, Method::PUT, "yank-pkg", "1.1.0", Some(&token)).await, StatusCode::OK);
+    assert_eq!(resolve(&pool, "yank-pkg").await["version"], "1.0.0",
+        "Resolve should skip the yanked version");
+
+    assert_eq!(yank_request(&pool, Method::DELETE, "yank-pkg", "1.1.0", Some(&token)).await, StatusCode::OK);
+    assert_eq!(resolve(&pool, "yank-pkg").await["version"], "1.1.0",
+        "Un-yanked version should resolve again");
+}
+
+#[tokio::test]
+async fn test_scoped_yank_when_all_versions_yanked() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    assert_eq!(publish_as(&pool, &token, "@alice/yanky", "0.1.0").await, StatusCode::CREATED);
+    assert_eq!(yank_request(&pool, Method::PUT, "@alice/yanky", "0.1.0", Some(&token)).await, StatusCode::OK);
+
+    let json = resolve(&pool, "@alice/yanky").await;
+    assert!(json["error"].is_string(), "Fully yanked package should not resolve: {}", json);
+}
+
+#[tokio::test]
+async fn test_yank_requires_owner_and_scope() {
+    let pool = common::setup_database().await;
+    let owner = common::create_test_token(&pool).await;
+    let stranger = common::create_test_token_with_scopes(&pool, "mallory", &["publish-new", "publish-update", "yank"]).await;
+    let no_yank = common::create_test_token_with_scopes(&pool, "tester", &["publish-new", "publish-update"]).await;
+
+    assert_eq!(publish_as(&pool, &owner, "guarded-pkg", "1.0.0").await, StatusCode::CREATED);
+
+    assert_eq!(yank_request(&pool, Method::PUT, "guarded-pkg", "1.0.0", None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(yank_request(&pool, Method::PUT, "guarded-pkg", "1.0.0", Some(&stranger)).await, StatusCode::FORBIDDEN);
+    assert_eq!(yank_request(&pool, Method::PUT, "guarded-pkg", "1.0.0", Some(&no_yank)).await, StatusCode::FORBIDDEN);
+    assert_eq!(yank_request(&pool, Method::PUT, "guarded-pkg", "9.9.9", Some(&owner)).await, StatusCode::NOT_FOUND);
+
+    let yanked: (bool,) = sqlx::query_as("SELECT yanked FROM zorbs WHERE name = 'guarded-pkg' AND version = '1.0.0'")
+        .fetch_one(&pool)
+        .await
+        .expect("fetch zorb");
+    assert!(!yanked.0, "Rejected requests must not yank");
+}
+
+#[tokio::test]
+async fn test_yanked_version_still_downloadable_and_badged() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+
+    assert_eq!(publish_as(&pool, &token, "locked-pkg", "1.0.0").await, StatusCode::CREATED);
+    assert_eq!(publish_as(&pool, &token, "locked-pkg", "1.0.1").await, StatusCode::CREATED);
+    assert_eq!(yank_request(&pool, Method::PUT, "locked-pkg", "1.0.1", Some(&token)).await, StatusCode::OK);
+
+    // Lockfiles pin exact versions, so the download route keeps serving yanked ones
+    let state = common::build_test_state(pool.clone());
+    let app = zorbs::build_app(state.clone());
+    let response = app
+        .oneshot(Request::builder().uri("/locked-pkg/1.0.1/download").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_ne!(response.status(), StatusCode::NOT_FOUND);
+    state.downloads.flush(&pool).await.expect("flush downloads");
+    let downloads: (i64,) = sqlx::query_as("SELECT downloads FROM zorbs WHERE name = 'locked-pkg' AND version = '1.0.1'")
+        .fetch_one(&pool)
+        .await
+        .expect("fetch downloads");
+    assert_eq!(downloads.0, 1);
+
+    let app = common::build_test_app_from_pool(pool.clone());
+    let response = app
+        .oneshot(Request::builder().uri("/locked-pkg").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let html = String::from_utf8_lossy(&body);
+    assert!(html.contains("yanked"), "Version history should badge yanked versions");
+}
@@ -0,0 +1,302 @@
+// Integration tests: A corpus of malicious and malformed archives for upload validation
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, Method},
+};
+use http_body_util::BodyExt;
+use serde_json::Value;
+use std::io::Write;
+use tower::ServiceExt;
+use zorbs::archive::{inspect, read_file, ArchiveError, Limits};
+use zorbs::utils::parse_zorb_toml;
+
+const MANIFEST: &[u8] = b"[package]\nname = \"corpus\"\nversion = \"1.0.0\"\nlicense = \"MIT\"\n";
+
+/// One tar entry written byte for byte, so paths the `tar` crate refuses to
+/// build (`..`, absolute, non-UTF-8) can still be produced.
+struct Raw<'a> {
+    path: &'a [u8],
+    kind: tar::EntryType,
+    link: &'a [u8],
+    data: &'a [u8],
+}
+
+fn file<'a>(path: &'a str, data: &'a [u8]) -> Raw<'a> {
+    Raw { path: path.as_bytes(), kind: tar::EntryType::Regular, link: b"", data }
+}
+
+fn special<'a>(path: &'a str, kind: tar::EntryType, link: &'a str) -> Raw<'a> {
+    Raw { path: path.as_bytes(), kind, link: link.as_bytes(), data: b"" }
+}
+
+fn tar_bytes(entries: &[Raw]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        {
+            let old = header.as_old_mut();
+            old.name[..entry.path.len()].copy_from_slice(entry.path);
+            old.linkname[..entry.link.len()].copy_from_slice(entry.link);
+        }
+        header.set_entry_type(entry.kind);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_size(entry.data.len() as u64);
+        header.set_cksum();
+        out.extend_from_slice(header.as_bytes());
+        out.extend_from_slice(entry.data);
+        out.resize(out.len().div_ceil(512) * 512, 0);
+    }
+    out.extend_from_slice(&[0; 1024]);
+    out
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    gz.write_all(data).unwrap();
+    gz.finish().unwrap()
+}
+
+/// A `.zorb` with a valid manifest first, then `entries`.
+fn archive(entries: &[Raw]) -> Vec<u8> {
+    let mut all = vec![file("zorb.toml", MANIFEST)];
+    all.extend(entries.iter().map(|e| Raw { path: e.path, kind: e.kind, link: e.link, data: e.data }));
+    gzip(&tar_bytes(&all))
+}
+
+fn rejection(entries: &[Raw]) -> ArchiveError {
+    inspect(&archive(entries), &Limits::default()).expect_err("archive should be rejected")
+}
+
+#[test]
+fn test_links_and_special_files_are_rejected() {
+    use tar::EntryType;
+    for (entry, kind) in [
+        (special("evil", EntryType::Symlink, "/etc/passwd"), "symbolic link"),
+        (special("src/up", EntryType::Symlink, "../../.."), "symbolic link"),
+        (special("hard", EntryType::Link, "zorb.toml"), "hard link"),
+        (special("tty", EntryType::Char, ""), "character device"),
+        (special("disk", EntryType::Block, ""), "block device"),
+        (special("pipe", EntryType::Fifo, ""), "FIFO"),
+    ] {
+        let path = String::from_utf8(entry.path.to_vec()).unwrap();
+        assert_eq!(rejection(&[entry]), ArchiveError::ForbiddenEntry { path, kind });
+    }
+}
+
+#[test]
+fn test_paths_that_escape_are_rejected() {
+    for (path, reason) in [
+        ("../escape", "climbs out of the package with '..'"),
+        ("src/../../escape", "climbs out of the package with '..'"),
+        ("src/..", "climbs out of the package with '..'"),
+        ("/etc/passwd", "is absolute"),
+        ("C:/Windows/evil.dll", "starts with a drive letter"),
+        ("c:evil", "starts with a drive letter"),
+        ("C:\\Windows\\evil.dll", "uses backslashes"),
+        ("src\\..\\..\\evil", "uses backslashes"),
+        ("\\\\server\\share\\evil", "uses backslashes"),
+        ("evil\nname", "contains control characters"),
+    ] {
+        assert_eq!(
+            rejection(&[file(path, b"x")]),
+            ArchiveError::InvalidPath { path: path.to_string(), reason },
+            "{:?}", path
+        );
+    }
+
+    let invalid_utf8 = Raw { path: b"caf\xe9.z", kind: tar::EntryType::Regular, link: b"", data: b"x" };
+    assert!(matches!(rejection(&[invalid_utf8]), ArchiveError::InvalidPath { reason: "is not valid UTF-8", .. }));
+}
+
+#[test]
+fn test_collisions_are_rejected() {
+    assert_eq!(
+        rejection(&[file("src/lib.z", b"one"), file("./src/lib.z", b"two")]),
+        ArchiveError::Duplicate("src/lib.z".to_string())
+    );
+    assert_eq!(
+        rejection(&[file("README.md", b"one"), file("readme.md", b"two")]),
+        ArchiveError::CaseCollision { first: "README.md".to_string(), second: "readme.md".to_string() }
+    );
+    assert_eq!(
+        rejection(&[file("Src/a.z", b"a"), file("src/b.z", b"b")]),
+        ArchiveError::CaseCollision { first: "Src".to_string(), second: "src".to_string() }
+    );
+    assert_eq!(
+        rejection(&[file("src", b"a file"), file("src/lib.z", b"x")]),
+        ArchiveError::FileConflict { file: "src".to_string(), path: "src/lib.z".to_string() }
+    );
+    assert!(matches!(
+        rejection(&[file("src/lib.z", b"x"), file("src", b"a file")]),
+        ArchiveError::FileConflict { .. }
+    ));
+}
+
+#[test]
+fn test_limits_are_enforced() {
+    let small = Limits { max_unpacked_size: 64 * 1024, max_entries: 5, max_path_len: 32 };
+
+    // Highly compressible: a few hundred bytes that unpack past the limit
+    let zeros = vec![0u8; 1024 * 1024];
+    let bomb = archive(&[file("zeros.bin", &zeros)]);
+    assert!(bomb.len() < 8 * 1024);
+    assert_eq!(inspect(&bomb, &small), Err(ArchiveError::TooLarge { limit: 64 * 1024 }));
+
+    let names: Vec<String> = (0..10).map(|i| format!("f{}.z", i)).collect();
+    let flood: Vec<Raw> = names.iter().map(|n| file(n, b"")).collect();
+    assert_eq!(inspect(&archive(&flood), &small), Err(ArchiveError::TooManyEntries { limit: 5 }));
+
+    let long = format!("src/{}.z", "a".repeat(40));
+    assert!(matches!(
+        inspect(&archive(&[file(&long, b"x")]), &small),
+        Err(ArchiveError::PathTooLong { limit: 32, .. })
+    ));
+
+    // The default path limit also applies to GNU long names
+    let mut tar = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(1);
+    header.set_mode(0o644);
+    tar.append_data(&mut header, "d/".repeat(200) + "x.z", &b"x"[..]).unwrap();
+    let long_name = gzip(&tar.into_inner().unwrap());
+    assert!(matches!(inspect(&long_name, &Limits::default()), Err(ArchiveError::PathTooLong { limit: 256, .. })));
+}
+
+#[test]
+fn test_corrupt_archives_are_rejected() {
+    let valid = archive(&[file("src/lib.z", b"pub fn f() {}\n")]);
+    let truncated = &valid[..valid.len() / 2];
+    assert!(matches!(inspect(truncated, &Limits::default()), Err(ArchiveError::Corrupt(_))));
+    assert!(matches!(inspect(b"not a gzip stream", &Limits::default()), Err(ArchiveError::Corrupt(_))));
+
+    // A header whose checksum does not match used to be skipped silently
+    let mut tar = tar_bytes(&[file("zorb.toml", MANIFEST), file("src/lib.z", b"x")]);
+    tar[1024 + 148] ^= 0x01;
+    assert!(matches!(inspect(&gzip(&tar), &Limits::default()), Err(ArchiveError::Corrupt(_))));
+}
+
+#[test]
+fn test_ordinary_archives_are_accepted() {
+    let dir = Raw { path: b"./src/", kind: tar::EntryType::Directory, link: b"", data: b"" };
+    let root = Raw { path: b"./", kind: tar::EntryType::Directory, link: b"", data: b"" };
+    let files = inspect(&archive(&[root, dir, file("./src/lib.z", b"x"), file("README.md", b"# hi")]), &Limits::default())
+        .expect("valid archive");
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["README.md", "src/lib.z", "zorb.toml"]);
+}
+
+#[test]
+fn test_pax_global_headers_are_skipped() {
+    // What `git archive` puts first: the commit id, for the archive as a whole
+    let global = Raw {
+        path: b"pax_global_header",
+        kind: tar::EntryType::XGlobalHeader,
+        link: b"",
+        data: b"52 comment=0123456789abcdef0123456789abcdef01234567\n",
+    };
+    let bytes = gzip(&tar_bytes(&[global, file("zorb.toml", MANIFEST), file("src/lib.z", b"x")]));
+    let files = inspect(&bytes, &Limits::default()).expect("git archive output is valid");
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["src/lib.z", "zorb.toml"]);
+    assert_eq!(read_file(&bytes, "pax_global_header").unwrap(), None);
+}
+
+#[test]
+fn test_every_listed_file_can_be_read() {
+    let contiguous = Raw { path: b"src/big.z", kind: tar::EntryType::Continuous, link: b"", data: b"contiguous" };
+    let bytes = archive(&[contiguous, file("src/lib.z", b"regular")]);
+    let files = inspect(&bytes, &Limits::default()).expect("valid archive");
+    for entry in &files {
+        let content = read_file(&bytes, &entry.path).unwrap();
+        assert_eq!(content.map(|c| c.len() as i64), Some(entry.size), "{} is listed but unreadable", entry.path);
+    }
+    assert_eq!(read_file(&bytes, "src/big.z").unwrap().as_deref(), Some(&b"contiguous"[..]));
+}
+
+/// Deterministic xorshift, so failures reproduce.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[test]
+fn test_mutated_archives_never_panic() {
+    let tar = tar_bytes(&[
+        file("zorb.toml", MANIFEST),
+        file("README.md", b"# corpus\n"),
+        file("src/lib.z", b"pub fn answer() -> i32 { 42 }\n"),
+    ]);
+    let gz = gzip(&tar);
+    let mut rng = Rng(0x5eed_1234_abcd_ef01);
+    for round in 0..400 {
+        // Alternate between corrupting the compressed stream and the tar inside it
+        let mutated = if round % 2 == 0 {
+            let mut bytes = gz.clone();
+            for _ in 0..1 + rng.below(8) {
+                let at = rng.below(bytes.len());
+                bytes[at] = rng.next() as u8;
+            }
+            bytes
+        } else {
+            let mut bytes = tar.clone();
+            for _ in 0..1 + rng.below(8) {
+                let at = rng.below(bytes.len());
+                bytes[at] = rng.next() as u8;
+            }
+            if rng.below(4) == 0 {
+                bytes.truncate(rng.below(bytes.len()));
+            }
+            gzip(&bytes)
+        };
+        // Any outcome is fine as long as it is an answer rather than a panic or a hang
+        let _ = inspect(&mutated, &Limits::default());
+        let _ = parse_zorb_toml(&mutated);
+    }
+}
+
+#[tokio::test]
+async fn test_publish_reports_the_rejection() {
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    let tarball = archive(&[special("src/link", tar::EntryType::Symlink, "/etc/shadow")]);
+
+    let boundary = "corpus-boundary";
+    let body = common::build_multipart_body(boundary, &[
+        ("file", "pkg.tar.gz", "application/octet-stream", &tarball),
+    ]);
+    let response = common::build_test_app_from_pool(pool.clone())
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/zorbs/new")
+                .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let json: Value = serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(
+        json["error"],
+        "'src/link' in archive is a symbolic link; only regular files and directories are allowed"
+    );
+
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM zorbs").fetch_one(&pool).await.unwrap();
+    assert_eq!(stored, 0);
+}
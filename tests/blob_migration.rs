@@ -0,0 +1,110 @@
+// Integration tests: Moving archives from name-derived keys to content-addressed keys
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use tower::ServiceExt;
+use zorbs::blobs::{migrate_legacy_blobs, LegacyBlobReport};
+use zorbs::utils::{blob_key, sha256_hex, zorb_filename};
+
+/// Seed a version published before content addressing: queued for migration,
+/// with its archive (if any) at the old name-derived key.
+async fn seed_legacy(pool: &sqlx::PgPool, name: &str, checksum: Option<&str>) {
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, checksum, created_at, updated_at)
+         VALUES ($1, $2, '1.0.0', 'Legacy', 'MIT', NULL, '{}'::jsonb, $3, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(name)
+    .bind(checksum)
+    .execute(pool)
+    .await
+    .expect("seed version");
+    sqlx::query("INSERT INTO legacy_blobs (name, version) VALUES ($1, '1.0.0')")
+        .bind(name)
+        .execute(pool)
+        .await
+        .expect("queue legacy blob");
+}
+
+#[tokio::test]
+async fn test_legacy_blobs_are_moved_to_digest_keys() {
+    let pool = common::setup_database().await;
+    let storage = common::test_storage();
+
+    seed_legacy(&pool, "@mig/plain", Some(&sha256_hex(b"plain"))).await;
+    common::store_raw_blob(&zorb_filename("@mig/plain", "1.0.0"), b"plain").await;
+    // Published before checksums were recorded: the archive's zorb.toml vouches for it
+    let unchecked = common::create_test_tarball("mig-unchecked", "1.0.0");
+    seed_legacy(&pool, "mig-unchecked", None).await;
+    common::store_raw_blob(&zorb_filename("mig-unchecked", "1.0.0"), &unchecked).await;
+    // No checksums and a shared old key: the archive names only one of them
+    let winner = common::create_test_tarball("@nc/a-b", "1.0.0");
+    seed_legacy(&pool, "@nc-a/b", None).await;
+    seed_legacy(&pool, "@nc/a-b", None).await;
+    common::store_raw_blob("nc-a-b-1.0.0.zorb", &winner).await;
+    // Two names sharing one old key: only the last one published still has its archive
+    seed_legacy(&pool, "@left-pad/util", Some(&sha256_hex(b"overwritten"))).await;
+    seed_legacy(&pool, "@left/pad-util", Some(&sha256_hex(b"survivor"))).await;
+    common::store_raw_blob("left-pad-util-1.0.0.zorb", b"survivor").await;
+    seed_legacy(&pool, "@mig/lost", Some(&sha256_hex(b"lost"))).await;
+
+    let report = migrate_legacy_blobs(&pool, &storage).await.expect("migrate");
+    assert_eq!(report, LegacyBlobReport { moved: 4, missing: 1, mismatched: 2 });
+
+    let mut expected = vec![
+        blob_key(&sha256_hex(b"plain")),
+        blob_key(&sha256_hex(&unchecked)),
+        blob_key(&sha256_hex(b"survivor")),
+        blob_key(&sha256_hex(&winner)),
+    ];
+    expected.sort();
+    assert_eq!(common::stored_keys().await, expected, "Old keys are removed once copied");
+
+    let checksum: Option<String> = sqlx::query_scalar("SELECT checksum FROM zorbs WHERE name = 'mig-unchecked'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(checksum, Some(sha256_hex(&unchecked)), "Missing checksums are filled in");
+    let checksum: Option<String> = sqlx::query_scalar("SELECT checksum FROM zorbs WHERE name = '@nc-a/b'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(checksum, None, "Another package's archive is not adopted");
+
+    let queued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM legacy_blobs").fetch_one(&pool).await.unwrap();
+    assert_eq!(queued, 0);
+    let again = migrate_legacy_blobs(&pool, &storage).await.expect("second run");
+    assert_eq!(again.total(), 0, "Nothing is left to migrate");
+
+    for (uri, status) in [
+        ("/@mig/plain/1.0.0/download", StatusCode::OK),
+        ("/mig-unchecked/1.0.0/download", StatusCode::OK),
+        ("/@left/pad-util/1.0.0/download", StatusCode::OK),
+        ("/@left-pad/util/1.0.0/download", StatusCode::NOT_FOUND),
+        ("/@nc/a-b/1.0.0/download", StatusCode::OK),
+        ("/@nc-a/b/1.0.0/download", StatusCode::NOT_FOUND),
+    ] {
+        let response = common::build_test_app_from_pool(pool.clone())
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_interrupted_migration_resumes() {
+    let pool = common::setup_database().await;
+    let storage = common::test_storage();
+
+    // The blob was copied but the run stopped before the version left the queue
+    seed_legacy(&pool, "@mig/resumed", Some(&sha256_hex(b"resumed"))).await;
+    common::store_raw_blob(&blob_key(&sha256_hex(b"resumed")), b"resumed").await;
+
+    let report = migrate_legacy_blobs(&pool, &storage).await.expect("migrate");
+    assert_eq!(report, LegacyBlobReport { moved: 1, missing: 0, mismatched: 0 });
+}
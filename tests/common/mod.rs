@@ -1,10 +1,18 @@
 // tests/common/mod.rs — shared test helpers for Zenith registry integration tests
+#![allow(dead_code)]
 
 use sqlx::{PgPool, Connection};
 use sqlx::postgres::{PgConnection, PgPoolOptions};
+use std::sync::{Arc, LazyLock};
+use zorbs::storage::{MemoryStorage, Storage, StorageBackend};
 
 const TEST_DB: &str = "zorbs_test";
 
+/// Blob storage shared by every app a test builds, so blobs outlive a single
+/// request; emptied by [`setup_database`] along with the tables.
+static STORAGE: LazyLock<Arc<StorageBackend>> =
+    LazyLock::new(|| Arc::new(StorageBackend::Memory(MemoryStorage::new())));
+
 /// Each call sets up a fresh test database with migrations, and returns a pool.
 /// The DB is created only once (idempotent check), but each test gets a clean pool
 /// to avoid connection contention from shared state.
@@ -27,7 +35,7 @@ pub async fn setup_database() -> PgPool {
         let sql = format!("CREATE DATABASE {}", TEST_DB);
         sqlx::query(&sql).execute(&mut admin_conn).await.unwrap();
     }
-    admin_conn.close().await;
+    let _ = admin_conn.close().await;
 
     // Connect with a fresh pool for this test
     let test_url = test_database_url();
@@ -40,12 +48,20 @@ pub async fn setup_database() -> PgPool {
     // Run migrations (idempotent) and clean tables
     zorbs::db::run_migrations(&pool).await;
     clean_tables(&pool).await;
+    clean_storage().await;
     pool
 }
 
+/// Blobs are immutable once stored, so a fresh database needs fresh storage too.
+async fn clean_storage() {
+    for key in STORAGE.list("").await.expect("list blobs") {
+        STORAGE.delete(&key).await.expect("delete blob");
+    }
+}
+
 async fn clean_tables(pool: &PgPool) {
     let mut conn = pool.acquire().await.expect("acquire for cleanup");
-    for table in &["webauthn_credentials", "sessions", "zorbs", "users"] {
+    for table in &["webhook_deliveries", "webhooks", "version_files", "legacy_blobs", "version_downloads", "zorb_owners", "api_tokens", "webauthn_credentials", "sessions", "zorbs", "users"] {
         let _ = sqlx::query(&format!("DELETE FROM {}", table))
             .execute(&mut *conn)
             .await;
@@ -54,6 +70,11 @@ async fn clean_tables(pool: &PgPool) {
 
 /// Build a test app from a pool.
 pub fn build_test_app_from_pool(pool: PgPool) -> axum::Router {
+    zorbs::build_app(build_test_state(pool))
+}
+
+/// The state behind a test app, for tests that need to reach into it (e.g. to flush download counts).
+pub fn build_test_state(pool: PgPool) -> std::sync::Arc<zorbs::state::AppState> {
     let backend = zorbs::models::user::UserBackend::new(pool.clone());
 
     let rp_origin = url::Url::parse("http://localhost:9999")
@@ -65,13 +86,114 @@ pub fn build_test_app_from_pool(pool: PgPool) -> axum::Router {
             .expect("webauthn build")
     );
 
-    let app_state = std::sync::Arc::new(zorbs::state::AppState {
+    std::sync::Arc::new(zorbs::state::AppState {
         db: pool,
         backend,
         webauthn,
-    });
+        storage: STORAGE.clone(),
+        downloads: std::sync::Arc::new(zorbs::downloads::DownloadCounter::new()),
+        source_cache: std::sync::Arc::new(zorbs::source_cache::SourceCache::new(1024 * 1024)),
+    })
+}
+
+/// A test app with an extra `/test/login/{username}` route that signs in a
+/// user created by [`create_test_token_with_scopes`], for session-only routes.
+/// Clones of the returned router share one session store.
+pub fn build_test_app_with_login(pool: PgPool) -> axum::Router {
+    use axum::routing::get;
+    use tower_sessions::{MemoryStore, SessionManagerLayer};
+
+    let state = build_test_state(pool);
+    let session_layer = SessionManagerLayer::new(MemoryStore::default()).with_secure(false);
+    let auth_layer = axum_login::AuthManagerLayerBuilder::new(state.backend.clone(), session_layer).build();
+
+    axum::Router::new()
+        .merge(zorbs::routes::routes())
+        .route("/test/login/{username}", get(login_as))
+        .layer(auth_layer)
+        .with_state(state)
+}
+
+async fn login_as(
+    mut auth_session: axum_login::AuthSession<zorbs::models::user::UserBackend>,
+    axum::extract::State(state): axum::extract::State<Arc<zorbs::state::AppState>>,
+    axum::extract::Path(username): axum::extract::Path<String>,
+) -> axum::http::StatusCode {
+    let user: zorbs::models::User = sqlx::query_as("SELECT * FROM users WHERE provider = 'github' AND provider_id = $1")
+        .bind(&username)
+        .fetch_one(&state.db)
+        .await
+        .expect("test user exists");
+    auth_session.login(&user).await.expect("log in test user");
+    axum::http::StatusCode::NO_CONTENT
+}
+
+/// Sign `username` in on an app from [`build_test_app_with_login`]; returns the
+/// `Cookie` header value for later requests.
+pub async fn session_cookie(app: &axum::Router, username: &str) -> String {
+    use tower::ServiceExt;
 
-    zorbs::build_app(app_state)
+    let response = app
+        .clone()
+        .oneshot(
+            axum::http::Request::builder()
+                .uri(format!("/test/login/{}", username))
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), axum::http::StatusCode::NO_CONTENT);
+    let cookie = response.headers()["set-cookie"].to_str().unwrap();
+    cookie.split(';').next().unwrap().to_string()
+}
+
+/// Create a test user holding an API token with every scope; returns the plaintext token.
+pub async fn create_test_token(pool: &PgPool) -> String {
+    create_test_token_with_scopes(pool, "tester", &["publish-new", "publish-update", "yank"]).await
+}
+
+/// Create (or reuse) the user `username` and give them a fresh API token with `scopes`.
+pub async fn create_test_token_with_scopes(pool: &PgPool, username: &str, scopes: &[&str]) -> String {
+    let user = zorbs::db::find_or_create_user(pool, "github", username, username, None, None)
+        .await
+        .expect("create test user");
+    let token = zorbs::models::token::generate_token();
+    let scopes: Vec<String> = scopes.iter().map(|s| s.to_string()).collect();
+    zorbs::db::queries::create_api_token(
+        pool,
+        user.id,
+        "test token",
+        &zorbs::models::token::hash_token(&token),
+        &scopes,
+        None,
+    )
+    .await
+    .expect("create test token");
+    token
+}
+
+/// Id of a user created by [`create_test_token_with_scopes`].
+pub async fn test_user_id(pool: &PgPool, username: &str) -> uuid::Uuid {
+    sqlx::query_scalar("SELECT id FROM users WHERE provider = 'github' AND provider_id = $1")
+        .bind(username)
+        .fetch_one(pool)
+        .await
+        .expect("test user exists")
+}
+
+/// Insert a bare version row, so publishes that depend on `name` can find it.
+pub async fn create_test_package(pool: &PgPool, name: &str, version: &str) {
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, dependencies, created_at, updated_at)
+         VALUES ($1, $2, $3, '{}'::jsonb, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(name)
+    .bind(version)
+    .execute(pool)
+    .await
+    .expect("insert test package");
 }
 
 // ─── Helpers ────────────────────────────────────────────────────
@@ -88,27 +210,34 @@ fn test_database_url() -> String {
     format!("{}/{}", &base[..slash], TEST_DB)
 }
 
-/// Build a single-entry tar.gz archive.
-fn build_tar_entry(path: &str, content: &[u8]) -> Vec<u8> {
+/// Build a tar.gz archive of `(path, mode, content)` regular files.
+fn build_tar(entries: &[(&str, u32, &[u8])]) -> Vec<u8> {
     let mut buf = Vec::new();
     {
         let gz = flate2::write::GzEncoder::new(&mut buf, flate2::Compression::fast());
         let mut tar = tar::Builder::new(gz);
-        let mut hdr = tar::Header::new_ustar();
-        hdr.set_entry_type(tar::EntryType::Regular);
-        hdr.set_mode(0o644);
-        hdr.set_mtime(0);
-        hdr.set_uid(0);
-        hdr.set_gid(0);
-        hdr.set_path(path).expect("set tar path");
-        hdr.set_size(content.len() as u64);
-        hdr.set_cksum();
-        tar.append(&hdr, content).expect("append tar entry");
+        for (path, mode, content) in entries {
+            let mut hdr = tar::Header::new_ustar();
+            hdr.set_entry_type(tar::EntryType::Regular);
+            hdr.set_mode(*mode);
+            hdr.set_mtime(0);
+            hdr.set_uid(0);
+            hdr.set_gid(0);
+            hdr.set_path(path).expect("set tar path");
+            hdr.set_size(content.len() as u64);
+            hdr.set_cksum();
+            tar.append(&hdr, *content).expect("append tar entry");
+        }
         tar.finish().expect("finish tar");
     }
     buf
 }
 
+/// Build a single-entry tar.gz archive.
+fn build_tar_entry(path: &str, content: &[u8]) -> Vec<u8> {
+    build_tar(&[(path, 0o644, content)])
+}
+
 /// Create a test tarball with a zorb.toml.
 pub fn create_test_tarball(name: &str, version: &str) -> Vec<u8> {
     let toml = format!(
@@ -141,3 +270,85 @@ license = "MIT"
     );
     build_tar_entry("zorb.toml", toml.as_bytes())
 }
+
+/// Create a test tarball with a zorb.toml followed by `(path, mode, content)` files.
+pub fn create_test_tarball_with_files(name: &str, version: &str, files: &[(&str, u32, &[u8])]) -> Vec<u8> {
+    let toml = format!("[package]\nname = \"{}\"\nversion = \"{}\"\nlicense = \"MIT\"\n", name, version);
+    let mut entries = vec![("zorb.toml", 0o644, toml.as_bytes())];
+    entries.extend_from_slice(files);
+    build_tar(&entries)
+}
+
+/// Create a test tarball whose zorb.toml is exactly `toml`.
+pub fn create_test_tarball_with_manifest(toml: &str) -> Vec<u8> {
+    build_tar_entry("zorb.toml", toml.as_bytes())
+}
+
+/// Store `data` as the archive of `name@version`, recording its checksum on the
+/// version's row (if seeded), and return the checksum. Identical data shares one blob.
+pub async fn store_test_blob(pool: &PgPool, name: &str, version: &str, data: &[u8]) -> String {
+    let checksum = zorbs::utils::sha256_hex(data);
+    match STORAGE.store(&zorbs::utils::blob_key(&checksum), data).await {
+        Ok(()) | Err(zorbs::storage::StorageError::AlreadyExists(_)) => {}
+        Err(e) => panic!("store test blob: {}", e),
+    }
+    sqlx::query("UPDATE zorbs SET checksum = $3 WHERE name = $1 AND version = $2")
+        .bind(name)
+        .bind(version)
+        .bind(&checksum)
+        .execute(pool)
+        .await
+        .expect("record test blob checksum");
+    checksum
+}
+
+/// Put `data` in test storage at exactly `key`, e.g. a pre-content-addressing key.
+pub async fn store_raw_blob(key: &str, data: &[u8]) {
+    STORAGE.store(key, data).await.expect("store raw blob");
+}
+
+/// The blob test storage holds at `key`, if any.
+pub async fn stored_blob(key: &str) -> Option<Vec<u8>> {
+    STORAGE.get(key).await.expect("read test blob")
+}
+
+/// Every key in test storage.
+pub async fn stored_keys() -> Vec<String> {
+    STORAGE.list("").await.expect("list test blobs")
+}
+
+/// The storage backend shared by test apps.
+pub fn test_storage() -> Arc<StorageBackend> {
+    STORAGE.clone()
+}
+
+/// Remove the blob at `key` from test storage.
+pub async fn delete_test_blob(key: &str) {
+    STORAGE.delete(key).await.expect("delete test blob");
+}
+
+/// Build a `multipart/form-data` body from `(name, filename, content_type, data)`
+/// fields; empty `filename`/`content_type` are left out.
+pub fn build_multipart_body(boundary: &str, fields: &[(&str, &str, &str, &[u8])]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (name, filename, content_type, data) in fields {
+        let mut header = String::new();
+        header += &format!("--{}\r\n", boundary);
+        header += &format!("Content-Disposition: form-data; name=\"{}\"", name);
+        if !filename.is_empty() {
+            header += &format!("; filename=\"{}\"", filename);
+        }
+        header += "\r\n";
+        if !content_type.is_empty() {
+            header += &format!("Content-Type: {}\r\n", content_type);
+        }
+        header += "\r\n";
+        body.extend_from_slice(header.as_bytes());
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\r\n");
+    }
+    // Closing boundary
+    let footer = format!("--{}--\r\n", boundary);
+    body.extend_from_slice(footer.as_bytes());
+    body
+}
@@ -0,0 +1,66 @@
+// Integration tests: The Storage trait on the local filesystem and in-memory backends
+
+use tokio::io::AsyncReadExt;
+use zorbs::storage::{LocalStorage, MemoryStorage, Storage, StorageBackend, StorageError};
+
+/// Every backend must behave identically for the same sequence of operations.
+async fn exercise(storage: &StorageBackend) {
+    assert!(storage.list("").await.unwrap().is_empty());
+    assert!(!storage.exists("crypto-bsv58-0.2.0.zorb").await.unwrap());
+    assert_eq!(storage.get("crypto-bsv58-0.2.0.zorb").await.unwrap(), None);
+    assert_eq!(storage.metadata("crypto-bsv58-0.2.0.zorb").await.unwrap(), None);
+    assert!(storage.get_stream("crypto-bsv58-0.2.0.zorb", 0).await.unwrap().is_none());
+
+    storage.store("crypto-bsv58-0.2.0.zorb", b"0123456789").await.expect("store");
+    storage.store("crypto-bsv58-0.3.0.zorb", b"newer").await.expect("store");
+    storage.store("nested/dir/blob.zorb", b"deep").await.expect("store nested");
+    assert!(matches!(
+        storage.store("crypto-bsv58-0.2.0.zorb", b"overwrite").await,
+        Err(StorageError::AlreadyExists(_))
+    ));
+
+    assert!(storage.exists("crypto-bsv58-0.2.0.zorb").await.unwrap());
+    assert_eq!(storage.get("crypto-bsv58-0.2.0.zorb").await.unwrap().as_deref(), Some(&b"0123456789"[..]));
+    let meta = storage.metadata("crypto-bsv58-0.2.0.zorb").await.unwrap().expect("metadata");
+    assert_eq!(meta.len, 10);
+    assert!(meta.last_modified.is_some());
+
+    let mut stream = storage.get_stream("crypto-bsv58-0.2.0.zorb", 4).await.unwrap().expect("stream");
+    let mut rest = Vec::new();
+    stream.reader.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(stream.len, 10, "Length of the whole object");
+    assert_eq!(rest, b"456789");
+
+    assert_eq!(
+        storage.list("").await.unwrap(),
+        ["crypto-bsv58-0.2.0.zorb", "crypto-bsv58-0.3.0.zorb", "nested/dir/blob.zorb"]
+    );
+    assert_eq!(storage.list("crypto-bsv58-0.2").await.unwrap(), ["crypto-bsv58-0.2.0.zorb"]);
+    assert_eq!(storage.list("nested/d").await.unwrap(), ["nested/dir/blob.zorb"]);
+
+    storage.delete("crypto-bsv58-0.2.0.zorb").await.expect("delete");
+    storage.delete("crypto-bsv58-0.2.0.zorb").await.expect("Deleting a missing key succeeds");
+    assert!(!storage.exists("crypto-bsv58-0.2.0.zorb").await.unwrap());
+    assert_eq!(storage.list("crypto").await.unwrap(), ["crypto-bsv58-0.3.0.zorb"]);
+}
+
+#[tokio::test]
+async fn test_memory_storage() {
+    let storage = StorageBackend::Memory(MemoryStorage::new());
+    exercise(&storage).await;
+    assert_eq!(storage.backend_name(), "memory");
+}
+
+#[tokio::test]
+async fn test_local_storage() {
+    let dir = std::env::temp_dir().join(format!("zorbs-storage-{}", uuid::Uuid::new_v4()));
+    let storage = StorageBackend::Local(LocalStorage::new(dir.to_str().unwrap()));
+    exercise(&storage).await;
+    assert_eq!(storage.download_url("crypto-bsv58-0.3.0.zorb"), "/uploads/crypto-bsv58-0.3.0.zorb");
+
+    // A write that never finished leaves only its temporary file, which is not an object
+    std::fs::write(dir.join("nested/dir/.blob2.zorb.1234.tmp"), b"de").unwrap();
+    assert_eq!(storage.list("nested").await.unwrap(), ["nested/dir/blob.zorb"]);
+    assert_eq!(std::fs::read_dir(dir.join("nested/dir")).unwrap().count(), 2, "Completed writes leave no temporary file");
+    let _ = std::fs::remove_dir_all(dir);
+}
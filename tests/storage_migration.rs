@@ -0,0 +1,120 @@
+// Integration tests: Copying every referenced blob between storage backends
+
+mod common;
+
+use zorbs::blobs::{copy_blobs, CopyReport};
+use zorbs::storage::{from_spec, MemoryStorage, Storage, StorageBackend};
+use zorbs::utils::{blob_key, sha256_hex};
+
+/// Seed `name@1.0.0` whose archive is `data`, and return its checksum.
+async fn seed(pool: &sqlx::PgPool, name: &str, data: &[u8]) -> String {
+    let checksum = sha256_hex(data);
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, checksum, created_at, updated_at)
+         VALUES ($1, $2, '1.0.0', 'Copied', 'MIT', NULL, '{}'::jsonb, $3, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(name)
+    .bind(&checksum)
+    .execute(pool)
+    .await
+    .expect("seed version");
+    checksum
+}
+
+fn memory() -> StorageBackend {
+    StorageBackend::Memory(MemoryStorage::new())
+}
+
+#[tokio::test]
+async fn test_copy_verifies_and_resumes() {
+    let pool = common::setup_database().await;
+    let (source, target) = (memory(), memory());
+
+    let good = seed(&pool, "@copy/good", b"good archive").await;
+    source.store(&blob_key(&good), b"good archive").await.unwrap();
+    // Identical archives share one blob, so this is copied once for both versions
+    seed(&pool, "@copy/twin", b"good archive").await;
+    let missing = seed(&pool, "@copy/missing", b"never stored").await;
+    let corrupt = seed(&pool, "@copy/corrupt", b"original").await;
+    source.store(&blob_key(&corrupt), b"bit rot").await.unwrap();
+    let resumed = seed(&pool, "@copy/resumed", b"copied last time").await;
+    source.store(&blob_key(&resumed), b"copied last time").await.unwrap();
+    target.store(&blob_key(&resumed), b"copied last time").await.unwrap();
+    let cut_short = seed(&pool, "@copy/cut-short", b"complete archive").await;
+    source.store(&blob_key(&cut_short), b"complete archive").await.unwrap();
+    target.store(&blob_key(&cut_short), b"compl").await.unwrap();
+
+    let dry = copy_blobs(&pool, &source, &target, true, false).await.expect("dry run");
+    assert_eq!((dry.copied, dry.already_present), (2, 1));
+    assert_eq!(target.list("").await.unwrap().len(), 2, "A dry run writes nothing");
+    assert_eq!(target.get(&blob_key(&cut_short)).await.unwrap().unwrap(), b"compl");
+
+    let report = copy_blobs(&pool, &source, &target, false, false).await.expect("copy");
+    assert_eq!((report.copied, report.already_present, report.unaddressed), (2, 1, 0));
+    assert_eq!(report.missing.len(), 1);
+    assert_eq!(report.missing[0].checksum, missing);
+    assert_eq!(report.missing[0].versions, ["@copy/missing@1.0.0"]);
+    assert_eq!(report.corrupt.len(), 1);
+    assert_eq!(report.corrupt[0].checksum, corrupt);
+    assert!(report.failed.is_empty());
+    assert!(!report.is_clean());
+
+    assert_eq!(target.get(&blob_key(&good)).await.unwrap().unwrap(), b"good archive");
+    assert_eq!(target.get(&blob_key(&cut_short)).await.unwrap().unwrap(), b"complete archive", "Partial copies are replaced");
+    assert!(!target.exists(&blob_key(&corrupt)).await.unwrap(), "Corrupt blobs are not propagated");
+
+    // A rerun only skips what is already there
+    let again = copy_blobs(&pool, &source, &target, false, false).await.expect("rerun");
+    assert_eq!((again.copied, again.already_present), (0, 3));
+}
+
+#[tokio::test]
+async fn test_verify_replaces_damaged_copies() {
+    let pool = common::setup_database().await;
+    let (source, target) = (memory(), memory());
+    let damaged = seed(&pool, "@verify/damaged", b"intact archive").await;
+    source.store(&blob_key(&damaged), b"intact archive").await.unwrap();
+    target.store(&blob_key(&damaged), b"intact archivX").await.unwrap();
+    let intact = seed(&pool, "@verify/intact", b"another archive").await;
+    source.store(&blob_key(&intact), b"another archive").await.unwrap();
+    target.store(&blob_key(&intact), b"another archive").await.unwrap();
+
+    let unverified = copy_blobs(&pool, &source, &target, false, false).await.expect("copy");
+    assert_eq!((unverified.copied, unverified.already_present), (0, 2), "Same-length copies are trusted without --verify");
+    assert_eq!(target.get(&blob_key(&damaged)).await.unwrap().unwrap(), b"intact archivX");
+
+    let dry = copy_blobs(&pool, &source, &target, true, true).await.expect("dry run");
+    assert_eq!((dry.copied, dry.already_present), (1, 1));
+    assert_eq!(target.get(&blob_key(&damaged)).await.unwrap().unwrap(), b"intact archivX", "A dry run writes nothing");
+
+    let verified = copy_blobs(&pool, &source, &target, false, true).await.expect("verify");
+    assert_eq!((verified.copied, verified.already_present), (1, 1));
+    assert!(verified.is_clean());
+    assert_eq!(target.get(&blob_key(&damaged)).await.unwrap().unwrap(), b"intact archive");
+}
+
+#[tokio::test]
+async fn test_versions_without_addressed_blobs_are_counted() {
+    let pool = common::setup_database().await;
+    let (source, target) = (memory(), memory());
+    sqlx::query(
+        "INSERT INTO zorbs (id, name, version, description, license, repository, dependencies, created_at, updated_at)
+         VALUES ($1, 'unchecked', '1.0.0', 'Old', 'MIT', NULL, '{}'::jsonb, NOW(), NOW())"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let report = copy_blobs(&pool, &source, &target, false, false).await.expect("copy");
+    assert_eq!(report, CopyReport { unaddressed: 1, ..CopyReport::default() });
+    assert!(report.is_clean());
+}
+
+#[test]
+fn test_storage_specs() {
+    assert_eq!(from_spec("local:/var/lib/zorbs").unwrap().backend_name(), "local");
+    assert!(from_spec("local:").is_err());
+    assert!(from_spec("ftp:somewhere").is_err());
+}
@@ -0,0 +1,76 @@
+// Integration tests: Webhooks may only point at public addresses unless allowlisted
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use serde_json::{json, Value};
+use std::net::IpAddr;
+use zorbs::webhooks;
+
+async fn register(pool: &sqlx::PgPool, token: &str, url: &str) -> (StatusCode, Value) {
+    let app = common::build_test_app_from_pool(pool.clone());
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/zorbs/guarded/webhooks")
+        .header("authorization", format!("Bearer {}", token))
+        .header("content-type", "application/json")
+        .body(Body::from(json!({"url": url}).to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[test]
+fn test_public_addresses() {
+    for ip in ["93.184.215.14", "1.1.1.1", "2606:4700:4700::1111"] {
+        assert!(webhooks::is_public(ip.parse::<IpAddr>().unwrap()), "{}", ip);
+    }
+    for ip in [
+        "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "0.0.0.0",
+        "100.64.0.1", "255.255.255.255", "::1", "::", "fe80::1", "fd00::1", "::ffff:127.0.0.1",
+    ] {
+        assert!(!webhooks::is_public(ip.parse::<IpAddr>().unwrap()), "{}", ip);
+    }
+}
+
+#[tokio::test]
+async fn test_internal_destinations_need_the_allowlist() {
+    std::env::remove_var("WEBHOOK_ALLOWED_HOSTS");
+    let pool = common::setup_database().await;
+    let token = common::create_test_token(&pool).await;
+    common::create_test_package(&pool, "guarded", "1.0.0").await;
+    sqlx::query("INSERT INTO zorb_owners (package_name, user_id) VALUES ('guarded', $1)")
+        .bind(common::test_user_id(&pool, "tester").await)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    for url in [
+        "http://127.0.0.1:8080/hook",
+        "http://localhost/hook",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://10.0.0.5/hook",
+        "http://[::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+    ] {
+        let (status, body) = register(&pool, &token, url).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", url);
+        assert!(body["error"].as_str().unwrap().contains("not allowed"), "{}", body);
+    }
+    let (status, _) = register(&pool, &token, "http://93.184.215.14/hook").await;
+    assert_eq!(status, StatusCode::CREATED, "Public addresses need no allowlist");
+
+    std::env::set_var("WEBHOOK_ALLOWED_HOSTS", "ci.internal, 10.0.0.5");
+    let (status, _) = register(&pool, &token, "http://10.0.0.5/hook").await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = register(&pool, &token, "http://127.0.0.1/hook").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "Only the listed hosts are allowed");
+    std::env::remove_var("WEBHOOK_ALLOWED_HOSTS");
+}